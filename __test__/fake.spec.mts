@@ -0,0 +1,951 @@
+import { it, expect, beforeEach, afterAll, describe } from 'vitest';
+import {
+  useBackend,
+  advanceFakeClock,
+  listMediaSessions,
+  getMediaInfo,
+  getThumbnail,
+  play,
+  pause,
+  next,
+  seek,
+  setPosition,
+  SeekResult,
+  getShuffle,
+  setShuffle,
+  getRepeatMode,
+  setRepeatMode,
+  getPlaybackRate,
+  setPlaybackRate,
+  RepeatMode,
+  previous,
+  getSystemVolume,
+  setSystemVolume,
+  getSystemMute,
+  setSystemMute,
+  getInputVolume,
+  setInputVolume,
+  getInputMute,
+  setInputMute,
+  getChannelVolumes,
+  setChannelVolumes,
+  getBalance,
+  setBalance,
+  ChannelPosition,
+  getVolumeRange,
+  getVolumeDb,
+  setVolumeDb,
+  volumeStep,
+  StepDirection,
+  setVolumeCurve,
+  getVolumeCurve,
+  VolumeCurve,
+  listAudioDevices,
+  setDefaultDevice,
+  DeviceDirection,
+  DeviceState,
+  listAudioSessions,
+  getSessionVolume,
+  setSessionVolume,
+  getSessionMute,
+  setSessionMute,
+  getSessionChannelVolumes,
+  setSessionChannelVolumes,
+  getSessionBalance,
+  setSessionBalance,
+  getSessionVolumeRange,
+  getSessionVolumeDb,
+  setSessionVolumeDb,
+  sessionVolumeStep,
+  subscribeToEvents,
+  EventType,
+  TrackEndReason,
+  SessionKind,
+  getCapabilities,
+  setErrorMode,
+  ErrorMode,
+  ErrorStatus,
+  type MediaInfo,
+  type MediaCapabilities,
+  type EventData,
+} from '../index.js';
+
+const track = (title: string, playbackStatus = 'playing'): MediaInfo => ({
+  title,
+  artist: 'Artist',
+  album: 'Album',
+  albumArtist: 'Album Artist',
+  playbackStatus,
+  hasThumbnail: false,
+});
+
+const allControls: MediaCapabilities = {
+  play: true,
+  pause: true,
+  stop: true,
+  next: true,
+  previous: true,
+  seek: true,
+  shuffle: true,
+  repeat: true,
+  rate: true,
+  volume: false,
+};
+
+describe('fake backend', () => {
+  beforeEach(() => {
+    useBackend('fake', {
+      realtime: false,
+      sessions: [
+        {
+          id: 'music',
+          mediaInfo: track('Intro'),
+          thumbnail: [1, 2, 3],
+          playlist: [track('Intro'), track('Outro')],
+        },
+        {
+          id: 'video',
+          sourceAppId: 'com.example.Video',
+          mediaInfo: track('Clip', 'paused'),
+        },
+      ],
+      volume: 0.25,
+      muted: false,
+      audioSessions: [
+        { id: 'firefox', name: 'Firefox', pid: 4242, volume: 0.5 },
+        { id: 'mpv', name: 'mpv', muted: true },
+      ],
+      steps: [
+        { atMs: 1000, mediaInfo: track('Interlude') },
+        { atMs: 2000, playbackStatus: 'paused' },
+        { atMs: 3000, currentSessionId: 'video', volume: 0.75, muted: true },
+      ],
+    });
+  });
+
+  afterAll(() => {
+    useBackend('system');
+  });
+
+  it('should report the initial scenario state', async () => {
+    expect(getMediaInfo()).toEqual({
+      ...track('Intro'),
+      sourceAppId: 'music',
+      capabilities: allControls,
+    });
+    expect(getThumbnail()).toEqual([1, 2, 3]);
+    expect(await getSystemVolume()).toBe(0.25);
+    expect(await getSystemMute()).toBe(false);
+  });
+
+  it('should apply steps as the clock advances', async () => {
+    advanceFakeClock(999);
+    expect(getMediaInfo()?.title).toBe('Intro');
+
+    advanceFakeClock(1);
+    expect(getMediaInfo()?.title).toBe('Interlude');
+
+    advanceFakeClock(1000);
+    expect(getMediaInfo()?.playbackStatus).toBe('paused');
+
+    advanceFakeClock(1000);
+    expect(getMediaInfo()?.title).toBe('Clip');
+    expect(await getSystemVolume()).toBe(0.75);
+    expect(await getSystemMute()).toBe(true);
+  });
+
+  it('should reject clock advances that are not finite', async () => {
+    expect(() => advanceFakeClock(Infinity)).toThrow();
+    expect(() => advanceFakeClock(NaN)).toThrow();
+    expect(getMediaInfo()?.title).toBe('Intro');
+
+    useBackend('fake', {
+      sessions: [{ id: 'music', mediaInfo: track('Intro') }],
+      steps: [{ atMs: Number.MAX_VALUE, mediaInfo: track('Never') }],
+    });
+    const subscription = subscribeToEvents(() => {});
+    expect(() => advanceFakeClock(Number.MAX_VALUE)).not.toThrow();
+    expect(getMediaInfo()?.title).toBe('Intro');
+    subscription.stop();
+  });
+
+  it('should handle transport commands in memory', async () => {
+    expect(await next()).toBe(true);
+    expect(getMediaInfo()?.title).toBe('Outro');
+
+    expect(await previous()).toBe(true);
+    expect(getMediaInfo()?.title).toBe('Intro');
+
+    advanceFakeClock(3000);
+    expect(await play()).toBe(true);
+    expect(getMediaInfo()).toMatchObject(track('Clip', 'playing'));
+  });
+
+  it('should list every session', () => {
+    expect(listMediaSessions()).toEqual([
+      { id: 'music', sourceAppId: 'music', isCurrent: true },
+      { id: 'video', sourceAppId: 'com.example.Video', isCurrent: false },
+    ]);
+
+    advanceFakeClock(3000);
+    expect(listMediaSessions().find((session) => session.isCurrent)?.id).toBe('video');
+  });
+
+  it('should target sessions other than the current one', async () => {
+    expect(getMediaInfo('video')).toEqual({
+      ...track('Clip', 'paused'),
+      sourceAppId: 'com.example.Video',
+      capabilities: allControls,
+    });
+    expect(getThumbnail('video')).toBeNull();
+
+    expect(await play('video')).toBe(true);
+    expect(await pause('music')).toBe(true);
+    expect(getMediaInfo('video')?.playbackStatus).toBe('playing');
+    expect(getMediaInfo()?.playbackStatus).toBe('paused');
+
+    expect(() => getMediaInfo('missing')).toThrow(expect.objectContaining({ code: ErrorStatus.NoSession }));
+    await expect(play('missing')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+  });
+
+  it('should fall back to the boolean API in the compatibility mode', async () => {
+    setErrorMode(ErrorMode.Compat);
+    try {
+      expect(getMediaInfo('missing')).toBeNull();
+      expect(await play('missing')).toBe(false);
+      expect(await setPosition(1_000, 'missing')).toBe(SeekResult.NoSession);
+      expect(await getSessionVolume('nope')).toBeNull();
+      expect(await setSystemVolume(2)).toBe(false);
+    } finally {
+      setErrorMode(ErrorMode.Throw);
+    }
+  });
+
+  it('should hold the volume in memory', async () => {
+    expect(await setSystemVolume(0.6)).toBe(true);
+    expect(await getSystemVolume()).toBe(0.6);
+  });
+
+  it('should pass timeline and track details through', () => {
+    useBackend('fake', {
+      sessions: [
+        {
+          id: 'video',
+          mediaInfo: {
+            ...track('Episode 1'),
+            subtitle: 'Pilot',
+            trackNumber: 1,
+            trackCount: 10,
+            genres: ['Drama'],
+            playbackType: 'video',
+            durationMs: 60_000,
+            positionMs: 1_500,
+            lastUpdated: 1_700_000_000_000,
+            sourceAppId: 'com.example.Tv',
+          },
+        },
+      ],
+    });
+
+    expect(getMediaInfo()).toMatchObject({
+      subtitle: 'Pilot',
+      trackNumber: 1,
+      trackCount: 10,
+      genres: ['Drama'],
+      playbackType: 'video',
+      durationMs: 60_000,
+      positionMs: 1_500,
+      sourceAppId: 'com.example.Tv',
+    });
+  });
+
+  it('should seek within the duration of the media', async () => {
+    useBackend('fake', {
+      sessions: [
+        { id: 'music', mediaInfo: { ...track('Intro'), durationMs: 60_000, positionMs: 1_000 } },
+        { id: 'radio', mediaInfo: track('Live'), canSeek: false },
+      ],
+    });
+
+    expect(await seek(5_000)).toBe(SeekResult.Ok);
+    expect(getMediaInfo()?.positionMs).toBe(6_000);
+    expect(await seek(-10_000)).toBe(SeekResult.Ok);
+    expect(getMediaInfo()?.positionMs).toBe(0);
+    expect(await setPosition(90_000)).toBe(SeekResult.Ok);
+    expect(getMediaInfo()?.positionMs).toBe(60_000);
+
+    await expect(seek(1_000, 'radio')).rejects.toMatchObject({ code: ErrorStatus.NotSupported });
+    await expect(setPosition(1_000, 'missing')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+  });
+
+  it('should reject seeks to positions that are not finite', async () => {
+    useBackend('fake', {
+      sessions: [{ id: 'music', mediaInfo: { ...track('Intro'), durationMs: 60_000, positionMs: 1_000 } }],
+    });
+
+    await expect(seek(NaN)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    await expect(seek(Infinity)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    await expect(setPosition(NaN)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    await expect(setPosition(-Infinity)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    expect(getMediaInfo()?.positionMs).toBe(1_000);
+  });
+
+  it('should hold shuffle, repeat mode and playback rate in memory', async () => {
+    expect(await getShuffle()).toBeNull();
+
+    expect(await setShuffle(true)).toBe(true);
+    expect(await setRepeatMode(RepeatMode.Track)).toBe(true);
+    expect(await setPlaybackRate(2)).toBe(true);
+    expect(await getShuffle()).toBe(true);
+    expect(await getRepeatMode()).toBe(RepeatMode.Track);
+    expect(await getPlaybackRate()).toBe(2);
+
+    await expect(setPlaybackRate(0)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    await expect(setShuffle(true, 'missing')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+  });
+
+  it('should report shuffle changes to subscribers', async () => {
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents(
+      (event) => events.push(event),
+      () => {},
+    );
+
+    await expect.poll(() => events.length).toBeGreaterThan(0);
+    await setShuffle(true);
+    await expect
+      .poll(() => events.find((event) => event.eventType === EventType.ShuffleChange))
+      .toMatchObject({ mediaInfo: { shuffle: true } });
+    subscription.stop();
+  });
+
+  it('should push changes to subscribers as they happen', async () => {
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents(
+      (event) => events.push(event),
+      () => {},
+    );
+
+    await expect.poll(() => events.length).toBeGreaterThan(0);
+    advanceFakeClock(1000);
+    await setSystemVolume(0.5);
+    await expect
+      .poll(() => events.map((event) => event.mediaInfo?.title ?? event.volume), { timeout: 250 })
+      .toEqual(expect.arrayContaining(['Interlude', 0.5]));
+    subscription.stop();
+  });
+
+  it('should stop delivering events once stopped', async () => {
+    const events: EventData[] = [];
+    let stopped = 0;
+    const subscription = subscribeToEvents(
+      (event) => events.push(event),
+      () => stopped++,
+    );
+
+    await expect.poll(() => events.length).toBeGreaterThan(0);
+    expect(subscription.isActive).toBe(true);
+    subscription.stop();
+    subscription.stop();
+    expect(subscription.isActive).toBe(false);
+    expect(stopped).toBe(1);
+
+    const delivered = events.length;
+    advanceFakeClock(1000);
+    await new Promise((resolve) => setTimeout(resolve, 100));
+    expect(events).toHaveLength(delivered);
+  });
+
+  it('should only report the event types and session asked for', async () => {
+    const volumes: EventData[] = [];
+    const video: EventData[] = [];
+    const volumeOnly = subscribeToEvents((event) => volumes.push(event), {
+      eventTypes: [EventType.VolumeChange],
+      initialSnapshot: false,
+    });
+    const videoOnly = subscribeToEvents((event) => video.push(event), {
+      sourceAppId: 'com.example.Video',
+      eventTypes: [EventType.MediaChange],
+    });
+
+    await expect.poll(() => video.map((event) => event.mediaInfo?.title)).toEqual(['Clip']);
+    advanceFakeClock(1000);
+    await setSystemVolume(0.5);
+    await expect.poll(() => volumes.map((event) => event.volume)).toEqual([0.5]);
+    expect(volumes.every((event) => event.eventType === EventType.VolumeChange)).toBe(true);
+    expect(video.map((event) => event.mediaInfo?.title)).toEqual(['Clip']);
+
+    volumeOnly.stop();
+    videoOnly.stop();
+  });
+
+  it('should keep delivering to other subscribers when one stops', async () => {
+    const first: EventData[] = [];
+    const second: EventData[] = [];
+    const firstSubscription = subscribeToEvents((event) => first.push(event));
+    const secondSubscription = subscribeToEvents((event) => second.push(event));
+
+    await expect.poll(() => first.length && second.length).toBeGreaterThan(0);
+    firstSubscription.stop();
+    const delivered = first.length;
+
+    advanceFakeClock(1000);
+    await expect
+      .poll(() => second.find((event) => event.mediaInfo?.title === 'Interlude'))
+      .toBeDefined();
+    expect(first).toHaveLength(delivered);
+
+    const late: EventData[] = [];
+    const lateSubscription = subscribeToEvents((event) => late.push(event));
+    await expect.poll(() => late.map((event) => event.mediaInfo?.title)).toContain('Interlude');
+
+    secondSubscription.stop();
+    lateSubscription.stop();
+  });
+
+  it('should name the fields that changed, artwork included', async () => {
+    const withArt = (extra: Partial<MediaInfo> = {}) => ({ ...track('Intro'), hasThumbnail: true, ...extra });
+    useBackend('fake', {
+      realtime: false,
+      sessions: [{ id: 'music', mediaInfo: withArt(), thumbnail: [1, 2, 3] }],
+      steps: [
+        { atMs: 1000, mediaInfo: withArt({ albumArtist: 'Someone Else' }) },
+        { atMs: 2000, thumbnail: [4, 5, 6] },
+      ],
+    });
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents((event) => events.push(event), {
+      eventTypes: [EventType.MediaChange],
+    });
+
+    await expect.poll(() => events.length).toBe(1);
+    expect(events[0].changedFields).toBeUndefined();
+    advanceFakeClock(1000);
+    await expect.poll(() => events.map((event) => event.changedFields)).toContainEqual(['albumArtist']);
+    advanceFakeClock(1000);
+    await expect.poll(() => events.map((event) => event.changedFields)).toContainEqual(['thumbnail']);
+    subscription.stop();
+  });
+
+  it('should report the position while playing and seeks as they happen', async () => {
+    useBackend('fake', {
+      realtime: false,
+      sessions: [
+        {
+          id: 'music',
+          mediaInfo: { ...track('Intro'), durationMs: 180_000, positionMs: 10_000, lastUpdated: Date.now() },
+        },
+      ],
+    });
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents((event) => events.push(event), {
+      eventTypes: [EventType.PositionChange, EventType.Seeked],
+      positionIntervalMs: 100,
+    });
+
+    await expect.poll(() => events.filter((event) => event.eventType === EventType.PositionChange).length).toBeGreaterThan(1);
+    const positions = events.map((event) => event.positionMs!);
+    expect(positions[1]).toBeGreaterThan(positions[0]);
+
+    expect(await setPosition(90_000)).toBe(SeekResult.Ok);
+    await expect
+      .poll(() => events.find((event) => event.eventType === EventType.Seeked)?.positionMs)
+      .toBeGreaterThanOrEqual(90_000);
+    subscription.stop();
+  });
+
+  it('should tell tracks played to the end from skipped ones', async () => {
+    const playing = (title: string, positionMs: number): MediaInfo => ({
+      ...track(title),
+      durationMs: 200_000,
+      positionMs,
+      lastUpdated: Date.now(),
+    });
+    useBackend('fake', {
+      sessions: [
+        {
+          id: 'music',
+          mediaInfo: playing('Intro', 190_000),
+          playlist: [playing('Intro', 190_000), playing('Verse', 5_000), playing('Outro', 0)],
+        },
+      ],
+    });
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents((event) => events.push(event), {
+      eventTypes: [EventType.TrackEnded, EventType.TrackNearEnd],
+      nearEndThresholdMs: 15_000,
+    });
+
+    await expect.poll(() => events.map((event) => event.eventType)).toEqual([EventType.TrackNearEnd]);
+    expect(events[0].mediaInfo?.title).toBe('Intro');
+
+    await setPosition(199_500);
+    await next();
+    await expect.poll(() => events.length).toBe(2);
+    expect(events[1]).toMatchObject({
+      eventType: EventType.TrackEnded,
+      endReason: TrackEndReason.Completed,
+      mediaInfo: { title: 'Intro' },
+    });
+
+    await next();
+    await expect.poll(() => events.length).toBe(3);
+    expect(events[2]).toMatchObject({
+      eventType: EventType.TrackEnded,
+      endReason: TrackEndReason.Skipped,
+      mediaInfo: { title: 'Verse' },
+    });
+    subscription.stop();
+  });
+
+  it('should end no track when another session becomes current', async () => {
+    const playing = (title: string): MediaInfo => ({
+      ...track(title),
+      durationMs: 200_000,
+      positionMs: 10_000,
+      lastUpdated: Date.now(),
+    });
+    useBackend('fake', {
+      realtime: false,
+      sessions: [
+        { id: 'music', mediaInfo: playing('Intro') },
+        { id: 'spotify', mediaInfo: playing('Song') },
+      ],
+      steps: [{ atMs: 1000, currentSessionId: 'spotify' }],
+    });
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents((event) => events.push(event), {
+      eventTypes: [EventType.MediaChange, EventType.TrackEnded, EventType.CurrentSessionChanged],
+    });
+
+    await expect.poll(() => events.length).toBe(1);
+    advanceFakeClock(1000);
+    await expect
+      .poll(() => events.map((event) => event.mediaInfo?.title))
+      .toContain('Song');
+    expect(events.some((event) => event.eventType === EventType.CurrentSessionChanged)).toBe(true);
+    expect(events.some((event) => event.eventType === EventType.TrackEnded)).toBe(false);
+    subscription.stop();
+  });
+
+  it('should report sessions showing up, going away and becoming current', async () => {
+    useBackend('fake', {
+      realtime: false,
+      sessions: [{ id: 'music', mediaInfo: track('Intro') }],
+      audioSessions: [{ id: 'player.exe', name: 'player.exe' }],
+      steps: [
+        { atMs: 100, addSessions: [{ id: 'video', sourceAppId: 'browser', mediaInfo: track('Clip') }], currentSessionId: 'video' },
+        { atMs: 200, removeAudioSessions: ['player.exe'] },
+      ],
+    });
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents((event) => events.push(event), {
+      // The snapshot of the media tells when the first read happened
+      eventTypes: [EventType.MediaChange, EventType.SessionAdded, EventType.SessionRemoved, EventType.CurrentSessionChanged],
+    });
+    const sessionEvents = () => events.filter((event) => event.eventType !== EventType.MediaChange);
+    await expect.poll(() => events.length).toBe(1);
+
+    advanceFakeClock(100);
+    await expect.poll(() => sessionEvents().length).toBe(2);
+    expect(sessionEvents()).toMatchObject([
+      { eventType: EventType.SessionAdded, sessionId: 'video', sourceAppId: 'browser', sessionKind: SessionKind.Media },
+      { eventType: EventType.CurrentSessionChanged, sessionId: 'video', sourceAppId: 'browser' },
+    ]);
+
+    advanceFakeClock(100);
+    await expect.poll(() => sessionEvents().length).toBe(3);
+    expect(sessionEvents()[2]).toMatchObject({
+      eventType: EventType.SessionRemoved,
+      sessionId: 'player.exe',
+      sourceAppId: 'player.exe',
+      sessionKind: SessionKind.Audio,
+    });
+    subscription.stop();
+  });
+
+  it('should report capabilities and refuse disabled controls', async () => {
+    const pauseOnly = { ...allControls, play: false, next: false, seek: false };
+    useBackend('fake', {
+      realtime: false,
+      sessions: [{ id: 'music', mediaInfo: track('Intro') }],
+      steps: [{ atMs: 1000, mediaInfo: { ...track('Ad'), capabilities: pauseOnly } }],
+    });
+
+    expect(await getCapabilities()).toEqual(allControls);
+    await expect(getCapabilities('missing')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+
+    advanceFakeClock(1000);
+    expect(await getCapabilities()).toEqual(pauseOnly);
+    await expect(play()).rejects.toMatchObject({ code: ErrorStatus.NotSupported });
+    await expect(next()).rejects.toMatchObject({ code: ErrorStatus.NotSupported });
+    await expect(seek(1000)).rejects.toMatchObject({ code: ErrorStatus.NotSupported });
+    expect(await pause()).toBe(true);
+  });
+
+  it('should report capability changes to subscribers', async () => {
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents(
+      (event) => events.push(event),
+      () => {},
+    );
+
+    await expect.poll(() => events.length).toBeGreaterThan(0);
+    useBackend('fake', {
+      sessions: [{ id: 'music', mediaInfo: track('Intro'), canSeek: false }],
+    });
+    await expect
+      .poll(() => events.find((event) => event.eventType === EventType.CapabilitiesChange))
+      .toMatchObject({ mediaInfo: { capabilities: { ...allControls, seek: false } } });
+    subscription.stop();
+  });
+
+  it('should hold per-application volumes in memory', async () => {
+    expect(await listAudioSessions()).toEqual([
+      { id: 'firefox', name: 'Firefox', pid: 4242 },
+      { id: 'mpv', name: 'mpv' },
+    ]);
+    expect(await getSessionVolume('mpv')).toBe(1);
+    expect(await getSessionMute('mpv')).toBe(true);
+
+    expect(await setSessionVolume('firefox', 0.2)).toBe(true);
+    expect(await getSessionVolume('firefox')).toBe(0.2);
+    expect(await setSessionMute('firefox', true)).toBe(true);
+    expect(await getSessionMute('firefox')).toBe(true);
+    expect(await getSystemVolume()).toBe(0.25);
+  });
+
+  it('should reject unknown audio sessions and invalid levels', async () => {
+    await expect(getSessionVolume('nope')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+    await expect(setSessionVolume('nope', 0.5)).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+    await expect(setSessionVolume('firefox', 1.5)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+  });
+
+  it('should balance an application while keeping its loudness', async () => {
+    expect(await getSessionChannelVolumes('firefox')).toEqual([
+      { position: ChannelPosition.FrontLeft, volume: 0.5 },
+      { position: ChannelPosition.FrontRight, volume: 0.5 },
+    ]);
+
+    expect(await setSessionBalance('firefox', 0.5)).toBe(true);
+    expect((await getSessionChannelVolumes('firefox')).map((channel) => channel.volume)).toEqual([0.25, 0.5]);
+    expect(await getSessionBalance('firefox')).toBeCloseTo(0.5, 9);
+    expect(await getSessionVolume('firefox')).toBe(0.5);
+
+    expect(await setSessionChannelVolumes('mpv', [0.2, 0.1])).toBe(true);
+    expect(await getSessionVolume('mpv')).toBe(0.2);
+    await expect(getSessionBalance('nope')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+    await expect(setSessionChannelVolumes('mpv', [0.2, 0.1, 0.1])).rejects.toMatchObject({
+      code: ErrorStatus.InvalidArgument,
+    });
+  });
+
+  it('should step and set application volumes in dB', async () => {
+    expect(await getSessionVolumeRange('firefox')).toEqual({ minDb: -60, maxDb: 0 });
+    expect(await getSessionVolumeDb('firefox')).toBeCloseTo(60 * Math.log10(0.5), 9);
+
+    expect(await setSessionVolumeDb('firefox', -30)).toBe(true);
+    expect(await getSessionVolume('firefox')).toBeCloseTo(10 ** -0.5, 9);
+    expect(await setSessionVolumeDb('mpv', -Infinity)).toBe(true);
+    expect(await getSessionVolumeDb('mpv')).toBe(-Infinity);
+
+    expect(await sessionVolumeStep('mpv', StepDirection.Up)).toBe(0.05);
+    expect(await sessionVolumeStep('mpv', StepDirection.Down, 0.5)).toBe(0);
+    expect(await getSessionVolume('mpv')).toBe(0);
+    await expect(sessionVolumeStep('nope', StepDirection.Up)).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+  });
+
+  it('should list output and input devices', async () => {
+    expect(await listAudioDevices()).toEqual([
+      {
+        id: 'fake-speakers',
+        name: 'Fake Speakers',
+        direction: DeviceDirection.Output,
+        state: DeviceState.Active,
+        isDefault: true,
+        isDefaultCommunication: true,
+        channels: 2,
+        sampleRate: 48000,
+      },
+      {
+        id: 'fake-microphone',
+        name: 'Fake Microphone',
+        direction: DeviceDirection.Input,
+        state: DeviceState.Active,
+        isDefault: true,
+        isDefaultCommunication: true,
+        channels: 2,
+        sampleRate: 48000,
+      },
+    ]);
+    expect(await listAudioDevices({ direction: DeviceDirection.Input })).toMatchObject([{ id: 'fake-microphone' }]);
+  });
+
+  it('should switch the default device and control devices by id', async () => {
+    useBackend('fake', {
+      devices: [
+        { id: 'speakers', name: 'Speakers', direction: DeviceDirection.Output, volume: 0.4 },
+        { id: 'headphones', name: 'Headphones', direction: DeviceDirection.Output, volume: 0.8, muted: true },
+        { id: 'hdmi', name: 'HDMI', direction: DeviceDirection.Output, state: DeviceState.Unplugged },
+      ],
+    });
+    const defaults = async () =>
+      (await listAudioDevices({ direction: DeviceDirection.Output }))
+        .filter((device) => device.isDefault)
+        .map((device) => device.id);
+
+    expect(await defaults()).toEqual(['speakers']);
+    expect(await getSystemVolume()).toBe(0.4);
+    expect(await getSystemMute('headphones')).toBe(true);
+
+    expect(await setDefaultDevice('headphones')).toBe(true);
+    expect(await defaults()).toEqual(['headphones']);
+    expect(await getSystemVolume()).toBe(0.8);
+
+    expect(await setSystemVolume(0.1, 'speakers')).toBe(true);
+    expect(await setSystemMute(false)).toBe(true);
+    expect(await getSystemVolume('speakers')).toBe(0.1);
+    expect(await getSystemVolume()).toBe(0.8);
+    expect(await getSystemMute('headphones')).toBe(false);
+
+    await expect(setDefaultDevice('missing')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+    await expect(getSystemVolume('missing')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+  });
+
+  it('should control the microphone apart from the output', async () => {
+    useBackend('fake', {
+      devices: [
+        { id: 'speakers', name: 'Speakers', direction: DeviceDirection.Output, volume: 0.4 },
+        { id: 'mic', name: 'Mic', direction: DeviceDirection.Input, volume: 0.6 },
+        { id: 'webcam', name: 'Webcam', direction: DeviceDirection.Input, volume: 0.2, muted: true },
+      ],
+    });
+
+    expect(await getInputVolume()).toBe(0.6);
+    expect(await getInputMute('webcam')).toBe(true);
+    expect(await setInputVolume(0.3)).toBe(true);
+    expect(await setInputMute(true)).toBe(true);
+    expect(await getInputVolume('mic')).toBe(0.3);
+    expect(await getInputMute()).toBe(true);
+    expect(await getSystemVolume()).toBe(0.4);
+    expect(await getSystemMute()).toBe(false);
+
+    await expect(setInputVolume(2)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    useBackend('fake', { devices: [{ id: 'speakers', name: 'Speakers', direction: DeviceDirection.Output }] });
+    await expect(getInputVolume()).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+  });
+
+  it('should report the levels of the default output and input', async () => {
+    useBackend('fake', {
+      realtime: false,
+      devices: [
+        { id: 'speakers', name: 'Speakers', direction: DeviceDirection.Output, volume: 0.4 },
+        { id: 'mic', name: 'Mic', direction: DeviceDirection.Input, volume: 0.6 },
+        { id: 'webcam', name: 'Webcam', direction: DeviceDirection.Input, volume: 0.2 },
+      ],
+    });
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents((event) => events.push(event), {
+      eventTypes: [EventType.VolumeChange],
+    });
+    const levels = () =>
+      events.map(({ deviceId, deviceDirection, volume, changedFields }) => ({
+        deviceId,
+        deviceDirection,
+        volume,
+        changedFields,
+      }));
+
+    await expect.poll(levels).toEqual([
+      { deviceId: 'speakers', deviceDirection: DeviceDirection.Output, volume: 0.4, changedFields: undefined },
+      { deviceId: 'mic', deviceDirection: DeviceDirection.Input, volume: 0.6, changedFields: undefined },
+    ]);
+    events.length = 0;
+    await setInputVolume(0.3);
+    await expect.poll(levels).toEqual([
+      { deviceId: 'mic', deviceDirection: DeviceDirection.Input, volume: 0.3, changedFields: ['volume'] },
+    ]);
+    events.length = 0;
+    await setDefaultDevice('webcam');
+    await expect.poll(levels).toEqual([
+      { deviceId: 'webcam', deviceDirection: DeviceDirection.Input, volume: 0.2, changedFields: ['volume', 'deviceId'] },
+    ]);
+    subscription.stop();
+  });
+
+  it('should set the volume of each channel and the balance of a device', async () => {
+    useBackend('fake', {
+      devices: [
+        { id: 'speakers', name: 'Speakers', direction: DeviceDirection.Output, volume: 0.8 },
+        { id: 'surround', name: 'Surround', direction: DeviceDirection.Output, channels: 6 },
+        { id: 'mic', name: 'Mic', direction: DeviceDirection.Input, channels: 1 },
+      ],
+    });
+
+    expect(await getChannelVolumes()).toEqual([
+      { position: ChannelPosition.FrontLeft, volume: 0.8 },
+      { position: ChannelPosition.FrontRight, volume: 0.8 },
+    ]);
+    expect((await getChannelVolumes('surround')).map((channel) => channel.position)).toEqual([
+      ChannelPosition.FrontLeft,
+      ChannelPosition.FrontRight,
+      ChannelPosition.FrontCenter,
+      ChannelPosition.Lfe,
+      ChannelPosition.RearLeft,
+      ChannelPosition.RearRight,
+    ]);
+
+    expect(await setBalance(-0.5)).toBe(true);
+    expect((await getChannelVolumes()).map((channel) => channel.volume)).toEqual([0.8, 0.4]);
+    expect(await getBalance()).toBeCloseTo(-0.5, 9);
+    expect(await getSystemVolume()).toBe(0.8);
+
+    expect(await setSystemVolume(0.4)).toBe(true);
+    expect((await getChannelVolumes()).map((channel) => channel.volume)).toEqual([0.4, 0.2]);
+
+    expect(await setChannelVolumes([0.1, 0.3])).toBe(true);
+    expect(await getSystemVolume()).toBe(0.3);
+    expect(await getBalance()).toBeCloseTo(2 / 3, 9);
+
+    await expect(setChannelVolumes([0.1])).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    await expect(setBalance(2)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    expect(await getBalance('mic')).toBe(0);
+    await expect(setBalance(0.5, 'mic')).rejects.toMatchObject({ code: ErrorStatus.NotSupported });
+  });
+
+  it('should read and write device volumes in dB', async () => {
+    useBackend('fake', {
+      devices: [
+        { id: 'speakers', name: 'Speakers', direction: DeviceDirection.Output, channelVolumes: [0.5, 0.25] },
+        { id: 'dac', name: 'DAC', direction: DeviceDirection.Output, volumeSteps: 11 },
+      ],
+    });
+
+    expect(await getVolumeRange()).toEqual({ minDb: -60, maxDb: 0 });
+    expect(await getVolumeRange('dac')).toEqual({ minDb: -60, maxDb: 0, steps: 11 });
+    expect(await getVolumeDb()).toBeCloseTo(-18.06, 2);
+
+    expect(await setVolumeDb(-6)).toBe(true);
+    expect(await getVolumeDb()).toBeCloseTo(-6, 9);
+    expect(await getSystemVolume()).toBeCloseTo(10 ** -0.1, 9);
+    const [left, right] = (await getChannelVolumes()).map((channel) => channel.volume);
+    expect(right / left).toBeCloseTo(0.5, 9);
+
+    expect(await setVolumeDb(-Infinity)).toBe(true);
+    expect(await getSystemVolume()).toBe(0);
+    await expect(setVolumeDb(3)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    await expect(setVolumeDb(NaN)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+  });
+
+  it('should map volume levels through the chosen curve', async () => {
+    expect(getVolumeCurve()).toBeNull();
+    try {
+      setVolumeCurve(VolumeCurve.Linear);
+      expect(getVolumeCurve()).toBe(VolumeCurve.Linear);
+      expect(await setSystemVolume(0.5)).toBe(true);
+      expect(await getVolumeDb()).toBeCloseTo(-6.02, 2);
+      expect(await getSystemVolume()).toBeCloseTo(0.5, 9);
+
+      setVolumeCurve(VolumeCurve.Db);
+      expect(await setSystemVolume(0.5)).toBe(true);
+      expect(await getVolumeDb()).toBeCloseTo(-30, 9);
+      expect(await setSessionVolume('firefox', 0.75)).toBe(true);
+      expect(await getSessionVolumeDb('firefox')).toBeCloseTo(-15, 9);
+      expect(await setSystemVolume(0)).toBe(true);
+      expect(await getVolumeDb()).toBe(-Infinity);
+
+      // The fake backend scales volumes like PulseAudio, so the cubic curve
+      // leaves its levels as they are
+      setVolumeCurve(VolumeCurve.Cubic);
+      expect(await setSystemVolume(0.4)).toBe(true);
+      setVolumeCurve();
+      expect(await getSystemVolume()).toBeCloseTo(0.4, 9);
+    } finally {
+      setVolumeCurve();
+    }
+  });
+
+  it('should step the volume, landing on the steps of the device', async () => {
+    useBackend('fake', {
+      devices: [
+        { id: 'speakers', name: 'Speakers', direction: DeviceDirection.Output, volume: 0.5 },
+        { id: 'dac', name: 'DAC', direction: DeviceDirection.Output, volume: 0.5, volumeSteps: 11 },
+      ],
+    });
+
+    expect(await volumeStep(StepDirection.Up)).toBeCloseTo(0.55, 9);
+    expect(await volumeStep(StepDirection.Down, 0.25)).toBeCloseTo(0.3, 9);
+    expect(await volumeStep(StepDirection.Up, 1)).toBe(1);
+    expect(await getSystemVolume()).toBeCloseTo(1, 9);
+
+    expect(await volumeStep(StepDirection.Up, undefined, 'dac')).toBe(0.6);
+    expect(await volumeStep(StepDirection.Up, 0.01, 'dac')).toBe(0.7);
+    expect(await volumeStep(StepDirection.Down, 0.2, 'dac')).toBe(0.5);
+    expect(await getSystemVolume('dac')).toBeCloseTo(0.5, 9);
+
+    await expect(volumeStep(StepDirection.Up, 0)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+    await expect(volumeStep(StepDirection.Down, 1.5)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+  });
+
+  it('should recover from transient audio failures', async () => {
+    useBackend('fake', {
+      audioSessions: [{ id: 'firefox', name: 'Firefox' }],
+      failures: [
+        { call: 'listAudioSessions', status: ErrorStatus.BackendUnavailable, nativeCode: 0x88890004, times: 1 },
+        { call: 'setSessionVolume', status: ErrorStatus.PermissionDenied, message: 'Access denied', times: 2 },
+      ],
+    });
+
+    await expect(listAudioSessions()).rejects.toMatchObject({
+      code: ErrorStatus.BackendUnavailable,
+      nativeCode: 0x88890004,
+    });
+    expect(await listAudioSessions()).toEqual([{ id: 'firefox', name: 'Firefox' }]);
+
+    await expect(setSessionVolume('firefox', 0.4)).rejects.toThrow('Access denied');
+    await expect(setSessionVolume('firefox', 0.4)).rejects.toMatchObject({ code: ErrorStatus.PermissionDenied });
+    expect(await setSessionVolume('firefox', 0.4)).toBe(true);
+    expect(await getSessionVolume('firefox')).toBe(0.4);
+  });
+
+  it('should keep failing calls that fail every time', async () => {
+    useBackend('fake', {
+      sessions: [{ id: 'music', mediaInfo: track('Intro') }],
+      failures: [{ call: 'getMediaInfo' }, { call: 'getSystemMute', status: ErrorStatus.Timeout }],
+    });
+
+    for (let attempt = 0; attempt < 3; attempt++) {
+      expect(() => getMediaInfo()).toThrow(expect.objectContaining({ code: ErrorStatus.PlatformError }));
+      await expect(getShuffle()).rejects.toMatchObject({ code: ErrorStatus.PlatformError });
+      await expect(getSystemMute()).rejects.toMatchObject({ code: ErrorStatus.Timeout });
+    }
+    expect(await play()).toBe(true);
+    expect(await getSystemVolume()).toBe(0.5);
+  });
+
+  it('should feed scripted changes to subscribers', async () => {
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents(
+      (event) => events.push(event),
+      () => {},
+    );
+
+    await expect.poll(() => events.length).toBeGreaterThanOrEqual(3);
+    advanceFakeClock(1000);
+    await expect
+      .poll(() => events.map((event) => event.mediaInfo?.title))
+      .toContain('Interlude');
+
+    expect(events.some((event) => event.eventType === EventType.VolumeChange)).toBe(true);
+    subscription.stop();
+  });
+
+  it('should reject unknown backends', () => {
+    expect(() => useBackend('nope' as 'fake')).toThrow('Unknown backend: nope');
+  });
+
+  it('should only advance the fake clock while it is in use', () => {
+    useBackend('system');
+    expect(() => advanceFakeClock(10)).toThrow();
+  });
+});
@@ -0,0 +1,190 @@
+// Minimal MPRIS player used by the Linux tests.
+// Usage: node fake-mpris-player.mjs <name> <playbackStatus> <artPath> <desktopEntry>
+// Prints `ready` once the bus name is owned, then one line per method call.
+import dbus from 'dbus-next';
+
+const { Interface, ACCESS_READ, ACCESS_READWRITE } = dbus.interface;
+const [name = 'fake', status = 'Paused', artPath = '', desktopEntry = ''] =
+  process.argv.slice(2);
+
+const tracks = ['First', 'Second'];
+
+class FakeRoot extends Interface {
+  constructor() {
+    super('org.mpris.MediaPlayer2');
+  }
+
+  get Identity() {
+    return name;
+  }
+
+  get DesktopEntry() {
+    return desktopEntry;
+  }
+}
+
+FakeRoot.configureMembers({
+  properties: {
+    Identity: { signature: 's', access: ACCESS_READ },
+    DesktopEntry: { signature: 's', access: ACCESS_READ },
+  },
+});
+
+class FakePlayer extends Interface {
+  constructor() {
+    super('org.mpris.MediaPlayer2.Player');
+    this.status = status;
+    this.track = 0;
+    this.shuffle = false;
+    this.loopStatus = 'None';
+    this.rate = 1;
+  }
+
+  get Shuffle() {
+    return this.shuffle;
+  }
+
+  set Shuffle(value) {
+    this.shuffle = value;
+  }
+
+  get LoopStatus() {
+    return this.loopStatus;
+  }
+
+  set LoopStatus(value) {
+    this.loopStatus = value;
+  }
+
+  get Rate() {
+    return this.rate;
+  }
+
+  set Rate(value) {
+    this.rate = value;
+  }
+
+  get MinimumRate() {
+    return 0.5;
+  }
+
+  get MaximumRate() {
+    return 2;
+  }
+
+  get PlaybackStatus() {
+    return this.status;
+  }
+
+  get CanPlay() {
+    return true;
+  }
+
+  get CanPause() {
+    return true;
+  }
+
+  get CanGoNext() {
+    return true;
+  }
+
+  get CanGoPrevious() {
+    return true;
+  }
+
+  get CanSeek() {
+    return true;
+  }
+
+  get CanControl() {
+    return true;
+  }
+
+  get Position() {
+    return 42_000_000;
+  }
+
+  get Metadata() {
+    const metadata = {
+      'xesam:title': new dbus.Variant('s', tracks[this.track]),
+      'xesam:artist': new dbus.Variant('as', ['Artist One', 'Artist Two']),
+      'xesam:album': new dbus.Variant('s', 'Album'),
+      'xesam:albumArtist': new dbus.Variant('as', ['Album Artist']),
+      'xesam:trackNumber': new dbus.Variant('i', this.track + 1),
+      'xesam:genre': new dbus.Variant('as', ['Rock']),
+      'mpris:length': new dbus.Variant('x', 180_000_000),
+      'mpris:trackid': new dbus.Variant('o', `/track/${this.track + 1}`),
+    };
+    if (artPath) {
+      metadata['mpris:artUrl'] = new dbus.Variant('s', `file://${encodeURI(artPath)}`);
+    }
+    return metadata;
+  }
+
+  Play() {
+    this.status = 'Playing';
+    console.log('Play');
+  }
+
+  Pause() {
+    this.status = 'Paused';
+    console.log('Pause');
+  }
+
+  Stop() {
+    this.status = 'Stopped';
+    console.log('Stop');
+  }
+
+  Next() {
+    this.track = Math.min(this.track + 1, tracks.length - 1);
+    console.log('Next');
+  }
+
+  Previous() {
+    this.track = Math.max(this.track - 1, 0);
+    console.log('Previous');
+  }
+
+  Seek(offset) {
+    console.log(`Seek ${offset}`);
+  }
+
+  SetPosition(trackId, position) {
+    console.log(`SetPosition ${trackId} ${position}`);
+  }
+}
+
+FakePlayer.configureMembers({
+  properties: {
+    PlaybackStatus: { signature: 's', access: ACCESS_READ },
+    Metadata: { signature: 'a{sv}', access: ACCESS_READ },
+    Position: { signature: 'x', access: ACCESS_READ },
+    CanPlay: { signature: 'b', access: ACCESS_READ },
+    CanPause: { signature: 'b', access: ACCESS_READ },
+    CanGoNext: { signature: 'b', access: ACCESS_READ },
+    CanGoPrevious: { signature: 'b', access: ACCESS_READ },
+    CanSeek: { signature: 'b', access: ACCESS_READ },
+    CanControl: { signature: 'b', access: ACCESS_READ },
+    Shuffle: { signature: 'b', access: ACCESS_READWRITE },
+    LoopStatus: { signature: 's', access: ACCESS_READWRITE },
+    Rate: { signature: 'd', access: ACCESS_READWRITE },
+    MinimumRate: { signature: 'd', access: ACCESS_READ },
+    MaximumRate: { signature: 'd', access: ACCESS_READ },
+  },
+  methods: {
+    Play: {},
+    Pause: {},
+    Stop: {},
+    Next: {},
+    Previous: {},
+    Seek: { inSignature: 'x' },
+    SetPosition: { inSignature: 'ox' },
+  },
+});
+
+const bus = dbus.sessionBus();
+await bus.requestName(`org.mpris.MediaPlayer2.${name}`, 0);
+bus.export('/org/mpris/MediaPlayer2', new FakePlayer());
+if (desktopEntry) bus.export('/org/mpris/MediaPlayer2', new FakeRoot());
+console.log('ready');
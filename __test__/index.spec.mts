@@ -1,5 +1,6 @@
-import { it, expect, test } from 'vitest';
+import { it, expect, test, beforeAll } from 'vitest';
 import {
+  useBackend,
   getMediaInfo,
   getThumbnail,
   play,
@@ -11,8 +12,34 @@ import {
   getSystemVolume,
   setSystemMute,
   getSystemMute,
+  ErrorStatus,
 } from '../index.js';
 
+// Run against the in-memory backend so the tests never touch the real
+// players or the master volume of the machine running them
+beforeAll(() => {
+  const track = (title: string) => ({
+    title,
+    artist: 'Artist',
+    album: 'Album',
+    albumArtist: 'Album Artist',
+    playbackStatus: 'paused',
+    hasThumbnail: true,
+  });
+
+  useBackend('fake', {
+    sessions: [
+      {
+        id: 'player',
+        mediaInfo: track('First'),
+        thumbnail: [0x89, 0x50, 0x4e, 0x47],
+        playlist: [track('First'), track('Second')],
+      },
+    ],
+    volume: 0.3,
+  });
+});
+
 // Media Info and Thumbnail
 it('should get media info', async () => {
   const info = getMediaInfo();
@@ -95,8 +122,8 @@ it('should handle volume controls', async () => {
 });
 
 it('should handle invalid volume values', async () => {
-  expect(await setSystemVolume(-0.1)).toBe(false);
-  expect(await setSystemVolume(1.1)).toBe(false);
+  await expect(setSystemVolume(-0.1)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
+  await expect(setSystemVolume(1.1)).rejects.toMatchObject({ code: ErrorStatus.InvalidArgument });
 });
 
 it('should handle mute controls', async () => {
@@ -0,0 +1,237 @@
+import { it, expect, beforeAll, afterAll, describe } from 'vitest';
+import { spawn, spawnSync, ChildProcess } from 'node:child_process';
+import { mkdtempSync, writeFileSync, rmSync } from 'node:fs';
+import { tmpdir } from 'node:os';
+import { join } from 'node:path';
+import { fileURLToPath } from 'node:url';
+import { once } from 'node:events';
+import { createInterface } from 'node:readline';
+import {
+  listMediaSessions,
+  getMediaInfo,
+  getThumbnail,
+  play,
+  pause,
+  next,
+  stop,
+  seek,
+  setPosition,
+  SeekResult,
+  setShuffle,
+  setRepeatMode,
+  setPlaybackRate,
+  RepeatMode,
+  subscribeToEvents,
+  EventType,
+  SessionKind,
+  ErrorStatus,
+  type EventData,
+} from '../index.js';
+
+const hasDbus =
+  process.platform === 'linux' &&
+  spawnSync('dbus-daemon', ['--version']).status === 0;
+
+// The players run in their own processes: getMediaInfo() blocks the event
+// loop while it waits for their replies.
+const playerScript = fileURLToPath(
+  new URL('./fixtures/fake-mpris-player.mjs', import.meta.url),
+);
+
+describe.skipIf(!hasDbus)('MPRIS backend', () => {
+  let busPid: number;
+  let tempDir: string;
+  const players: ChildProcess[] = [];
+  const calls: string[] = [];
+
+  async function startPlayer(
+    name: string,
+    status: string,
+    artPath = '',
+    desktopEntry = '',
+  ) {
+    const player = spawn(
+      process.execPath,
+      [playerScript, name, status, artPath, desktopEntry],
+      { stdio: ['ignore', 'pipe', 'inherit'] },
+    );
+    players.push(player);
+
+    const lines = createInterface({ input: player.stdout! });
+    const ready = once(lines, 'line');
+    lines.on('line', (line) => {
+      if (line !== 'ready') calls.push(`${name}:${line}`);
+    });
+    await ready;
+  }
+
+  beforeAll(async () => {
+    // Run against a private session bus so the host's players don't interfere
+    const daemon = spawnSync('dbus-daemon', [
+      '--session',
+      '--fork',
+      '--print-address=1',
+      '--print-pid=1',
+    ]);
+    const [address, pid] = daemon.stdout.toString().trim().split('\n');
+    process.env.DBUS_SESSION_BUS_ADDRESS = address;
+    busPid = Number(pid);
+
+    tempDir = mkdtempSync(join(tmpdir(), 'universal-media-'));
+    const artPath = join(tempDir, 'cover art.png');
+    writeFileSync(artPath, Buffer.from([0x89, 0x50, 0x4e, 0x47]));
+
+    await startPlayer('idle', 'Paused');
+    await startPlayer('active', 'Playing', artPath, 'active-player');
+  });
+
+  afterAll(() => {
+    players.forEach((player) => player.kill());
+    if (busPid) process.kill(busPid);
+    if (tempDir) rmSync(tempDir, { recursive: true, force: true });
+  });
+
+  it('should read metadata from the playing player', () => {
+    const before = Date.now();
+    const info = getMediaInfo();
+    expect(info).toEqual({
+      title: 'First',
+      artist: 'Artist One, Artist Two',
+      album: 'Album',
+      albumArtist: 'Album Artist',
+      playbackStatus: 'playing',
+      hasThumbnail: true,
+      trackNumber: 1,
+      genres: ['Rock'],
+      durationMs: 180_000,
+      positionMs: 42_000,
+      lastUpdated: expect.any(Number),
+      sourceAppId: 'active-player',
+      shuffle: false,
+      repeatMode: 'none',
+      playbackRate: 1,
+      capabilities: {
+        play: true,
+        pause: true,
+        stop: true,
+        next: true,
+        previous: true,
+        seek: true,
+        shuffle: true,
+        repeat: true,
+        rate: true,
+        // The fixture publishes no Volume property
+        volume: false,
+      },
+      trackId: '/track/1',
+    });
+    expect(info!.lastUpdated).toBeGreaterThanOrEqual(before);
+  });
+
+  it('should read the thumbnail from a file art url', () => {
+    expect(getThumbnail()).toEqual([0x89, 0x50, 0x4e, 0x47]);
+  });
+
+  it('should send transport commands to the current player', async () => {
+    expect(await next()).toBe(true);
+    expect(getMediaInfo()?.title).toBe('Second');
+
+    expect(await play()).toBe(true);
+    expect(await stop()).toBe(true);
+    expect(calls).toEqual(['active:Next', 'active:Play', 'active:Stop']);
+  });
+
+  it('should fall back to a paused player', async () => {
+    calls.length = 0;
+    expect(await pause()).toBe(true);
+    expect(calls).toEqual(['idle:Pause']);
+  });
+
+  it('should list every player as a session', () => {
+    expect(listMediaSessions()).toEqual([
+      {
+        id: 'org.mpris.MediaPlayer2.active',
+        sourceAppId: 'active-player',
+        isCurrent: false,
+      },
+      {
+        id: 'org.mpris.MediaPlayer2.idle',
+        sourceAppId: 'idle',
+        isCurrent: true,
+      },
+    ]);
+  });
+
+  it('should control a player that is not the current one', async () => {
+    calls.length = 0;
+    const active = 'org.mpris.MediaPlayer2.active';
+
+    expect(await play(active)).toBe(true);
+    expect(calls).toEqual(['active:Play']);
+    expect(getMediaInfo(active)?.playbackStatus).toBe('playing');
+    expect(getThumbnail(active)).toEqual([0x89, 0x50, 0x4e, 0x47]);
+  });
+
+  it('should not fall back to the current player for unknown sessions', async () => {
+    const missing = 'org.mpris.MediaPlayer2.missing';
+    expect(() => getMediaInfo(missing)).toThrow(expect.objectContaining({ code: ErrorStatus.NoSession }));
+    await expect(play(missing)).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+  });
+
+  it('should seek relative to the position and to the track id', async () => {
+    calls.length = 0;
+    const active = 'org.mpris.MediaPlayer2.active';
+
+    expect(await seek(-5000, active)).toBe(SeekResult.Ok);
+    expect(await setPosition(10_000, active)).toBe(SeekResult.Ok);
+    expect(await setPosition(999_999_999, active)).toBe(SeekResult.Ok);
+    expect(calls).toEqual([
+      'active:Seek -5000000',
+      'active:SetPosition /track/2 10000000',
+      'active:SetPosition /track/2 180000000',
+    ]);
+    await expect(seek(1000, 'org.mpris.MediaPlayer2.missing')).rejects.toMatchObject({
+      code: ErrorStatus.NoSession,
+    });
+  });
+
+  it('should change shuffle, loop status and rate', async () => {
+    const active = 'org.mpris.MediaPlayer2.active';
+
+    expect(await setShuffle(true, active)).toBe(true);
+    expect(await setRepeatMode(RepeatMode.List, active)).toBe(true);
+    expect(await setPlaybackRate(1.5, active)).toBe(true);
+    expect(getMediaInfo(active)).toMatchObject({
+      shuffle: true,
+      repeatMode: 'list',
+      playbackRate: 1.5,
+    });
+
+    // Outside of the MinimumRate..MaximumRate range of the player
+    await expect(setPlaybackRate(4, active)).rejects.toMatchObject({ code: ErrorStatus.NotSupported });
+  });
+
+  it('should report players appearing and going away', async () => {
+    const events: EventData[] = [];
+    const subscription = subscribeToEvents((event) => events.push(event), {
+      eventTypes: [EventType.SessionAdded, EventType.SessionRemoved],
+    });
+
+    await startPlayer('late', 'Paused');
+    await expect.poll(() => events.length).toBe(1);
+    expect(events[0]).toMatchObject({
+      eventType: EventType.SessionAdded,
+      sessionId: 'org.mpris.MediaPlayer2.late',
+      sourceAppId: 'late',
+      sessionKind: SessionKind.Media,
+    });
+
+    players.pop()!.kill();
+    await expect.poll(() => events.length).toBe(2);
+    expect(events[1]).toMatchObject({
+      eventType: EventType.SessionRemoved,
+      sessionId: 'org.mpris.MediaPlayer2.late',
+    });
+    subscription.stop();
+  });
+});
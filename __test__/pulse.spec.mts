@@ -0,0 +1,314 @@
+import { it, expect, beforeAll, afterAll, describe } from 'vitest';
+import { spawn, spawnSync, ChildProcess } from 'node:child_process';
+import { mkdtempSync, existsSync, rmSync } from 'node:fs';
+import { tmpdir } from 'node:os';
+import { join } from 'node:path';
+import {
+  useBackend,
+  getSystemVolume,
+  setSystemVolume,
+  getSystemMute,
+  setSystemMute,
+  getChannelVolumes,
+  setChannelVolumes,
+  getBalance,
+  setBalance,
+  ChannelPosition,
+  getVolumeRange,
+  setVolumeDb,
+  volumeStep,
+  StepDirection,
+  setVolumeCurve,
+  VolumeCurve,
+  getInputVolume,
+  setInputVolume,
+  getInputMute,
+  setInputMute,
+  listAudioSessions,
+  getSessionVolume,
+  setSessionVolume,
+  getSessionMute,
+  setSessionMute,
+  getSessionChannelVolumes,
+  setSessionChannelVolumes,
+  getSessionBalance,
+  setSessionBalance,
+  getSessionVolumeRange,
+  getSessionVolumeDb,
+  setSessionVolumeDb,
+  listAudioDevices,
+  setDefaultDevice,
+  DeviceDirection,
+  DeviceState,
+  ErrorStatus,
+} from '../index.js';
+
+const hasPulse =
+  process.platform === 'linux' &&
+  spawnSync('pulseaudio', ['--version']).status === 0;
+
+// Runs a private pulseaudio daemon whose only sink is a null sink, so the
+// tests never touch the audio devices of the machine running them
+describe.skipIf(!hasPulse)('PulseAudio backend', () => {
+  let daemon: ChildProcess;
+  let runtimeDir: string;
+  let server: string;
+
+  const pactl = (...args: string[]) =>
+    spawnSync('pactl', ['-s', server, ...args]).stdout.toString().trim();
+
+  beforeAll(async () => {
+    runtimeDir = mkdtempSync(join(tmpdir(), 'universal-media-pulse-'));
+    const socket = join(runtimeDir, 'native');
+    server = `unix:${socket}`;
+
+    daemon = spawn(
+      'pulseaudio',
+      [
+        '-n',
+        '--daemonize=no',
+        '--exit-idle-time=-1',
+        `--load=module-native-protocol-unix socket=${socket} auth-anonymous=1`,
+        '--load=module-null-sink sink_name=test_sink',
+      ],
+      { env: { ...process.env, PULSE_RUNTIME_PATH: runtimeDir }, stdio: 'ignore' },
+    );
+
+    await expect.poll(() => existsSync(socket), { timeout: 5000 }).toBe(true);
+    process.env.PULSE_SERVER = server;
+    useBackend('system');
+  });
+
+  afterAll(() => {
+    daemon?.kill();
+    if (runtimeDir) rmSync(runtimeDir, { recursive: true, force: true });
+  });
+
+  it('should set and read the volume of the default sink', async () => {
+    expect(await setSystemVolume(0.4)).toBe(true);
+    expect(await getSystemVolume()).toBeCloseTo(0.4, 3);
+    expect(pactl('get-sink-volume', 'test_sink')).toContain('40%');
+  });
+
+  it('should keep the balance between channels', async () => {
+    pactl('set-sink-volume', 'test_sink', '50%', '25%');
+    expect(await getSystemVolume()).toBeCloseTo(0.5, 3);
+
+    expect(await setSystemVolume(0.8)).toBe(true);
+    const volume = pactl('get-sink-volume', 'test_sink');
+    expect(volume).toContain('80%');
+    expect(volume).toContain('40%');
+  });
+
+  it('should mute and unmute the default sink', async () => {
+    expect(await setSystemMute(true)).toBe(true);
+    expect(await getSystemMute()).toBe(true);
+    expect(pactl('get-sink-mute', 'test_sink')).toBe('Mute: yes');
+
+    expect(await setSystemMute(false)).toBe(true);
+    expect(await getSystemMute()).toBe(false);
+  });
+
+  it('should set the channels and balance of the default sink', async () => {
+    expect(await setChannelVolumes([0.6, 0.3])).toBe(true);
+    expect(await getChannelVolumes()).toEqual([
+      { position: ChannelPosition.FrontLeft, volume: expect.closeTo(0.6, 3) },
+      { position: ChannelPosition.FrontRight, volume: expect.closeTo(0.3, 3) },
+    ]);
+    expect(await getBalance()).toBeCloseTo(-0.5, 3);
+
+    expect(await setBalance(0.25)).toBe(true);
+    const volume = pactl('get-sink-volume', 'test_sink');
+    expect(volume).toContain('45%');
+    expect(volume).toContain('60%');
+  });
+
+  it('should set the default sink in dB and through a volume curve', async () => {
+    pactl('set-sink-volume', 'test_sink', '50%');
+    expect(await getVolumeRange()).toEqual({ minDb: -60, maxDb: 0 });
+    expect(await setVolumeDb(-30)).toBe(true);
+    expect(pactl('get-sink-volume', 'test_sink')).toContain('-30.00 dB');
+
+    try {
+      setVolumeCurve(VolumeCurve.Linear);
+      expect(await setSystemVolume(0.125)).toBe(true);
+      expect(pactl('get-sink-volume', 'test_sink')).toContain('50%');
+    } finally {
+      setVolumeCurve();
+    }
+
+    expect(await volumeStep(StepDirection.Up, 0.1)).toBeCloseTo(0.6, 9);
+    expect(pactl('get-sink-volume', 'test_sink')).toContain('60%');
+  });
+
+  describe('with a second sink', () => {
+    let module: string;
+
+    beforeAll(() => {
+      module = pactl('load-module', 'module-null-sink', 'sink_name=other_sink', 'sink_properties=device.description=Other');
+    });
+
+    afterAll(() => {
+      pactl('set-default-sink', 'test_sink');
+      pactl('unload-module', module);
+    });
+
+    it('should list the sinks as output devices', async () => {
+      const outputs = await listAudioDevices({ direction: DeviceDirection.Output });
+      expect(outputs.map((device) => device.id).sort()).toEqual(['other_sink', 'test_sink']);
+      expect(outputs.find((device) => device.id === 'test_sink')).toMatchObject({
+        direction: DeviceDirection.Output,
+        state: DeviceState.Active,
+        isDefault: true,
+      });
+      expect(outputs.find((device) => device.id === 'other_sink')).toMatchObject({
+        name: 'Other',
+        isDefault: false,
+        channels: 2,
+      });
+    });
+
+    it('should leave the monitor sources out of the inputs', async () => {
+      expect(await listAudioDevices({ direction: DeviceDirection.Input })).toEqual([]);
+    });
+
+    it('should control a sink by id', async () => {
+      expect(await setSystemVolume(0.3, 'other_sink')).toBe(true);
+      expect(await getSystemVolume('other_sink')).toBeCloseTo(0.3, 3);
+      expect(pactl('get-sink-volume', 'other_sink')).toContain('30%');
+
+      expect(await setSystemMute(true, 'other_sink')).toBe(true);
+      expect(pactl('get-sink-mute', 'other_sink')).toBe('Mute: yes');
+      expect(await getSystemMute()).toBe(false);
+
+      await expect(getSystemVolume('missing_sink')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+    });
+
+    it('should switch the default sink', async () => {
+      expect(await setDefaultDevice('other_sink')).toBe(true);
+      expect(pactl('get-default-sink')).toBe('other_sink');
+      expect(await getSystemMute()).toBe(true);
+
+      const outputs = await listAudioDevices({ direction: DeviceDirection.Output });
+      expect(outputs.filter((device) => device.isDefault).map((device) => device.id)).toEqual(['other_sink']);
+    });
+  });
+
+  describe('with a microphone', () => {
+    let module: string;
+
+    beforeAll(() => {
+      module = pactl('load-module', 'module-null-source', 'source_name=test_source');
+      pactl('set-default-source', 'test_source');
+    });
+
+    afterAll(() => {
+      pactl('unload-module', module);
+    });
+
+    it('should set and read the volume and mute state of the default source', async () => {
+      expect(await setInputVolume(0.4)).toBe(true);
+      expect(await getInputVolume()).toBeCloseTo(0.4, 3);
+      expect(pactl('get-source-volume', 'test_source')).toContain('40%');
+
+      expect(await setInputMute(true)).toBe(true);
+      expect(await getInputMute('test_source')).toBe(true);
+      expect(pactl('get-source-mute', 'test_source')).toBe('Mute: yes');
+      expect(await getSystemMute()).toBe(false);
+    });
+
+    it('should list the source as the default input', async () => {
+      expect(await listAudioDevices({ direction: DeviceDirection.Input })).toMatchObject([
+        { id: 'test_source', direction: DeviceDirection.Input, isDefault: true },
+      ]);
+    });
+  });
+
+  describe('with an application playing', () => {
+    let player: ChildProcess;
+
+    beforeAll(async () => {
+      player = spawn(
+        'pacat',
+        ['-s', server, '--client-name=universal-media-test', '/dev/zero'],
+        { stdio: 'ignore' },
+      );
+      await expect
+        .poll(async () => (await listAudioSessions()).length, { timeout: 5000 })
+        .toBe(1);
+    });
+
+    afterAll(() => {
+      player?.kill();
+    });
+
+    it('should list the sink inputs as audio sessions', async () => {
+      const [session] = await listAudioSessions();
+      expect(session.name).toBe('universal-media-test');
+      expect(session.pid).toBe(player.pid);
+    });
+
+    it('should set the volume and mute state of a sink input', async () => {
+      const [session] = await listAudioSessions();
+
+      expect(await setSessionVolume(session.id, 0.3)).toBe(true);
+      expect(await getSessionVolume(session.id)).toBeCloseTo(0.3, 3);
+      expect(pactl('list', 'sink-inputs')).toContain('30%');
+
+      expect(await setSessionMute(session.id, true)).toBe(true);
+      expect(await getSessionMute(session.id)).toBe(true);
+    });
+
+    it('should balance a sink input', async () => {
+      const [session] = await listAudioSessions();
+
+      expect(await setSessionChannelVolumes(session.id, [0.5, 0.5])).toBe(true);
+      expect(await setSessionBalance(session.id, -1)).toBe(true);
+      expect(await getSessionChannelVolumes(session.id)).toEqual([
+        { position: ChannelPosition.FrontLeft, volume: expect.closeTo(0.5, 3) },
+        { position: ChannelPosition.FrontRight, volume: 0 },
+      ]);
+      expect(await getSessionBalance(session.id)).toBe(-1);
+    });
+
+    it('should set the volume of a sink input in dB', async () => {
+      const [session] = await listAudioSessions();
+
+      expect(await getSessionVolumeRange(session.id)).toEqual({ minDb: -60, maxDb: 0 });
+      expect(await setSessionVolumeDb(session.id, -60)).toBe(true);
+      expect(await getSessionVolumeDb(session.id)).toBeCloseTo(-60, 1);
+      expect(await getSessionVolume(session.id)).toBeCloseTo(0.1, 3);
+    });
+
+    it('should not find sessions that do not exist', async () => {
+      await expect(getSessionVolume('999999')).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+      await expect(setSessionMute('not-an-index', true)).rejects.toMatchObject({ code: ErrorStatus.NoSession });
+    });
+
+    it('should tell apart sessions of the same application', async () => {
+      const [first] = await listAudioSessions();
+      const second = spawn(
+        'pacat',
+        ['-s', server, '--client-name=universal-media-test', '/dev/zero'],
+        { stdio: 'ignore' },
+      );
+      try {
+        await expect
+          .poll(async () => (await listAudioSessions()).length, { timeout: 5000 })
+          .toBe(2);
+        const other = (await listAudioSessions()).find((session) => session.id !== first.id)!;
+        expect(other).toMatchObject({ name: first.name, pid: second.pid });
+
+        expect(await setSessionVolume(first.id, 0.2)).toBe(true);
+        expect(await setSessionVolume(other.id, 0.7)).toBe(true);
+        expect(await getSessionVolume(first.id)).toBeCloseTo(0.2, 3);
+        expect(await getSessionVolume(other.id)).toBeCloseTo(0.7, 3);
+      } finally {
+        second.kill();
+      }
+      await expect
+        .poll(async () => (await listAudioSessions()).map((session) => session.id), { timeout: 5000 })
+        .toEqual([first.id]);
+    });
+  });
+});
@@ -3,12 +3,245 @@
 
 /* auto-generated by NAPI-RS */
 
+/** A media session held by the fake backend */
+export interface FakeSession {
+  /** Id the session is addressed by */
+  id: string
+  /** Id of the application owning the session, defaults to `id` */
+  sourceAppId?: string
+  /** Metadata and playback status reported for the session */
+  mediaInfo: MediaInfo
+  /** Bytes returned by `getThumbnail` for the session */
+  thumbnail?: Array<number>
+  /** Tracks walked through by `next` and `previous`, starting at the first */
+  playlist?: Array<MediaInfo>
+  /**
+   * Whether `seek` and `setPosition` are supported, defaults to true.
+   * `mediaInfo.capabilities` takes precedence when given
+   */
+  canSeek?: boolean
+}
+/** An application playing audio in the fake backend */
+export interface FakeAudioSession {
+  /** Id the session is addressed by */
+  id: string
+  /** The name of the application */
+  name: string
+  /** The process id of the application */
+  pid?: number
+  /** Initial volume between 0.0 and 1.0, defaults to 1.0 */
+  volume?: number
+  /** Initial mute state, defaults to false */
+  muted?: boolean
+  /**
+   * Initial volume of each channel between 0.0 and 1.0, taking precedence
+   * over `volume`. Defaults to two channels at `volume`
+   */
+  channelVolumes?: Array<number>
+}
+/** An output or input device of the fake backend */
+export interface FakeAudioDevice {
+  /** Id the device is addressed by */
+  id: string
+  /** The name to show for the device */
+  name: string
+  /** What kind of device it is */
+  description?: string
+  /** Whether the device plays or records audio */
+  direction: DeviceDirection
+  /** Whether the device can be used, defaults to active */
+  state?: DeviceState
+  /**
+   * Whether the device starts as the default for its direction, which
+   * is otherwise the first device of that direction
+   */
+  isDefault?: boolean
+  /** Number of channels, defaults to 2 */
+  channels?: number
+  /** Sample rate in Hz, defaults to 48000 */
+  sampleRate?: number
+  /** Initial volume between 0.0 and 1.0, defaults to 0.5 */
+  volume?: number
+  /** Initial mute state, defaults to false */
+  muted?: boolean
+  /**
+   * Initial volume of each channel between 0.0 and 1.0, taking precedence
+   * over `volume` and `channels`
+   */
+  channelVolumes?: Array<number>
+  /**
+   * Number of volume steps the device reports, for a device that cannot
+   * be set to any volume
+   */
+  volumeSteps?: number
+}
+/** A call the fake backend fails instead of carrying out */
+export interface FakeFailure {
+  /**
+   * Name of the exported function whose request to the backend fails.
+   * `getMediaInfo` also fails the getters reading from the media info, the
+   * channel volume calls the balance functions built on them, and the dB
+   * calls the volume functions while a volume curve is set
+   */
+  call: 'listMediaSessions' | 'getMediaInfo' | 'getThumbnail' | 'play' | 'pause' | 'next' | 'previous' | 'stop' | 'seek' | 'setPosition' | 'setShuffle' | 'setRepeatMode' | 'setPlaybackRate' | 'getSystemVolume' | 'setSystemVolume' | 'getSystemMute' | 'setSystemMute' | 'getInputVolume' | 'setInputVolume' | 'getInputMute' | 'setInputMute' | 'getChannelVolumes' | 'setChannelVolumes' | 'getVolumeRange' | 'getVolumeDb' | 'setVolumeDb' | 'listAudioDevices' | 'setDefaultDevice' | 'listAudioSessions' | 'getSessionVolume' | 'setSessionVolume' | 'getSessionMute' | 'setSessionMute' | 'getSessionChannelVolumes' | 'setSessionChannelVolumes' | 'getSessionVolumeRange' | 'getSessionVolumeDb' | 'setSessionVolumeDb'
+  /** Why the call fails, defaults to `ERR_PLATFORM` */
+  status?: ErrorStatus
+  /** Native code reported with the error */
+  nativeCode?: number
+  /** Message of the error */
+  message?: string
+  /**
+   * How many calls fail before the call works again, every call failing
+   * when not given
+   */
+  times?: number
+}
+/** A change applied by the fake backend once its clock reaches `atMs` */
+export interface FakeStep {
+  /** Milliseconds after `useBackend` at which the step applies */
+  atMs: number
+  /** Session the media changes apply to, defaults to the current session */
+  sessionId?: string
+  /** Replaces the metadata of the session */
+  mediaInfo?: MediaInfo
+  /** Replaces only the playback status of the session */
+  playbackStatus?: string
+  /** Replaces the thumbnail of the session */
+  thumbnail?: Array<number>
+  /** Makes another session the current one */
+  currentSessionId?: string
+  /** Media sessions that show up */
+  addSessions?: Array<FakeSession>
+  /** Ids of media sessions that go away */
+  removeSessions?: Array<string>
+  /** Applications that start playing audio */
+  addAudioSessions?: Array<FakeAudioSession>
+  /** Ids of audio sessions that go away */
+  removeAudioSessions?: Array<string>
+  /** New volume of the default output device between 0.0 and 1.0 */
+  volume?: number
+  /** New mute state of the default output device */
+  muted?: boolean
+}
+/** Initial state and timeline of the fake backend */
+export interface FakeScenario {
+  /** Sessions present from the start */
+  sessions?: Array<FakeSession>
+  /** Id of the current session, defaults to the first session */
+  currentSessionId?: string
+  /** Initial volume of the default output device, overriding its own */
+  volume?: number
+  /** Initial mute state of the default output device, overriding its own */
+  muted?: boolean
+  /** Output and input devices, defaults to one speaker and one microphone */
+  devices?: Array<FakeAudioDevice>
+  /** Applications playing audio from the start */
+  audioSessions?: Array<FakeAudioSession>
+  /** Changes to apply over time */
+  steps?: Array<FakeStep>
+  /** Calls that fail, to see how callers deal with platform errors */
+  failures?: Array<FakeFailure>
+  /**
+   * Whether the clock follows real time (default) or only moves through
+   * `advanceFakeClock`
+   */
+  realtime?: boolean
+}
+/** How volume levels between 0.0 and 1.0 map to loudness */
+export const enum VolumeCurve {
+  /** The level is the amplitude the signal is scaled by */
+  Linear = 'linear',
+  /**
+   * The level is the cube root of the amplitude, like the percentages of
+   * PulseAudio, which is close to how loud people hear it
+   */
+  Cubic = 'cubic',
+  /** The level moves evenly through the dB range of the device */
+  Db = 'db'
+}
+/**
+ * Chooses the curve the volume levels of devices and applications follow,
+ * or goes back to the scale of the platform, which is the default
+ * @param curve - `linear`, `cubic` or `db`, or nothing for the platform scale
+ */
+export declare function setVolumeCurve(curve?: VolumeCurve | undefined | null): void
+/**
+ * Gets the curve the volume levels follow
+ * @returns The curve, or null while levels follow the scale of the platform
+ */
+export declare function getVolumeCurve(): VolumeCurve | null
+/** Why a call failed, given as the `code` of the error */
+export const enum ErrorStatus {
+  /** There is no such session or device, or no session at all */
+  NoSession = 'ERR_NO_SESSION',
+  /** The player, device or platform does not support the request */
+  NotSupported = 'ERR_NOT_SUPPORTED',
+  /** The system refused access */
+  PermissionDenied = 'ERR_PERMISSION_DENIED',
+  /**
+   * The platform service cannot be reached, such as the D-Bus session
+   * bus or the sound server
+   */
+  BackendUnavailable = 'ERR_BACKEND_UNAVAILABLE',
+  /** The platform did not answer in time */
+  Timeout = 'ERR_TIMEOUT',
+  /** An argument is out of range or malformed */
+  InvalidArgument = 'ERR_INVALID_ARGUMENT',
+  /**
+   * The platform failed with an error of its own, given as `nativeCode`
+   * where it has one
+   */
+  PlatformError = 'ERR_PLATFORM'
+}
+/** How failed calls report to JavaScript */
+export const enum ErrorMode {
+  /** Promises reject and functions throw with the status as `code` */
+  Throw = 'throw',
+  /**
+   * Calls resolve with `false`, `null` or an empty list on failure, as
+   * before errors had a status
+   */
+  Compat = 'compat'
+}
+/**
+ * Chooses whether failures reject and throw with a coded error, which is
+ * the default, or resolve with `false` and `null` like the boolean API
+ * @param mode - `throw` or `compat`
+ */
+export declare function setErrorMode(mode: ErrorMode): void
 /** The type of event that occurred */
 export const enum EventType {
   MediaChange = 0,
   PlaybackChange = 1,
   VolumeChange = 2,
-  MuteChange = 3
+  MuteChange = 3,
+  ShuffleChange = 4,
+  RepeatModeChange = 5,
+  PlaybackRateChange = 6,
+  CapabilitiesChange = 7,
+  PositionChange = 8,
+  Seeked = 9,
+  TrackEnded = 10,
+  TrackNearEnd = 11,
+  SessionAdded = 12,
+  SessionRemoved = 13,
+  CurrentSessionChanged = 14
+}
+/** Which list a session of a session event belongs to */
+export const enum SessionKind {
+  /** A media session, as listed by `listMediaSessions` */
+  Media = 'media',
+  /** An application with its own volume, as listed by `listAudioSessions` */
+  Audio = 'audio'
+}
+/** How the track of a `TrackEnded` event came to an end */
+export const enum TrackEndReason {
+  /** The track played to its end */
+  Completed = 'completed',
+  /** Another track was started before the end, such as through `next` */
+  Skipped = 'skipped',
+  /** The player reports no position or duration to tell either way */
+  Unknown = 'unknown'
 }
 /** The data for an event */
 export interface EventData {
@@ -16,18 +249,117 @@ export interface EventData {
   mediaInfo?: MediaInfo
   volume?: number
   muted?: boolean
+  /**
+   * The playback position in milliseconds, moved forward from the last
+   * position the player reported, for `PositionChange`, `Seeked` and
+   * `TrackNearEnd`. For `TrackEnded`, where the track stopped playing.
+   */
+  positionMs?: number
+  /**
+   * Names of the fields that changed, such as `albumArtist`, or
+   * `thumbnail` when the artwork did. Absent from the initial snapshot
+   * and when media shows up.
+   */
+  changedFields?: Array<string>
+  /**
+   * Whether the track of a `TrackEnded` event, given in `mediaInfo`,
+   * played to completion or was skipped
+   */
+  endReason?: TrackEndReason
+  /**
+   * The session of a `SessionAdded`, `SessionRemoved` or
+   * `CurrentSessionChanged` event, absent when no session is current
+   * anymore
+   */
+  sessionId?: string
+  /**
+   * Id of the application owning the session, which is the application
+   * name for audio sessions
+   */
+  sourceAppId?: string
+  /** Whether the session is a media session or an audio session */
+  sessionKind?: SessionKind
+  /**
+   * The default device whose level a `VolumeChange` or `MuteChange`
+   * event reports. `changedFields` holds `deviceId` when another device
+   * became the default.
+   */
+  deviceId?: string
+  /**
+   * Whether the device of a `VolumeChange` or `MuteChange` event is the
+   * default output or the default input
+   */
+  deviceDirection?: DeviceDirection
 }
-/** The data for a subscription */
-export interface Subscription {
-  stop: () => void
+/** Options narrowing what a subscription reports */
+export interface SubscriptionOptions {
+  /** Event types to receive, all of them when not given */
+  eventTypes?: Array<EventType>
+  /**
+   * How often the state is read when the platform has to be polled, and
+   * the least time between two reads when it pushes changes, so bursts
+   * of changes come as one event. Defaults to 500 ms when polling and to
+   * no debouncing otherwise. Intervals below 100 ms are raised to 100 ms.
+   */
+  intervalMs?: number
+  /**
+   * Session whose media events are reported, instead of the current one.
+   * Session events are reported for every session regardless.
+   */
+  sessionId?: string
+  /**
+   * App whose media events are reported, instead of the current session.
+   * Ignored when `sessionId` is given.
+   */
+  sourceAppId?: string
+  /**
+   * Whether to report the current media, volume and mute state right
+   * after subscribing. Defaults to true.
+   */
+  initialSnapshot?: boolean
+  /**
+   * How often to report the position while media is playing. Position
+   * events are only sent when this is given, or when `eventTypes` asks
+   * for `PositionChange`, in which case it defaults to 1000 ms. Intervals
+   * below 50 ms are raised to 50 ms.
+   */
+  positionIntervalMs?: number
+  /**
+   * How long before the end of a track `TrackNearEnd` is sent, once per
+   * track while it plays. Defaults to 10000 ms.
+   */
+  nearEndThresholdMs?: number
 }
-/** Subscribes to events */
-export declare function subscribeToEvents(callback: (arg: EventData) => any, stopCallback: () => void): Subscription
-/** Custom error codes that can be thrown */
-export const enum ErrorStatus {
-  CantDoSomething = 'ERR_STATUS_CANTDSMTGH',
-  InvalidCondition = 'ERR_STATUS_INVALID'
+/**
+ * Handle to a running event subscription
+ *
+ * Events keep coming until `stop` is called or the environment that
+ * created the subscription is torn down, such as when a worker thread
+ * exits or an Electron window reloads.
+ */
+export class Subscription {
+  /**
+   * Stops the subscription, then calls the stop callback given to
+   * `subscribeToEvents`. Stopping the last subscription also waits for
+   * the native event thread to exit, for at most a second while it is
+   * stuck on an unresponsive backend. Does nothing when already stopped.
+   */
+  stop(): void
+  /** Whether the subscription still delivers events */
+  get isActive(): boolean
 }
+/**
+ * Subscribes to events
+ *
+ * Changes are picked up from platform notifications where the backend
+ * supports them, and by polling otherwise. Every subscription shares the
+ * same native event thread, so adding subscribers costs no extra system
+ * queries.
+ * @param callback - Called with every event
+ * @param stopCallbackOrOptions - Called once the subscription is stopped, or the options when no stop callback is needed
+ * @param options - Options of the subscription, when a stop callback is given
+ */
+export declare function subscribeToEvents(callback: (arg: EventData) => any, stopCallbackOrOptions?: (() => void) | SubscriptionOptions, options?: SubscriptionOptions | undefined | null): Subscription
 /** Information about the currently playing media */
 export interface MediaInfo {
   /** The title of the media */
@@ -42,55 +374,507 @@ export interface MediaInfo {
   playbackStatus: string
   /** Whether the media has an associated thumbnail image */
   hasThumbnail: boolean
+  /** The subtitle of the media */
+  subtitle?: string
+  /** The position of the track on its album */
+  trackNumber?: number
+  /** The number of tracks on the album */
+  trackCount?: number
+  /** The genres of the media */
+  genres?: Array<string>
+  /** The kind of media being played (music, video, image or unknown) */
+  playbackType?: string
+  /** The length of the media in milliseconds */
+  durationMs?: number
+  /** The playback position in milliseconds, as of `lastUpdated` */
+  positionMs?: number
+  /** When the position was last updated, in milliseconds since the Unix epoch */
+  lastUpdated?: number
+  /** Id of the application playing the media */
+  sourceAppId?: string
+  /** Whether tracks are played in random order */
+  shuffle?: boolean
+  /** Whether the track or the whole list is repeated */
+  repeatMode?: RepeatMode
+  /** The playback speed, 1.0 being normal speed */
+  playbackRate?: number
+  /** The controls the player currently accepts */
+  capabilities?: MediaCapabilities
+  /**
+   * Id the player gives the track, which tells apart tracks with the
+   * same metadata. Only MPRIS players publish one.
+   */
+  trackId?: string
+}
+/** The controls a media session currently accepts */
+export interface MediaCapabilities {
+  /** Whether `play` is accepted */
+  play: boolean
+  /** Whether `pause` is accepted */
+  pause: boolean
+  /** Whether `stop` is accepted */
+  stop: boolean
+  /** Whether `next` is accepted */
+  next: boolean
+  /** Whether `previous` is accepted */
+  previous: boolean
+  /** Whether `seek` and `setPosition` are accepted */
+  seek: boolean
+  /** Whether `setShuffle` is accepted */
+  shuffle: boolean
+  /** Whether `setRepeatMode` is accepted */
+  repeat: boolean
+  /** Whether `setPlaybackRate` is accepted */
+  rate: boolean
+  /** Whether the player exposes its own volume */
+  volume: boolean
+}
+/** What a player repeats once the current track ends */
+export const enum RepeatMode {
+  /** Playback stops at the end of the list */
+  None = 'none',
+  /** The current track is repeated */
+  Track = 'track',
+  /** The whole list is repeated */
+  List = 'list'
 }
-/** Gets information about the currently playing media */
-export declare function getMediaInfo(): MediaInfo | null
-/** Gets the thumbnail image for the currently playing media as a byte array */
-export declare function getThumbnail(): Array<number> | null
+/** Outcome of a seek or set-position request */
+export const enum SeekResult {
+  /** The player moved to the new position */
+  Ok = 'ok',
+  /** The player does not support seeking in the current media */
+  Unsupported = 'unsupported',
+  /** There is no such session, or no session at all */
+  NoSession = 'no-session',
+  /** The player supports seeking but rejected the request */
+  Failed = 'failed'
+}
+/** A media session, such as a player or a browser tab playing media */
+export interface MediaSession {
+  /** Id the session is addressed by, stable for as long as the session exists */
+  id: string
+  /** Id of the application owning the session */
+  sourceAppId: string
+  /** Whether this is the session the system considers current */
+  isCurrent: boolean
+}
+/** An application playing audio, with its own volume and mute state */
+export interface AudioSession {
+  /** Id the session is addressed by */
+  id: string
+  /** The name of the application */
+  name: string
+  /** The process id of the application, if known */
+  pid?: number
+}
+/** Whether a device plays or records audio */
+export const enum DeviceDirection {
+  /** Speakers, headphones and other outputs */
+  Output = 'output',
+  /** Microphones and other inputs */
+  Input = 'input'
+}
+/** Whether a device can be used */
+export const enum DeviceState {
+  /** The device is there and enabled */
+  Active = 'active',
+  /** The device was turned off in the sound settings */
+  Disabled = 'disabled',
+  /** Nothing is plugged into the jack of the device */
+  Unplugged = 'unplugged'
+}
+/** An output or input audio device */
+export interface AudioDevice {
+  /** Id the device is addressed by, which stays the same across restarts */
+  id: string
+  /** The name to show for the device */
+  name: string
+  /** What kind of device it is, such as speakers or headphones */
+  description?: string
+  /** Whether the device plays or records audio */
+  direction: DeviceDirection
+  /** Whether the device can be used */
+  state: DeviceState
+  /** Whether the system plays or records through the device by default */
+  isDefault: boolean
+  /** Whether the system uses the device for calls by default */
+  isDefaultCommunication: boolean
+  /** The number of channels of the device, if known */
+  channels?: number
+  /** The sample rate of the device in Hz, if known */
+  sampleRate?: number
+}
+/** Where the speaker a channel plays through stands */
+export const enum ChannelPosition {
+  /** The only channel of a mono stream */
+  Mono = 'mono',
+  FrontLeft = 'front-left',
+  FrontRight = 'front-right',
+  FrontCenter = 'front-center',
+  /** The subwoofer */
+  Lfe = 'lfe',
+  RearLeft = 'rear-left',
+  RearRight = 'rear-right',
+  RearCenter = 'rear-center',
+  FrontLeftOfCenter = 'front-left-of-center',
+  FrontRightOfCenter = 'front-right-of-center',
+  SideLeft = 'side-left',
+  SideRight = 'side-right',
+  /** Height and auxiliary channels */
+  Other = 'other'
+}
+/** The volume of one channel of a device or an application */
+export interface ChannelVolume {
+  /** Where the channel plays */
+  position: ChannelPosition
+  /** Volume level of the channel between 0.0 and 1.0 */
+  volume: number
+}
+/** The volumes in dB a device or an application can be set to */
+export interface VolumeRange {
+  /**
+   * The quietest volume in dB. Volumes applied in software go below it
+   * down to silence.
+   */
+  minDb: number
+  /** The loudest volume in dB */
+  maxDb: number
+  /** The smallest change in dB the device makes, if it tells */
+  incrementDb?: number
+  /**
+   * The number of volume steps of the device, for devices that cannot be
+   * set to any volume
+   */
+  steps?: number
+}
+/** Which way `volumeStep` moves the volume */
+export const enum StepDirection {
+  Up = 'up',
+  Down = 'down'
+}
+/** Which devices `listAudioDevices` returns */
+export interface AudioDeviceFilter {
+  /** Only list devices of this direction, both when not given */
+  direction?: DeviceDirection
+}
+/**
+ * Switches the backend every function talks to
+ * @param name - `system` for the platform backend, `fake` for an in-memory backend
+ * @param scenario - Initial state and timeline of the fake backend
+ */
+export declare function useBackend(name: 'system' | 'fake', scenario?: FakeScenario | undefined | null): void
+/**
+ * Moves the clock of the fake backend forward, applying any scenario steps that become due
+ * @param ms - Milliseconds to advance by
+ */
+export declare function advanceFakeClock(ms: number): void
+/**
+ * Lists every media session, not only the current one
+ * @returns The media sessions, whose ids the other media functions take
+ */
+export declare function listMediaSessions(): Array<MediaSession>
+/**
+ * Gets information about the currently playing media
+ * @param sessionId - Session to read instead of the current one
+ * @returns The media information, or null if there is no current session
+ */
+export declare function getMediaInfo(sessionId?: string | undefined | null): MediaInfo | null
+/**
+ * Gets the thumbnail image for the currently playing media as a byte array
+ * @param sessionId - Session to read instead of the current one
+ * @returns The image, or null if there is no current session or no artwork
+ */
+export declare function getThumbnail(sessionId?: string | undefined | null): Array<number> | null
 /**
  * Attempts to play the current media
+ * @param sessionId - Session to control instead of the current one
  * @returns Whether the play command was successful
  */
-export declare function play(): Promise<boolean>
+export declare function play(sessionId?: string | undefined | null): Promise<boolean>
 /**
  * Attempts to pause the current media
+ * @param sessionId - Session to control instead of the current one
  * @returns Whether the pause command was successful
  */
-export declare function pause(): Promise<boolean>
+export declare function pause(sessionId?: string | undefined | null): Promise<boolean>
 /**
  * Attempts to skip to the next track
+ * @param sessionId - Session to control instead of the current one
  * @returns Whether the next command was successful
  */
-export declare function next(): Promise<boolean>
+export declare function next(sessionId?: string | undefined | null): Promise<boolean>
 /**
  * Attempts to go back to the previous track
+ * @param sessionId - Session to control instead of the current one
  * @returns Whether the previous command was successful
  */
-export declare function previous(): Promise<boolean>
+export declare function previous(sessionId?: string | undefined | null): Promise<boolean>
 /**
  * Attempts to stop playback of the current media
+ * @param sessionId - Session to control instead of the current one
  * @returns Whether the stop command was successful
  */
-export declare function stop(): Promise<boolean>
+export declare function stop(sessionId?: string | undefined | null): Promise<boolean>
+/**
+ * Moves the playback position of the current media forwards or backwards
+ * @param offsetMs - Milliseconds to move by, negative to go back
+ * @param sessionId - Session to control instead of the current one
+ * @returns Whether the seek was applied, or why it was not
+ */
+export declare function seek(offsetMs: number, sessionId?: string | undefined | null): Promise<SeekResult>
+/**
+ * Moves the playback position of the current media to an absolute position
+ * @param positionMs - Position from the start of the media in milliseconds
+ * @param sessionId - Session to control instead of the current one
+ * @returns Whether the position was changed, or why it was not
+ */
+export declare function setPosition(positionMs: number, sessionId?: string | undefined | null): Promise<SeekResult>
+/**
+ * Gets the controls the current media session accepts
+ * @param sessionId - Session to read instead of the current one
+ * @returns The enabled controls, or null if there is no current session
+ */
+export declare function getCapabilities(sessionId?: string | undefined | null): Promise<MediaCapabilities | null>
+/**
+ * Gets whether the current media is shuffled
+ * @param sessionId - Session to read instead of the current one
+ * @returns Whether shuffle is on, or null if the player does not report it
+ */
+export declare function getShuffle(sessionId?: string | undefined | null): Promise<boolean | null>
+/**
+ * Turns shuffle on or off
+ * @param shuffle - Whether tracks should play in random order
+ * @param sessionId - Session to control instead of the current one
+ * @returns Whether the player applied the change
+ */
+export declare function setShuffle(shuffle: boolean, sessionId?: string | undefined | null): Promise<boolean>
+/**
+ * Gets what the player repeats once the current track ends
+ * @param sessionId - Session to read instead of the current one
+ * @returns The repeat mode, or null if the player does not report it
+ */
+export declare function getRepeatMode(sessionId?: string | undefined | null): Promise<RepeatMode | null>
+/**
+ * Sets what the player repeats once the current track ends
+ * @param mode - `none`, `track` or `list`
+ * @param sessionId - Session to control instead of the current one
+ * @returns Whether the player applied the change
+ */
+export declare function setRepeatMode(mode: RepeatMode, sessionId?: string | undefined | null): Promise<boolean>
+/**
+ * Gets the playback speed of the current media
+ * @param sessionId - Session to read instead of the current one
+ * @returns The playback rate, 1.0 being normal speed, or null if the player does not report it
+ */
+export declare function getPlaybackRate(sessionId?: string | undefined | null): Promise<number | null>
+/**
+ * Sets the playback speed of the current media
+ * @param rate - Playback rate, 1.0 being normal speed
+ * @param sessionId - Session to control instead of the current one
+ * @returns Whether the player applied the change
+ */
+export declare function setPlaybackRate(rate: number, sessionId?: string | undefined | null): Promise<boolean>
 /**
  * Sets the system volume level
  * @param level - Volume level between 0.0 and 1.0
+ * @param deviceId - Device to change instead of the default output device
  * @returns Whether setting the volume was successful
  */
-export declare function setSystemVolume(level: number): Promise<boolean>
+export declare function setSystemVolume(level: number, deviceId?: string | undefined | null): Promise<boolean>
 /**
  * Gets the current system volume level
- * @returns Volume level between 0.0 and 1.0, or null if unable to get volume
+ * @param deviceId - Device to read instead of the default output device
+ * @returns Volume level between 0.0 and 1.0, or null in the compatibility mode if unable to get volume
  */
-export declare function getSystemVolume(): Promise<number | null>
+export declare function getSystemVolume(deviceId?: string | undefined | null): Promise<number | null>
 /**
  * Sets the system mute state
  * @param mute - Whether to mute (true) or unmute (false) the system audio
+ * @param deviceId - Device to change instead of the default output device
  * @returns Whether setting the mute state was successful
  */
-export declare function setSystemMute(mute: boolean): Promise<boolean>
+export declare function setSystemMute(mute: boolean, deviceId?: string | undefined | null): Promise<boolean>
 /**
  * Gets the current system mute state
- * @returns Whether the system is muted (true) or not (false), or null if unable to get state
+ * @param deviceId - Device to read instead of the default output device
+ * @returns Whether the system is muted (true) or not (false), or null in the compatibility mode if unable to get state
+ */
+export declare function getSystemMute(deviceId?: string | undefined | null): Promise<boolean | null>
+/**
+ * Sets the volume level of the microphone
+ * @param level - Volume level between 0.0 and 1.0
+ * @param deviceId - Device to change instead of the default input device
+ * @returns Whether setting the volume was successful
+ */
+export declare function setInputVolume(level: number, deviceId?: string | undefined | null): Promise<boolean>
+/**
+ * Gets the volume level of the microphone
+ * @param deviceId - Device to read instead of the default input device
+ * @returns Volume level between 0.0 and 1.0, or null in the compatibility mode if unable to get volume
+ */
+export declare function getInputVolume(deviceId?: string | undefined | null): Promise<number | null>
+/**
+ * Mutes or unmutes the microphone
+ * @param mute - Whether to mute (true) or unmute (false) the microphone
+ * @param deviceId - Device to change instead of the default input device
+ * @returns Whether setting the mute state was successful
+ */
+export declare function setInputMute(mute: boolean, deviceId?: string | undefined | null): Promise<boolean>
+/**
+ * Gets whether the microphone is muted
+ * @param deviceId - Device to read instead of the default input device
+ * @returns Whether the microphone is muted, or null in the compatibility mode if unable to get state
+ */
+export declare function getInputMute(deviceId?: string | undefined | null): Promise<boolean | null>
+/**
+ * Gets the volume of each channel of a device
+ * @param deviceId - Device to read instead of the default output device
+ * @returns The channels in the order the device mixes them, with where each one plays
+ */
+export declare function getChannelVolumes(deviceId?: string | undefined | null): Promise<Array<ChannelVolume>>
+/**
+ * Sets the volume of each channel of a device
+ * @param levels - Volume level between 0.0 and 1.0 for each channel, in the order `getChannelVolumes` gives them
+ * @param deviceId - Device to change instead of the default output device
+ * @returns Whether setting the volumes was successful
+ */
+export declare function setChannelVolumes(levels: Array<number>, deviceId?: string | undefined | null): Promise<boolean>
+/**
+ * Gets the balance between the left and right channels of a device
+ * @param deviceId - Device to read instead of the default output device
+ * @returns Balance between -1.0 (left only) and 1.0 (right only), 0.0 for devices without left and right channels
+ */
+export declare function getBalance(deviceId?: string | undefined | null): Promise<number | null>
+/**
+ * Moves the balance between the left and right channels of a device. The louder side keeps its volume, so the
+ * overall loudness stays the same.
+ * @param balance - Balance between -1.0 (left only) and 1.0 (right only)
+ * @param deviceId - Device to change instead of the default output device
+ * @returns Whether setting the balance was successful
+ */
+export declare function setBalance(balance: number, deviceId?: string | undefined | null): Promise<boolean>
+/**
+ * Gets the volumes in dB a device can be set to
+ * @param deviceId - Device to read instead of the default output device
+ * @returns The range of the device, or null in the compatibility mode if unable to get it
+ */
+export declare function getVolumeRange(deviceId?: string | undefined | null): Promise<VolumeRange | null>
+/**
+ * Gets the volume of a device in dB
+ * @param deviceId - Device to read instead of the default output device
+ * @returns The volume in dB, `-Infinity` when silent, or null in the compatibility mode if unable to get volume
+ */
+export declare function getVolumeDb(deviceId?: string | undefined | null): Promise<number | null>
+/**
+ * Sets the volume of a device in dB
+ * @param db - Volume in dB up to the `maxDb` of `getVolumeRange`. Volumes below `minDb` give the quietest volume
+ * the device has.
+ * @param deviceId - Device to change instead of the default output device
+ * @returns Whether setting the volume was successful
+ */
+export declare function setVolumeDb(db: number, deviceId?: string | undefined | null): Promise<boolean>
+/**
+ * Turns the volume of a device up or down, like the volume keys do. On devices with a fixed number of volume steps
+ * the volume lands on one of them.
+ * @param direction - `up` or `down`
+ * @param step - How far to move the volume level, defaults to one step of the device or else 0.05
+ * @param deviceId - Device to change instead of the default output device
+ * @returns The new volume level between 0.0 and 1.0, or null in the compatibility mode if unable to change it
+ */
+export declare function volumeStep(direction: StepDirection, step?: number | undefined | null, deviceId?: string | undefined | null): Promise<number | null>
+/**
+ * Lists the output and input audio devices
+ * @param filter - Limits the list to outputs or inputs
+ * @returns The devices, whose ids the volume and mute functions take
+ */
+export declare function listAudioDevices(filter?: AudioDeviceFilter | undefined | null): Promise<Array<AudioDevice>>
+/**
+ * Makes a device the default output or input, depending on its direction
+ * @param id - Id of the audio device
+ * @returns Whether the default device was changed
+ */
+export declare function setDefaultDevice(id: string): Promise<boolean>
+/**
+ * Lists the applications currently playing audio
+ * @returns The audio sessions, whose ids the other session functions take
+ */
+export declare function listAudioSessions(): Promise<Array<AudioSession>>
+/**
+ * Sets the volume level of an application
+ * @param id - Id of the audio session
+ * @param level - Volume level between 0.0 and 1.0
+ * @returns Whether setting the volume was successful
+ */
+export declare function setSessionVolume(id: string, level: number): Promise<boolean>
+/**
+ * Gets the volume level of an application
+ * @param id - Id of the audio session
+ * @returns Volume level between 0.0 and 1.0, or null in the compatibility mode if the session was not found
+ */
+export declare function getSessionVolume(id: string): Promise<number | null>
+/**
+ * Sets the mute state of an application
+ * @param id - Id of the audio session
+ * @param mute - Whether to mute (true) or unmute (false) the application
+ * @returns Whether setting the mute state was successful
+ */
+export declare function setSessionMute(id: string, mute: boolean): Promise<boolean>
+/**
+ * Gets the mute state of an application
+ * @param id - Id of the audio session
+ * @returns Whether the application is muted, or null in the compatibility mode if the session was not found
+ */
+export declare function getSessionMute(id: string): Promise<boolean | null>
+/**
+ * Gets the volume of each channel of an application
+ * @param id - Id of the audio session
+ * @returns The channels of the application, with where each one plays
+ */
+export declare function getSessionChannelVolumes(id: string): Promise<Array<ChannelVolume>>
+/**
+ * Sets the volume of each channel of an application
+ * @param id - Id of the audio session
+ * @param levels - Volume level between 0.0 and 1.0 for each channel, in the order `getSessionChannelVolumes` gives them
+ * @returns Whether setting the volumes was successful
+ */
+export declare function setSessionChannelVolumes(id: string, levels: Array<number>): Promise<boolean>
+/**
+ * Gets the balance between the left and right channels of an application
+ * @param id - Id of the audio session
+ * @returns Balance between -1.0 (left only) and 1.0 (right only), or null in the compatibility mode if the session was not found
+ */
+export declare function getSessionBalance(id: string): Promise<number | null>
+/**
+ * Moves the balance between the left and right channels of an application, keeping its loudness
+ * @param id - Id of the audio session
+ * @param balance - Balance between -1.0 (left only) and 1.0 (right only)
+ * @returns Whether setting the balance was successful
+ */
+export declare function setSessionBalance(id: string, balance: number): Promise<boolean>
+/**
+ * Gets the volumes in dB an application can be set to
+ * @param id - Id of the audio session
+ * @returns The range of the application, or null in the compatibility mode if the session was not found
+ */
+export declare function getSessionVolumeRange(id: string): Promise<VolumeRange | null>
+/**
+ * Gets the volume of an application in dB
+ * @param id - Id of the audio session
+ * @returns The volume in dB, `-Infinity` when silent, or null in the compatibility mode if the session was not found
+ */
+export declare function getSessionVolumeDb(id: string): Promise<number | null>
+/**
+ * Sets the volume of an application in dB
+ * @param id - Id of the audio session
+ * @param db - Volume in dB up to the `maxDb` of `getSessionVolumeRange`, going down to silence
+ * @returns Whether setting the volume was successful
+ */
+export declare function setSessionVolumeDb(id: string, db: number): Promise<boolean>
+/**
+ * Turns the volume of an application up or down
+ * @param id - Id of the audio session
+ * @param direction - `up` or `down`
+ * @param step - How far to move the volume level, defaults to 0.05
+ * @returns The new volume level between 0.0 and 1.0, or null in the compatibility mode if unable to change it
  */
-export declare function getSystemMute(): Promise<boolean | null>
+export declare function sessionVolumeStep(id: string, direction: StepDirection, step?: number | undefined | null): Promise<number | null>
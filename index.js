@@ -310,11 +310,28 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { EventType, subscribeToEvents, ErrorStatus, getMediaInfo, getThumbnail, play, pause, next, previous, stop, setSystemVolume, getSystemVolume, setSystemMute, getSystemMute } = nativeBinding
+const { VolumeCurve, setVolumeCurve, getVolumeCurve, ErrorStatus, ErrorMode, setErrorMode, EventType, SessionKind, TrackEndReason, Subscription, subscribeToEvents, RepeatMode, SeekResult, DeviceDirection, DeviceState, ChannelPosition, StepDirection, useBackend, advanceFakeClock, listMediaSessions, getMediaInfo, getThumbnail, play, pause, next, previous, stop, seek, setPosition, getCapabilities, getShuffle, setShuffle, getRepeatMode, setRepeatMode, getPlaybackRate, setPlaybackRate, setSystemVolume, getSystemVolume, setSystemMute, getSystemMute, setInputVolume, getInputVolume, setInputMute, getInputMute, getChannelVolumes, setChannelVolumes, getBalance, setBalance, getVolumeRange, getVolumeDb, setVolumeDb, volumeStep, listAudioDevices, setDefaultDevice, listAudioSessions, setSessionVolume, getSessionVolume, setSessionMute, getSessionMute, getSessionChannelVolumes, setSessionChannelVolumes, getSessionBalance, setSessionBalance, getSessionVolumeRange, getSessionVolumeDb, setSessionVolumeDb, sessionVolumeStep } = nativeBinding
 
+module.exports.VolumeCurve = VolumeCurve
+module.exports.setVolumeCurve = setVolumeCurve
+module.exports.getVolumeCurve = getVolumeCurve
+module.exports.ErrorStatus = ErrorStatus
+module.exports.ErrorMode = ErrorMode
+module.exports.setErrorMode = setErrorMode
 module.exports.EventType = EventType
+module.exports.SessionKind = SessionKind
+module.exports.TrackEndReason = TrackEndReason
+module.exports.Subscription = Subscription
 module.exports.subscribeToEvents = subscribeToEvents
-module.exports.ErrorStatus = ErrorStatus
+module.exports.RepeatMode = RepeatMode
+module.exports.SeekResult = SeekResult
+module.exports.DeviceDirection = DeviceDirection
+module.exports.DeviceState = DeviceState
+module.exports.ChannelPosition = ChannelPosition
+module.exports.StepDirection = StepDirection
+module.exports.useBackend = useBackend
+module.exports.advanceFakeClock = advanceFakeClock
+module.exports.listMediaSessions = listMediaSessions
 module.exports.getMediaInfo = getMediaInfo
 module.exports.getThumbnail = getThumbnail
 module.exports.play = play
@@ -322,7 +339,43 @@ module.exports.pause = pause
 module.exports.next = next
 module.exports.previous = previous
 module.exports.stop = stop
+module.exports.seek = seek
+module.exports.setPosition = setPosition
+module.exports.getCapabilities = getCapabilities
+module.exports.getShuffle = getShuffle
+module.exports.setShuffle = setShuffle
+module.exports.getRepeatMode = getRepeatMode
+module.exports.setRepeatMode = setRepeatMode
+module.exports.getPlaybackRate = getPlaybackRate
+module.exports.setPlaybackRate = setPlaybackRate
 module.exports.setSystemVolume = setSystemVolume
 module.exports.getSystemVolume = getSystemVolume
 module.exports.setSystemMute = setSystemMute
 module.exports.getSystemMute = getSystemMute
+module.exports.setInputVolume = setInputVolume
+module.exports.getInputVolume = getInputVolume
+module.exports.setInputMute = setInputMute
+module.exports.getInputMute = getInputMute
+module.exports.getChannelVolumes = getChannelVolumes
+module.exports.setChannelVolumes = setChannelVolumes
+module.exports.getBalance = getBalance
+module.exports.setBalance = setBalance
+module.exports.getVolumeRange = getVolumeRange
+module.exports.getVolumeDb = getVolumeDb
+module.exports.setVolumeDb = setVolumeDb
+module.exports.volumeStep = volumeStep
+module.exports.listAudioDevices = listAudioDevices
+module.exports.setDefaultDevice = setDefaultDevice
+module.exports.listAudioSessions = listAudioSessions
+module.exports.setSessionVolume = setSessionVolume
+module.exports.getSessionVolume = getSessionVolume
+module.exports.setSessionMute = setSessionMute
+module.exports.getSessionMute = getSessionMute
+module.exports.getSessionChannelVolumes = getSessionChannelVolumes
+module.exports.setSessionChannelVolumes = setSessionChannelVolumes
+module.exports.getSessionBalance = getSessionBalance
+module.exports.setSessionBalance = setSessionBalance
+module.exports.getSessionVolumeRange = getSessionVolumeRange
+module.exports.getSessionVolumeDb = getSessionVolumeDb
+module.exports.setSessionVolumeDb = setSessionVolumeDb
+module.exports.sessionVolumeStep = sessionVolumeStep
@@ -8,7 +8,15 @@ const __filename = fileURLToPath(import.meta.url);
 const __dirname = dirname(__filename);
 
 // Configuration
-const PLATFORM = 'win32-x64-msvc';
+const PLATFORMS: Record<string, string> = {
+  win32: 'win32-x64-msvc',
+  linux: 'linux-x64-gnu',
+};
+const PLATFORM = PLATFORMS[process.platform];
+if (!PLATFORM) {
+  console.error(`Unsupported platform: ${process.platform}`);
+  process.exit(1);
+}
 const FILENAME = `universal-media.${PLATFORM}.node`;
 const TARGET_DIR = join(__dirname, '..', 'npm', PLATFORM);
 
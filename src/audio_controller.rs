@@ -2,9 +2,9 @@ use windows::{
     core::Interface,
     Win32::{
         Media::Audio::{
-            eMultimedia, eRender, Endpoints::IAudioEndpointVolume, IAudioSessionControl,
-            IAudioSessionControl2, IAudioSessionEnumerator, IAudioSessionManager2, IMMDevice,
-            IMMDeviceEnumerator, ISimpleAudioVolume, MMDeviceEnumerator,
+            eMultimedia, eRender, Endpoints::IAudioEndpointVolume, IAudioSessionControl2,
+            IAudioSessionEnumerator, IAudioSessionManager2, IMMDevice, IMMDeviceEnumerator,
+            MMDeviceEnumerator,
         },
         System::{
             Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED, CLSCTX_ALL, COINIT_APARTMENTTHREADED},
@@ -13,8 +13,10 @@ use windows::{
         },
     },
 };
-use std::process::exit;
-use crate::session::{Session, ApplicationSession, EndPointSession};
+use crate::{
+    error::{Error, ErrorStatus, Result},
+    session::{Session, ApplicationSession, EndPointSession},
+};
 
 pub enum CoinitMode {
     MultiTreaded,
@@ -28,7 +30,7 @@ pub struct AudioController {
 }
 
 impl AudioController {
-    pub unsafe fn init(coinit_mode: Option<CoinitMode>) -> Self {
+    pub unsafe fn init(coinit_mode: Option<CoinitMode>) -> Result<Self> {
         let mut coinit = COINIT_MULTITHREADED;
         if let Some(x) = coinit_mode {
             match x {
@@ -37,154 +39,116 @@ impl AudioController {
             }
         }
         
-        let hr = CoInitializeEx(None, coinit);
-        if hr.is_err() {
-            eprintln!("ERROR: Couldn't initialize windows connection");
-            exit(1);
-        }
+        CoInitializeEx(None, coinit)
+            .ok()
+            .map_err(|err| Error::from(err).context("Couldn't initialize windows connection"))?;
 
-        Self {
+        Ok(Self {
             default_device: None,
             imm_device_enumerator: None,
             sessions: vec![],
-        }
+        })
     }
 
-    pub unsafe fn get_sessions(&mut self) {
-        self.imm_device_enumerator = Some(
-            CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_INPROC_SERVER).unwrap_or_else(
-                |err| {
-                    eprintln!("ERROR: Couldn't get Media device enumerator: {err}");
-                    exit(1);
-                },
-            ),
-        );
+    pub unsafe fn get_sessions(&mut self) -> Result<()> {
+        let enumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_INPROC_SERVER)
+            .map_err(|err| Error::from(err).context("Couldn't get Media device enumerator"))?;
+        self.imm_device_enumerator = Some(enumerator);
+        Ok(())
     }
 
-    pub unsafe fn get_default_audio_endpoint_volume_control(&mut self) {
-        if self.imm_device_enumerator.is_none() {
-            eprintln!("ERROR: Function called before creating enumerator");
-            return;
-        }
+    pub unsafe fn get_default_audio_endpoint_volume_control(&mut self) -> Result<()> {
+        let enumerator = self.imm_device_enumerator.as_ref().ok_or_else(|| {
+            Error::new(ErrorStatus::PlatformError, "Function called before creating enumerator")
+        })?;
 
-        self.default_device = Some(
-            self.imm_device_enumerator
-                .clone()
-                .unwrap()
-                .GetDefaultAudioEndpoint(eRender, eMultimedia)
-                .unwrap_or_else(|err| {
-                    eprintln!("ERROR: Couldn't get Default audio endpoint {err}");
-                    exit(1);
-                }),
-        );
-        let simple_audio_volume: IAudioEndpointVolume = self
-            .default_device
-            .clone()
-            .unwrap()
+        let default_device = enumerator
+            .GetDefaultAudioEndpoint(eRender, eMultimedia)
+            .map_err(|err| Error::from(err).context("Couldn't get Default audio endpoint"))?;
+        let simple_audio_volume: IAudioEndpointVolume = default_device
             .Activate(CLSCTX_ALL, None)
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldn't get Endpoint volume control: {err}");
-                exit(1);
-            });
-
+            .map_err(|err| Error::from(err).context("Couldn't get Endpoint volume control"))?;
         self.sessions.push(Box::new(EndPointSession::new(
+            &default_device,
             simple_audio_volume,
             "master".to_string(),
-        )));
+        )?));
+        self.default_device = Some(default_device);
+        Ok(())
     }
 
-    pub unsafe fn get_all_process_sessions(&mut self) {
-        if self.default_device.is_none() {
-            eprintln!("ERROR: Default device hasn't been initialized...");
-            return;
-        }
+    pub unsafe fn get_all_process_sessions(&mut self) -> Result<()> {
+        let default_device = self.default_device.as_ref().ok_or_else(|| {
+            Error::new(ErrorStatus::PlatformError, "Default device hasn't been initialized")
+        })?;
 
-        let session_manager2: IAudioSessionManager2 = match self.default_device
-            .as_ref()
-            .unwrap()
-            .Activate(CLSCTX_INPROC_SERVER, None) {
-                Ok(manager) => manager,
-                Err(err) => {
-                    eprintln!("ERROR: Couldn't get AudioSessionManager: {err}");
-                    return;
-                }
-            };
+        let session_manager2: IAudioSessionManager2 = default_device
+            .Activate(CLSCTX_INPROC_SERVER, None)
+            .map_err(|err| Error::from(err).context("Couldn't get AudioSessionManager"))?;
 
         let session_enumerator: IAudioSessionEnumerator = session_manager2
             .GetSessionEnumerator()
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldnt get session enumerator... {err}");
-                exit(1);
-            });
-
-        for i in 0..session_enumerator.GetCount().unwrap() {
-            let normal_session_control: Option<IAudioSessionControl> =
-                session_enumerator.GetSession(i).ok();
-            if normal_session_control.is_none() {
-                eprintln!("ERROR: Couldn't get session control of audio session...");
+            .map_err(|err| Error::from(err).context("Couldn't get session enumerator"))?;
+
+        let count = session_enumerator
+            .GetCount()
+            .map_err(|err| Error::from(err).context("Couldn't count audio sessions"))?;
+        // Sessions can expire or their process exit while being enumerated,
+        // so any that cannot be read are skipped
+        for i in 0..count {
+            let Ok(normal_session_control) = session_enumerator.GetSession(i) else {
+                continue;
+            };
+            let Ok(session_control) = normal_session_control.cast::<IAudioSessionControl2>() else {
+                continue;
+            };
+            let Ok(pid) = session_control.GetProcessId() else {
+                continue;
+            };
+            if pid == 0 {
                 continue;
             }
+            let Ok(process) = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid) else {
+                continue;
+            };
 
-            let session_control: Option<IAudioSessionControl2> =
-                normal_session_control.unwrap().cast().ok();
-            if session_control.is_none() {
-                eprintln!(
-                    "ERROR: Couldn't convert from normal session control to session control 2"
-                );
+            let mut filename: [u8; 128] = [0; 128];
+            let bytes_written = K32GetProcessImageFileNameA(process, &mut filename);
+            if bytes_written == 0 {
                 continue;
             }
+            let new_filename: Vec<u8> = filename.iter().copied().take_while(|&byte| byte != 0).collect();
+            let Ok(data) = String::from_utf8(new_filename) else {
+                continue;
+            };
+            let Some(name) = data.split('\\').last() else {
+                continue;
+            };
 
-            let pid = session_control.as_ref().unwrap().GetProcessId().unwrap();
-            if pid == 0 {
+            let Ok(application_session) = ApplicationSession::new(session_control, name.replace(".exe", "")) else {
                 continue;
-            }
-            if let Ok(process) = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, pid) {
-                let mut filename: [u8; 128] = [0; 128];
-                let bytes_written = K32GetProcessImageFileNameA(process, &mut filename);
-                if bytes_written > 0 {
-                    let mut new_filename: Vec<u8> = vec![];
-                    for byte in filename.iter() {
-                        if *byte == 0 {
-                            break;
-                        }
-                        new_filename.push(*byte);
-                    }
-                    
-                    let str_filename = match String::from_utf8(new_filename) {
-                        Ok(data) => {
-                            if let Some(name) = data.split('\\').last() {
-                                name.replace(".exe", "")
-                            } else {
-                                continue;
-                            }
-                        },
-                        Err(err) => {
-                            eprintln!("ERROR: Filename couldn't be converted to string, {err}");
-                            continue;
-                        }
-                    };
-
-                    let audio_control: ISimpleAudioVolume = match session_control.unwrap().cast() {
-                        Ok(data) => data,
-                        Err(err) => {
-                            eprintln!(
-                                "ERROR: Couldn't get the simpleaudiovolume from session controller: {err}"
-                            );
-                            continue;
-                        }
-                    };
-                    let application_session = ApplicationSession::new(audio_control, str_filename);
-                    self.sessions.push(Box::new(application_session));
-                }
-            }
+            };
+            self.sessions.push(Box::new(application_session));
         }
+        Ok(())
+    }
+
+    pub fn get_all_sessions(&self) -> &[Box<dyn Session>] {
+        &self.sessions
+    }
+
+    pub fn get_all_session_names(&self) -> Vec<String> {
+        self.sessions.iter().map(|i| i.display_name().to_string()).collect()
     }
 
-    pub unsafe fn get_all_session_names(&self) -> Vec<String> {
-        self.sessions.iter().map(|i| i.get_name()).collect()
+    /// Finds the first session shown under `name`. Several processes of one
+    /// application share a name, which [`AudioController::get_session_by_id`]
+    /// tells apart
+    pub fn get_session_by_name(&self, name: &str) -> Option<&dyn Session> {
+        self.sessions.iter().find(|i| i.display_name() == name).map(Box::as_ref)
     }
 
-    pub unsafe fn get_session_by_name(&self, name: String) -> Option<&Box<dyn Session>> {
-        self.sessions.iter().find(|i| i.get_name() == name)
+    pub fn get_session_by_id(&self, id: &str) -> Option<&dyn Session> {
+        self.sessions.iter().find(|i| i.id() == id).map(Box::as_ref)
     }
 } 
\ No newline at end of file
@@ -0,0 +1,911 @@
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+use napi_derive::napi;
+use crate::{
+    error::{Error, ErrorStatus, Result},
+    channels, AudioDevice, AudioSession, ChannelPosition, ChannelVolume, DeviceDirection, DeviceState,
+    MediaCapabilities, MediaInfo, RepeatMode, VolumeCurve, VolumeRange,
+};
+use super::{
+    unix_now_ms, watch::WATCHER_CHECK_INTERVAL, AudioBackend, DeviceRef, MediaBackend, TransportCommand,
+    Watcher,
+};
+
+/// A media session held by the fake backend
+#[napi(object)]
+#[derive(Clone)]
+pub struct FakeSession {
+    /// Id the session is addressed by
+    pub id: String,
+    /// Id of the application owning the session, defaults to `id`
+    pub source_app_id: Option<String>,
+    /// Metadata and playback status reported for the session
+    pub media_info: MediaInfo,
+    /// Bytes returned by `getThumbnail` for the session
+    pub thumbnail: Option<Vec<u8>>,
+    /// Tracks walked through by `next` and `previous`, starting at the first
+    pub playlist: Option<Vec<MediaInfo>>,
+    /// Whether `seek` and `setPosition` are supported, defaults to true.
+    /// `mediaInfo.capabilities` takes precedence when given
+    pub can_seek: Option<bool>,
+}
+
+impl FakeSession {
+    /// The capabilities of the media info, or every control but volume
+    fn capabilities(&self) -> MediaCapabilities {
+        self.media_info.capabilities.clone().unwrap_or(MediaCapabilities {
+            play: true,
+            pause: true,
+            stop: true,
+            next: true,
+            previous: true,
+            seek: self.can_seek.unwrap_or(true),
+            shuffle: true,
+            repeat: true,
+            rate: true,
+            volume: false,
+        })
+    }
+}
+
+/// An application playing audio in the fake backend
+#[napi(object)]
+#[derive(Clone)]
+pub struct FakeAudioSession {
+    /// Id the session is addressed by
+    pub id: String,
+    /// The name of the application
+    pub name: String,
+    /// The process id of the application
+    pub pid: Option<u32>,
+    /// Initial volume between 0.0 and 1.0, defaults to 1.0
+    pub volume: Option<f64>,
+    /// Initial mute state, defaults to false
+    pub muted: Option<bool>,
+    /// Initial volume of each channel between 0.0 and 1.0, taking precedence
+    /// over `volume`. Defaults to two channels at `volume`
+    pub channel_volumes: Option<Vec<f64>>,
+}
+
+impl FakeAudioSession {
+    /// Fills in the initial volume and mute state when not given
+    fn with_defaults(self) -> Self {
+        let volume = self.volume.unwrap_or(1.0);
+        FakeAudioSession {
+            channel_volumes: Some(initial_levels(self.channel_volumes, volume, 2)),
+            muted: Some(self.muted.unwrap_or(false)),
+            ..self
+        }
+    }
+
+    fn levels(&mut self) -> &mut Vec<f64> {
+        self.channel_volumes.get_or_insert_with(Vec::new)
+    }
+}
+
+/// The volume of each channel to start from, every level kept between 0.0
+/// and 1.0
+fn initial_levels(levels: Option<Vec<f64>>, volume: f64, channels: u32) -> Vec<f64> {
+    levels
+        .unwrap_or_else(|| vec![volume; channels as usize])
+        .into_iter()
+        .map(|level| level.clamp(0.0, 1.0))
+        .collect()
+}
+
+/// The volume level of a device or application, which is the level of its
+/// loudest channel
+fn loudest(levels: &[f64]) -> f64 {
+    levels.iter().copied().fold(0.0, f64::max)
+}
+
+/// Moves the loudest channel to `level`, keeping the balance between the
+/// channels
+fn scale_levels(levels: &mut [f64], level: f64) {
+    let current = loudest(levels);
+    for channel in levels {
+        *channel = if current == 0.0 { level } else { *channel * (level / current) };
+    }
+}
+
+/// The fake backend scales volumes like PulseAudio, whose levels are the
+/// cube root of the amplitude
+const SCALE: VolumeCurve = VolumeCurve::Cubic;
+
+/// Pairs the levels of the channels with their positions
+fn channel_volumes(levels: &[f64]) -> Vec<ChannelVolume> {
+    ChannelPosition::layout(levels.len())
+        .into_iter()
+        .zip(levels)
+        .map(|(position, &volume)| ChannelVolume { position, volume })
+        .collect()
+}
+
+/// An output or input device of the fake backend
+#[napi(object)]
+#[derive(Clone)]
+pub struct FakeAudioDevice {
+    /// Id the device is addressed by
+    pub id: String,
+    /// The name to show for the device
+    pub name: String,
+    /// What kind of device it is
+    pub description: Option<String>,
+    /// Whether the device plays or records audio
+    pub direction: DeviceDirection,
+    /// Whether the device can be used, defaults to active
+    pub state: Option<DeviceState>,
+    /// Whether the device starts as the default for its direction, which
+    /// is otherwise the first device of that direction
+    pub is_default: Option<bool>,
+    /// Number of channels, defaults to 2
+    pub channels: Option<u32>,
+    /// Sample rate in Hz, defaults to 48000
+    pub sample_rate: Option<u32>,
+    /// Initial volume between 0.0 and 1.0, defaults to 0.5
+    pub volume: Option<f64>,
+    /// Initial mute state, defaults to false
+    pub muted: Option<bool>,
+    /// Initial volume of each channel between 0.0 and 1.0, taking precedence
+    /// over `volume` and `channels`
+    pub channel_volumes: Option<Vec<f64>>,
+    /// Number of volume steps the device reports, for a device that cannot
+    /// be set to any volume
+    pub volume_steps: Option<u32>,
+}
+
+impl FakeAudioDevice {
+    /// The devices used when the scenario gives none
+    fn builtin() -> Vec<Self> {
+        let device = |id: &str, name: &str, direction| FakeAudioDevice {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: None,
+            direction,
+            state: None,
+            is_default: None,
+            channels: None,
+            sample_rate: None,
+            volume: None,
+            muted: None,
+            channel_volumes: None,
+            volume_steps: None,
+        };
+        vec![
+            device("fake-speakers", "Fake Speakers", DeviceDirection::Output),
+            device("fake-microphone", "Fake Microphone", DeviceDirection::Input),
+        ]
+    }
+
+    /// Fills in the initial volume and mute state when not given
+    fn with_defaults(self) -> Self {
+        let levels = initial_levels(self.channel_volumes, self.volume.unwrap_or(0.5), self.channels.unwrap_or(2));
+        FakeAudioDevice {
+            channels: Some(levels.len() as u32),
+            channel_volumes: Some(levels),
+            muted: Some(self.muted.unwrap_or(false)),
+            ..self
+        }
+    }
+
+    fn levels(&mut self) -> &mut Vec<f64> {
+        self.channel_volumes.get_or_insert_with(Vec::new)
+    }
+
+    fn range(&self) -> VolumeRange {
+        VolumeRange::software(self.volume_steps)
+    }
+}
+
+/// Picks the default device of a direction, as described by
+/// [`FakeAudioDevice::is_default`]
+fn initial_default(devices: &[FakeAudioDevice], direction: DeviceDirection) -> Option<String> {
+    let mut candidates = devices.iter().filter(|device| device.direction == direction);
+    candidates
+        .clone()
+        .find(|device| device.is_default == Some(true))
+        .or_else(|| candidates.next())
+        .map(|device| device.id.clone())
+}
+
+/// A call the fake backend fails instead of carrying out
+#[napi(object)]
+#[derive(Clone)]
+pub struct FakeFailure {
+    /// Name of the exported function whose request to the backend fails.
+    /// `getMediaInfo` also fails the getters reading from the media info, the
+    /// channel volume calls the balance functions built on them, and the dB
+    /// calls the volume functions while a volume curve is set
+    #[napi(
+        ts_type = "'listMediaSessions' | 'getMediaInfo' | 'getThumbnail' | 'play' | 'pause' | 'next' | 'previous' | 'stop' | 'seek' | 'setPosition' | 'setShuffle' | 'setRepeatMode' | 'setPlaybackRate' | 'getSystemVolume' | 'setSystemVolume' | 'getSystemMute' | 'setSystemMute' | 'getInputVolume' | 'setInputVolume' | 'getInputMute' | 'setInputMute' | 'getChannelVolumes' | 'setChannelVolumes' | 'getVolumeRange' | 'getVolumeDb' | 'setVolumeDb' | 'listAudioDevices' | 'setDefaultDevice' | 'listAudioSessions' | 'getSessionVolume' | 'setSessionVolume' | 'getSessionMute' | 'setSessionMute' | 'getSessionChannelVolumes' | 'setSessionChannelVolumes' | 'getSessionVolumeRange' | 'getSessionVolumeDb' | 'setSessionVolumeDb'"
+    )]
+    pub call: String,
+    /// Why the call fails, defaults to `ERR_PLATFORM`
+    pub status: Option<ErrorStatus>,
+    /// Native code reported with the error
+    pub native_code: Option<i64>,
+    /// Message of the error
+    pub message: Option<String>,
+    /// How many calls fail before the call works again, every call failing
+    /// when not given
+    pub times: Option<u32>,
+}
+
+/// A change applied by the fake backend once its clock reaches `atMs`
+#[napi(object)]
+#[derive(Clone)]
+pub struct FakeStep {
+    /// Milliseconds after `useBackend` at which the step applies
+    pub at_ms: f64,
+    /// Session the media changes apply to, defaults to the current session
+    pub session_id: Option<String>,
+    /// Replaces the metadata of the session
+    pub media_info: Option<MediaInfo>,
+    /// Replaces only the playback status of the session
+    pub playback_status: Option<String>,
+    /// Replaces the thumbnail of the session
+    pub thumbnail: Option<Vec<u8>>,
+    /// Makes another session the current one
+    pub current_session_id: Option<String>,
+    /// Media sessions that show up
+    pub add_sessions: Option<Vec<FakeSession>>,
+    /// Ids of media sessions that go away
+    pub remove_sessions: Option<Vec<String>>,
+    /// Applications that start playing audio
+    pub add_audio_sessions: Option<Vec<FakeAudioSession>>,
+    /// Ids of audio sessions that go away
+    pub remove_audio_sessions: Option<Vec<String>>,
+    /// New volume of the default output device between 0.0 and 1.0
+    pub volume: Option<f64>,
+    /// New mute state of the default output device
+    pub muted: Option<bool>,
+}
+
+/// Initial state and timeline of the fake backend
+#[napi(object)]
+#[derive(Clone, Default)]
+pub struct FakeScenario {
+    /// Sessions present from the start
+    pub sessions: Option<Vec<FakeSession>>,
+    /// Id of the current session, defaults to the first session
+    pub current_session_id: Option<String>,
+    /// Initial volume of the default output device, overriding its own
+    pub volume: Option<f64>,
+    /// Initial mute state of the default output device, overriding its own
+    pub muted: Option<bool>,
+    /// Output and input devices, defaults to one speaker and one microphone
+    pub devices: Option<Vec<FakeAudioDevice>>,
+    /// Applications playing audio from the start
+    pub audio_sessions: Option<Vec<FakeAudioSession>>,
+    /// Changes to apply over time
+    pub steps: Option<Vec<FakeStep>>,
+    /// Calls that fail, to see how callers deal with platform errors
+    pub failures: Option<Vec<FakeFailure>>,
+    /// Whether the clock follows real time (default) or only moves through
+    /// `advanceFakeClock`
+    pub realtime: Option<bool>,
+}
+
+struct SessionState {
+    session: FakeSession,
+    track: usize,
+}
+
+struct FakeState {
+    sessions: Vec<SessionState>,
+    current: Option<String>,
+    devices: Vec<FakeAudioDevice>,
+    default_output: Option<String>,
+    default_input: Option<String>,
+    audio_sessions: Vec<FakeAudioSession>,
+    steps: VecDeque<FakeStep>,
+    failures: Vec<FakeFailure>,
+    started: Instant,
+    realtime: bool,
+    advanced: Duration,
+}
+
+impl FakeState {
+    fn elapsed_ms(&self) -> f64 {
+        let real = if self.realtime { self.started.elapsed() } else { Duration::ZERO };
+        real.saturating_add(self.advanced).as_secs_f64() * 1000.0
+    }
+
+    fn session_mut(&mut self, session_id: &str) -> Option<&mut SessionState> {
+        self.sessions.iter_mut().find(|state| state.session.id == session_id)
+    }
+
+    fn existing_session(&mut self, session_id: &str) -> Result<&mut SessionState> {
+        self.session_mut(session_id).ok_or_else(|| Error::no_session(session_id))
+    }
+
+    fn audio_session_mut(&mut self, session_id: &str) -> Result<&mut FakeAudioSession> {
+        self.audio_sessions
+            .iter_mut()
+            .find(|session| session.id == session_id)
+            .ok_or_else(|| Error::no_session(session_id))
+    }
+
+    /// Finds a device by its id, or the default device of a direction
+    fn device_mut(&mut self, device: DeviceRef) -> Result<&mut FakeAudioDevice> {
+        let device_id = match device {
+            DeviceRef::Id(device_id) => Some(device_id),
+            DeviceRef::Default(DeviceDirection::Output) => self.default_output.as_deref(),
+            DeviceRef::Default(DeviceDirection::Input) => self.default_input.as_deref(),
+        };
+        let index = self
+            .devices
+            .iter()
+            .position(|candidate| Some(candidate.id.as_str()) == device_id)
+            .ok_or_else(|| device.missing())?;
+        Ok(&mut self.devices[index])
+    }
+
+    /// Finds a device, failing with the first of `calls` for an output and
+    /// the second for an input while a failure for it is left
+    fn checked_device(&mut self, device: DeviceRef, calls: [&str; 2]) -> Result<&mut FakeAudioDevice> {
+        let call = match self.device_mut(device)?.direction {
+            DeviceDirection::Output => calls[0],
+            DeviceDirection::Input => calls[1],
+        };
+        self.check(call)?;
+        self.device_mut(device)
+    }
+
+    /// Fails `call` while a failure for it is left
+    fn check(&mut self, call: &str) -> Result<()> {
+        let failure = match self.failures.iter_mut().find(|failure| failure.call == call && failure.times != Some(0)) {
+            Some(failure) => failure,
+            None => return Ok(()),
+        };
+        if let Some(times) = failure.times.as_mut() {
+            *times -= 1;
+        }
+
+        Err(Error {
+            status: failure.status.unwrap_or(ErrorStatus::PlatformError),
+            message: failure.message.clone().unwrap_or_else(|| format!("{call} failed")),
+            native_code: failure.native_code,
+        })
+    }
+
+    /// Applies every step whose time has come, in order
+    fn apply_due_steps(&mut self) {
+        let now = self.elapsed_ms();
+        while self.steps.front().is_some_and(|step| step.at_ms <= now) {
+            let step = self.steps.pop_front().unwrap();
+
+            if let Some(current) = step.current_session_id {
+                self.current = Some(current);
+            }
+            if let Ok(device) = self.device_mut(DeviceRef::Default(DeviceDirection::Output)) {
+                if let Some(volume) = step.volume {
+                    scale_levels(device.levels(), volume.clamp(0.0, 1.0));
+                }
+                if let Some(muted) = step.muted {
+                    device.muted = Some(muted);
+                }
+            }
+            if let Some(removed) = step.remove_sessions {
+                self.sessions.retain(|state| !removed.contains(&state.session.id));
+            }
+            if let Some(added) = step.add_sessions {
+                self.sessions.extend(added.into_iter().map(|session| SessionState { session, track: 0 }));
+            }
+            if let Some(removed) = step.remove_audio_sessions {
+                self.audio_sessions.retain(|session| !removed.contains(&session.id));
+            }
+            if let Some(added) = step.add_audio_sessions {
+                self.audio_sessions.extend(added.into_iter().map(FakeAudioSession::with_defaults));
+            }
+
+            let target = step.session_id.or_else(|| self.current.clone());
+            if let Some(state) = target.and_then(|id| self.session_mut(&id)) {
+                if let Some(media_info) = step.media_info {
+                    state.session.media_info = media_info;
+                }
+                if let Some(status) = step.playback_status {
+                    state.session.media_info.playback_status = status;
+                }
+                if let Some(thumbnail) = step.thumbnail {
+                    state.session.thumbnail = Some(thumbnail);
+                }
+            }
+        }
+    }
+}
+
+/// Applies a transport command to a session, failing when it does not
+/// accept it
+fn apply_command(state: &mut SessionState, command: TransportCommand) -> Result<()> {
+    let capabilities = state.session.capabilities();
+    let enabled = match command {
+        TransportCommand::Play => capabilities.play,
+        TransportCommand::Pause => capabilities.pause,
+        TransportCommand::Stop => capabilities.stop,
+        TransportCommand::Next => capabilities.next,
+        TransportCommand::Previous => capabilities.previous,
+    };
+    if !enabled {
+        return Err(Error::not_supported(format!("The session does not accept {command:?}")));
+    }
+
+    let info = &mut state.session.media_info;
+    match command {
+        TransportCommand::Play => info.playback_status = "playing".to_string(),
+        TransportCommand::Pause => info.playback_status = "paused".to_string(),
+        TransportCommand::Stop => info.playback_status = "stopped".to_string(),
+        TransportCommand::Next | TransportCommand::Previous => {
+            let playlist = match &state.session.playlist {
+                Some(playlist) if !playlist.is_empty() => playlist,
+                _ => return Ok(()),
+            };
+            state.track = if command == TransportCommand::Next {
+                (state.track + 1).min(playlist.len() - 1)
+            } else {
+                state.track.saturating_sub(1)
+            };
+
+            let status = std::mem::take(&mut info.playback_status);
+            *info = playlist[state.track].clone();
+            info.playback_status = status;
+        }
+    }
+    Ok(())
+}
+
+/// In-memory backend driven by a [`FakeScenario`].
+///
+/// Nothing here touches the system, which makes it suitable for tests and UI
+/// demos. Steps are applied lazily whenever the state is read.
+pub struct FakeBackend {
+    state: Mutex<FakeState>,
+    watchers: Mutex<Vec<Watcher>>,
+}
+
+impl FakeBackend {
+    pub fn new(scenario: FakeScenario) -> Self {
+        let sessions: Vec<SessionState> = scenario
+            .sessions
+            .unwrap_or_default()
+            .into_iter()
+            .map(|session| SessionState { session, track: 0 })
+            .collect();
+
+        let mut steps = scenario.steps.unwrap_or_default();
+        steps.sort_by(|a, b| a.at_ms.total_cmp(&b.at_ms));
+
+        let audio_sessions = scenario
+            .audio_sessions
+            .unwrap_or_default()
+            .into_iter()
+            .map(FakeAudioSession::with_defaults)
+            .collect();
+
+        let current = scenario
+            .current_session_id
+            .or_else(|| sessions.first().map(|state| state.session.id.clone()));
+
+        let mut devices: Vec<FakeAudioDevice> = scenario
+            .devices
+            .unwrap_or_else(FakeAudioDevice::builtin)
+            .into_iter()
+            .map(FakeAudioDevice::with_defaults)
+            .collect();
+        let default_output = initial_default(&devices, DeviceDirection::Output);
+        let default_input = initial_default(&devices, DeviceDirection::Input);
+        if let Some(output) = devices.iter_mut().find(|device| Some(&device.id) == default_output.as_ref()) {
+            if let Some(volume) = scenario.volume {
+                scale_levels(output.levels(), volume.clamp(0.0, 1.0));
+            }
+            output.muted = scenario.muted.or(output.muted);
+        }
+
+        Self {
+            state: Mutex::new(FakeState {
+                sessions,
+                current,
+                devices,
+                default_output,
+                default_input,
+                audio_sessions,
+                steps: steps.into(),
+                failures: scenario.failures.unwrap_or_default(),
+                started: Instant::now(),
+                realtime: scenario.realtime.unwrap_or(true),
+                advanced: Duration::ZERO,
+            }),
+            watchers: Mutex::new(vec![]),
+        }
+    }
+
+    /// Moves the clock forward, applying any steps that become due. Amounts
+    /// too large for a `Duration` move it as far as it goes.
+    pub fn advance(&self, ms: f64) -> Result<()> {
+        if !ms.is_finite() {
+            return Err(Error::invalid_argument(format!("The clock can only advance by a finite number of milliseconds, got {ms}")));
+        }
+        let mut state = self.lock();
+        let by = Duration::try_from_secs_f64(ms.max(0.0) / 1000.0).unwrap_or(Duration::MAX);
+        state.advanced = state.advanced.saturating_add(by);
+        state.apply_due_steps();
+        drop(state);
+        self.notify_watchers();
+        Ok(())
+    }
+
+    /// Notifies `watcher` whenever a pending step comes due on the real clock.
+    /// Steps brought forward by [`FakeBackend::advance`] notify from there.
+    fn watch_steps(&self, watcher: Watcher) {
+        let state = self.lock();
+        if !state.realtime || state.steps.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let elapsed_ms = state.elapsed_ms();
+        // Steps too far out to ever come due on the real clock are left to
+        // `advance`
+        let deadlines: Vec<Instant> = state
+            .steps
+            .iter()
+            .map_while(|step| {
+                let remaining = Duration::try_from_secs_f64((step.at_ms - elapsed_ms).max(0.0) / 1000.0).ok()?;
+                now.checked_add(remaining)
+            })
+            .collect();
+        drop(state);
+
+        std::thread::spawn(move || {
+            for deadline in deadlines {
+                loop {
+                    if !watcher.is_active() {
+                        return;
+                    }
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        break;
+                    }
+                    std::thread::sleep(remaining.min(WATCHER_CHECK_INTERVAL));
+                }
+                watcher.notify();
+            }
+        });
+    }
+
+    /// Tells every subscription watching this backend that its state changed
+    fn notify_watchers(&self) {
+        let mut watchers = self.watchers.lock().unwrap_or_else(|err| err.into_inner());
+        watchers.retain(Watcher::notify);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FakeState> {
+        // The state stays consistent even if a holder panicked
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.apply_due_steps();
+        state
+    }
+}
+
+impl FakeBackend {
+    /// Applies `update` to the media info of a session, failing when the
+    /// session is missing or its capabilities do not allow the `control`
+    fn update_media_info(
+        &self,
+        call: &str,
+        session_id: &str,
+        control: &str,
+        enabled: impl FnOnce(&MediaCapabilities) -> bool,
+        update: impl FnOnce(&mut MediaInfo),
+    ) -> Result<()> {
+        self.mutate(|state| {
+            state.check(call)?;
+            let session = &mut state.existing_session(session_id)?.session;
+            if !enabled(&session.capabilities()) {
+                return Err(Error::not_supported(format!("The session does not accept {control}")));
+            }
+            update(&mut session.media_info);
+            Ok(())
+        })
+    }
+
+    /// Moves the position of a session to `position_ms`, kept within the
+    /// duration of its media
+    fn move_position(&self, call: &str, session_id: &str, position_ms: impl FnOnce(f64) -> f64) -> Result<()> {
+        self.update_media_info(call, session_id, "seeking", |caps| caps.seek, |info| {
+            let duration = info.duration_ms.unwrap_or(f64::INFINITY);
+            info.position_ms = Some(position_ms(info.position_ms.unwrap_or(0.0)).clamp(0.0, duration));
+            info.last_updated = Some(unix_now_ms());
+        })
+    }
+
+    /// Runs `mutation` on the state, then tells the subscriptions watching
+    /// this backend to look at it again if the mutation succeeded
+    fn mutate<T>(&self, mutation: impl FnOnce(&mut FakeState) -> Result<T>) -> Result<T> {
+        let result = mutation(&mut self.lock());
+        if result.is_ok() {
+            self.notify_watchers();
+        }
+        result
+    }
+}
+
+impl MediaBackend for FakeBackend {
+    fn current_session(&self) -> Result<Option<String>> {
+        let state = self.lock();
+        Ok(state
+            .current
+            .clone()
+            .filter(|id| state.sessions.iter().any(|s| &s.session.id == id)))
+    }
+
+    fn list_sessions(&self) -> Result<Vec<String>> {
+        let mut state = self.lock();
+        state.check("listMediaSessions")?;
+        Ok(state.sessions.iter().map(|state| state.session.id.clone()).collect())
+    }
+
+    fn source_app_id(&self, session_id: &str) -> Option<String> {
+        let mut state = self.lock();
+        let session = &state.session_mut(session_id)?.session;
+        Some(session.source_app_id.clone().unwrap_or_else(|| session.id.clone()))
+    }
+
+    fn media_info(&self, session_id: &str) -> Result<MediaInfo> {
+        let mut state = self.lock();
+        state.check("getMediaInfo")?;
+        let session = &state.existing_session(session_id)?.session;
+        let mut info = session.media_info.clone();
+        if info.source_app_id.is_none() {
+            info.source_app_id = Some(session.source_app_id.clone().unwrap_or_else(|| session.id.clone()));
+        }
+        info.capabilities = Some(session.capabilities());
+        Ok(info)
+    }
+
+    fn thumbnail(&self, session_id: &str) -> Result<Option<Vec<u8>>> {
+        let mut state = self.lock();
+        state.check("getThumbnail")?;
+        Ok(state.existing_session(session_id)?.session.thumbnail.clone())
+    }
+
+    fn send_command(&self, session_id: &str, command: TransportCommand) -> Result<()> {
+        let call = match command {
+            TransportCommand::Play => "play",
+            TransportCommand::Pause => "pause",
+            TransportCommand::Next => "next",
+            TransportCommand::Previous => "previous",
+            TransportCommand::Stop => "stop",
+        };
+        self.mutate(|state| {
+            state.check(call)?;
+            apply_command(state.existing_session(session_id)?, command)
+        })
+    }
+
+    fn seek(&self, session_id: &str, offset_ms: f64) -> Result<()> {
+        self.move_position("seek", session_id, |position| position + offset_ms)
+    }
+
+    fn set_position(&self, session_id: &str, position_ms: f64) -> Result<()> {
+        self.move_position("setPosition", session_id, |_| position_ms)
+    }
+
+    fn set_shuffle(&self, session_id: &str, shuffle: bool) -> Result<()> {
+        self.update_media_info("setShuffle", session_id, "shuffle", |caps| caps.shuffle, |info| info.shuffle = Some(shuffle))
+    }
+
+    fn set_repeat_mode(&self, session_id: &str, mode: RepeatMode) -> Result<()> {
+        self.update_media_info("setRepeatMode", session_id, "repeat modes", |caps| caps.repeat, |info| info.repeat_mode = Some(mode))
+    }
+
+    fn set_playback_rate(&self, session_id: &str, rate: f64) -> Result<()> {
+        self.update_media_info("setPlaybackRate", session_id, "playback rates", |caps| caps.rate, |info| info.playback_rate = Some(rate))
+    }
+
+    fn watch(&self, watcher: Watcher) -> bool {
+        self.watch_steps(watcher.clone());
+        self.watchers.lock().unwrap_or_else(|err| err.into_inner()).push(watcher);
+        true
+    }
+}
+
+impl AudioBackend for FakeBackend {
+    fn device_id(&self, device: DeviceRef) -> Result<String> {
+        Ok(self.lock().device_mut(device)?.id.clone())
+    }
+
+    fn volume(&self, device: DeviceRef) -> Result<f64> {
+        let mut state = self.lock();
+        let device = state.checked_device(device, ["getSystemVolume", "getInputVolume"])?;
+        Ok(loudest(device.levels()))
+    }
+
+    fn set_volume(&self, device: DeviceRef, level: f64) -> Result<()> {
+        self.mutate(|state| {
+            scale_levels(state.checked_device(device, ["setSystemVolume", "setInputVolume"])?.levels(), level);
+            Ok(())
+        })
+    }
+
+    fn muted(&self, device: DeviceRef) -> Result<bool> {
+        let mut state = self.lock();
+        let device = state.checked_device(device, ["getSystemMute", "getInputMute"])?;
+        Ok(device.muted.unwrap_or(false))
+    }
+
+    fn set_muted(&self, device: DeviceRef, muted: bool) -> Result<()> {
+        self.mutate(|state| {
+            state.checked_device(device, ["setSystemMute", "setInputMute"])?.muted = Some(muted);
+            Ok(())
+        })
+    }
+
+    fn channel_volumes(&self, device: DeviceRef) -> Result<Vec<ChannelVolume>> {
+        let mut state = self.lock();
+        state.check("getChannelVolumes")?;
+        Ok(channel_volumes(state.device_mut(device)?.levels()))
+    }
+
+    fn set_channel_volumes(&self, device: DeviceRef, levels: &[f64]) -> Result<()> {
+        self.mutate(|state| {
+            state.check("setChannelVolumes")?;
+            let channels = state.device_mut(device)?.levels();
+            channels::check_levels(channels.len(), levels)?;
+            channels.copy_from_slice(levels);
+            Ok(())
+        })
+    }
+
+    fn volume_range(&self, device: DeviceRef) -> Result<VolumeRange> {
+        let mut state = self.lock();
+        state.check("getVolumeRange")?;
+        Ok(state.device_mut(device)?.range())
+    }
+
+    fn volume_db(&self, device: DeviceRef) -> Result<f64> {
+        let mut state = self.lock();
+        state.check("getVolumeDb")?;
+        let device = state.device_mut(device)?;
+        Ok(SCALE.db(loudest(device.levels()), &device.range()))
+    }
+
+    fn set_volume_db(&self, device: DeviceRef, db: f64) -> Result<()> {
+        self.mutate(|state| {
+            state.check("setVolumeDb")?;
+            let device = state.device_mut(device)?;
+            let level = SCALE.level(db, &device.range());
+            scale_levels(device.levels(), level);
+            Ok(())
+        })
+    }
+
+    fn list_devices(&self, direction: Option<DeviceDirection>) -> Result<Vec<AudioDevice>> {
+        let mut state = self.lock();
+        state.check("listAudioDevices")?;
+        Ok(state
+            .devices
+            .iter()
+            .filter(|device| direction.is_none_or(|direction| device.direction == direction))
+            .map(|device| {
+                let default = match device.direction {
+                    DeviceDirection::Output => &state.default_output,
+                    DeviceDirection::Input => &state.default_input,
+                };
+                let is_default = default.as_ref() == Some(&device.id);
+                AudioDevice {
+                    id: device.id.clone(),
+                    name: device.name.clone(),
+                    description: device.description.clone(),
+                    direction: device.direction,
+                    state: device.state.unwrap_or(DeviceState::Active),
+                    is_default,
+                    is_default_communication: is_default,
+                    channels: device.channel_volumes.as_ref().map(|levels| levels.len() as u32),
+                    sample_rate: Some(device.sample_rate.unwrap_or(48000)),
+                }
+            })
+            .collect())
+    }
+
+    fn set_default_device(&self, device_id: &str) -> Result<()> {
+        self.mutate(|state| {
+            state.check("setDefaultDevice")?;
+            let device = state.device_mut(DeviceRef::Id(device_id))?;
+            let id = Some(device.id.clone());
+            match device.direction {
+                DeviceDirection::Output => state.default_output = id,
+                DeviceDirection::Input => state.default_input = id,
+            }
+            Ok(())
+        })
+    }
+
+    fn list_sessions(&self) -> Result<Vec<AudioSession>> {
+        let mut state = self.lock();
+        state.check("listAudioSessions")?;
+        Ok(state
+            .audio_sessions
+            .iter()
+            .map(|session| AudioSession {
+                id: session.id.clone(),
+                name: session.name.clone(),
+                pid: session.pid,
+            })
+            .collect())
+    }
+
+    fn session_volume(&self, session_id: &str) -> Result<f64> {
+        let mut state = self.lock();
+        state.check("getSessionVolume")?;
+        Ok(loudest(state.audio_session_mut(session_id)?.levels()))
+    }
+
+    fn set_session_volume(&self, session_id: &str, level: f64) -> Result<()> {
+        self.mutate(|state| {
+            state.check("setSessionVolume")?;
+            scale_levels(state.audio_session_mut(session_id)?.levels(), level);
+            Ok(())
+        })
+    }
+
+    fn session_muted(&self, session_id: &str) -> Result<bool> {
+        let mut state = self.lock();
+        state.check("getSessionMute")?;
+        Ok(state.audio_session_mut(session_id)?.muted.unwrap_or(false))
+    }
+
+    fn set_session_muted(&self, session_id: &str, muted: bool) -> Result<()> {
+        self.mutate(|state| {
+            state.check("setSessionMute")?;
+            state.audio_session_mut(session_id)?.muted = Some(muted);
+            Ok(())
+        })
+    }
+
+    fn session_channel_volumes(&self, session_id: &str) -> Result<Vec<ChannelVolume>> {
+        let mut state = self.lock();
+        state.check("getSessionChannelVolumes")?;
+        Ok(channel_volumes(state.audio_session_mut(session_id)?.levels()))
+    }
+
+    fn set_session_channel_volumes(&self, session_id: &str, levels: &[f64]) -> Result<()> {
+        self.mutate(|state| {
+            state.check("setSessionChannelVolumes")?;
+            let channels = state.audio_session_mut(session_id)?.levels();
+            channels::check_levels(channels.len(), levels)?;
+            channels.copy_from_slice(levels);
+            Ok(())
+        })
+    }
+
+    fn session_volume_range(&self, session_id: &str) -> Result<VolumeRange> {
+        let mut state = self.lock();
+        state.check("getSessionVolumeRange")?;
+        state.audio_session_mut(session_id)?;
+        Ok(VolumeRange::software(None))
+    }
+
+    fn session_volume_db(&self, session_id: &str) -> Result<f64> {
+        let mut state = self.lock();
+        state.check("getSessionVolumeDb")?;
+        let level = loudest(state.audio_session_mut(session_id)?.levels());
+        Ok(SCALE.db(level, &VolumeRange::software(None)))
+    }
+
+    fn set_session_volume_db(&self, session_id: &str, db: f64) -> Result<()> {
+        self.mutate(|state| {
+            state.check("setSessionVolumeDb")?;
+            let level = SCALE.level(db, &VolumeRange::software(None));
+            scale_levels(state.audio_session_mut(session_id)?.levels(), level);
+            Ok(())
+        })
+    }
+
+    fn watch(&self, watcher: Watcher) -> bool {
+        self.watchers.lock().unwrap_or_else(|err| err.into_inner()).push(watcher);
+        true
+    }
+}
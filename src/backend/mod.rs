@@ -0,0 +1,409 @@
+//! Implementations of the exported functions.
+//!
+//! Media sessions are reached through the [`MediaBackend`] trait and audio
+//! devices and their volume through [`AudioBackend`], so the napi functions in `lib.rs`
+//! never talk to WinRT, Core Audio or D-Bus directly. Each platform module
+//! provides the implementations for its OS, and [`use_backend`] can swap
+//! them for the in-memory [`FakeBackend`] at runtime.
+//!
+//! Requests that fail report why through the crate's
+//! [`Error`](crate::error::Error), which `lib.rs` hands on to JavaScript.
+//!
+//! Backends also push change notifications to event subscriptions through a
+//! [`Watcher`], which falls back to polling where a platform offers no way
+//! to be notified.
+
+use std::{
+    sync::{Arc, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use crate::{
+    error::{Error, ErrorStatus, Result},
+    AudioDevice, AudioSession, ChannelVolume, DeviceDirection, MediaInfo, RepeatMode, VolumeRange,
+};
+
+mod fake;
+mod watch;
+#[cfg(windows)]
+mod windows;
+#[cfg(target_os = "linux")]
+mod mpris;
+#[cfg(target_os = "linux")]
+mod pulse;
+
+pub use fake::{FakeAudioDevice, FakeAudioSession, FakeBackend, FakeFailure, FakeScenario, FakeSession, FakeStep};
+pub use watch::{generation, ChangeSignal, Watcher};
+
+/// A transport command that can be sent to a media session
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransportCommand {
+    Play,
+    Pause,
+    Next,
+    Previous,
+    Stop,
+}
+
+/// The device a volume or mute request is for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceRef<'a> {
+    /// Whichever device is the default of a direction
+    Default(DeviceDirection),
+    /// A device by the id `listAudioDevices` gives it
+    Id(&'a str),
+}
+
+impl<'a> DeviceRef<'a> {
+    /// The device with `device_id`, or the default device of `direction`
+    pub fn new(device_id: Option<&'a str>, direction: DeviceDirection) -> Self {
+        device_id.map_or(DeviceRef::Default(direction), DeviceRef::Id)
+    }
+
+    /// The error for a device that does not exist
+    pub fn missing(&self) -> Error {
+        match self {
+            DeviceRef::Default(DeviceDirection::Output) => {
+                Error::new(ErrorStatus::NoSession, "There is no default output device")
+            }
+            DeviceRef::Default(DeviceDirection::Input) => {
+                Error::new(ErrorStatus::NoSession, "There is no default input device")
+            }
+            DeviceRef::Id(device_id) => Error::no_device(device_id),
+        }
+    }
+}
+
+/// Current time in milliseconds since the Unix epoch, the unit of
+/// [`MediaInfo::last_updated`]
+pub fn unix_now_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs_f64() * 1000.0)
+        .unwrap_or_default()
+}
+
+/// A source of media sessions, such as GSMTC on Windows or MPRIS on Linux.
+///
+/// Sessions are addressed by an opaque id that stays the same for as long
+/// as the session exists.
+pub trait MediaBackend: Send + Sync {
+    /// Id of the session the platform considers current, if there is one
+    fn current_session(&self) -> Result<Option<String>>;
+    /// Ids of every session known to the platform
+    fn list_sessions(&self) -> Result<Vec<String>>;
+    /// Id of the application that owns a session
+    fn source_app_id(&self, session_id: &str) -> Option<String>;
+    /// Metadata and playback status of a session
+    fn media_info(&self, session_id: &str) -> Result<MediaInfo>;
+    /// Raw bytes of the artwork of a session, if it has any
+    fn thumbnail(&self, session_id: &str) -> Result<Option<Vec<u8>>>;
+    /// Sends a transport command, failing when the session refuses it
+    fn send_command(&self, session_id: &str, command: TransportCommand) -> Result<()>;
+    /// Moves the playback position by `offset_ms`, which may be negative
+    fn seek(&self, session_id: &str, offset_ms: f64) -> Result<()>;
+    /// Moves the playback position to `position_ms` from the start
+    fn set_position(&self, session_id: &str, position_ms: f64) -> Result<()>;
+    /// Turns shuffle on or off
+    fn set_shuffle(&self, session_id: &str, shuffle: bool) -> Result<()>;
+    /// Changes the repeat mode
+    fn set_repeat_mode(&self, session_id: &str, mode: RepeatMode) -> Result<()>;
+    /// Changes the playback speed
+    fn set_playback_rate(&self, session_id: &str, rate: f64) -> Result<()>;
+    /// Starts reporting changes of any session to `watcher` until it goes
+    /// inactive. Returns false when the platform can only be polled.
+    fn watch(&self, watcher: Watcher) -> bool;
+}
+
+/// Control over the audio devices and their volume, and over the
+/// applications playing through the default output device
+pub trait AudioBackend: Send + Sync {
+    /// Id of the device, which tells which one is the default
+    fn device_id(&self, device: DeviceRef) -> Result<String>;
+    /// Volume level between 0.0 and 1.0
+    fn volume(&self, device: DeviceRef) -> Result<f64>;
+    /// Sets the volume level
+    fn set_volume(&self, device: DeviceRef, level: f64) -> Result<()>;
+    /// Whether the device is muted
+    fn muted(&self, device: DeviceRef) -> Result<bool>;
+    /// Sets the mute state
+    fn set_muted(&self, device: DeviceRef, muted: bool) -> Result<()>;
+    /// Volume of each channel, the loudest of which is the volume level
+    fn channel_volumes(&self, device: DeviceRef) -> Result<Vec<ChannelVolume>>;
+    /// Sets the volume of each channel, in the order of `channel_volumes`
+    fn set_channel_volumes(&self, device: DeviceRef, levels: &[f64]) -> Result<()>;
+    /// Volumes in dB the device can be set to
+    fn volume_range(&self, device: DeviceRef) -> Result<VolumeRange>;
+    /// Volume in dB, negative infinity when silent
+    fn volume_db(&self, device: DeviceRef) -> Result<f64>;
+    /// Sets the volume in dB, going no lower than the device can
+    fn set_volume_db(&self, device: DeviceRef, db: f64) -> Result<()>;
+    /// Output and input devices, or those of one direction only
+    fn list_devices(&self, direction: Option<DeviceDirection>) -> Result<Vec<AudioDevice>>;
+    /// Makes a device the default for its direction
+    fn set_default_device(&self, device_id: &str) -> Result<()>;
+    /// Applications currently playing audio
+    fn list_sessions(&self) -> Result<Vec<AudioSession>>;
+    /// Volume level of an application between 0.0 and 1.0
+    fn session_volume(&self, session_id: &str) -> Result<f64>;
+    /// Sets the volume level of an application
+    fn set_session_volume(&self, session_id: &str, level: f64) -> Result<()>;
+    /// Whether an application is muted
+    fn session_muted(&self, session_id: &str) -> Result<bool>;
+    /// Sets the mute state of an application
+    fn set_session_muted(&self, session_id: &str, muted: bool) -> Result<()>;
+    /// Volume of each channel of an application
+    fn session_channel_volumes(&self, session_id: &str) -> Result<Vec<ChannelVolume>>;
+    /// Sets the volume of each channel of an application
+    fn set_session_channel_volumes(&self, session_id: &str, levels: &[f64]) -> Result<()>;
+    /// Volumes in dB an application can be set to
+    fn session_volume_range(&self, session_id: &str) -> Result<VolumeRange>;
+    /// Volume of an application in dB, negative infinity when silent
+    fn session_volume_db(&self, session_id: &str) -> Result<f64>;
+    /// Sets the volume of an application in dB
+    fn set_session_volume_db(&self, session_id: &str, db: f64) -> Result<()>;
+    /// Starts reporting volume and mute changes to `watcher` until it goes
+    /// inactive. Returns false when the platform can only be polled.
+    fn watch(&self, watcher: Watcher) -> bool;
+}
+
+/// Backend for platforms without a media or audio implementation
+#[cfg(not(any(windows, target_os = "linux")))]
+struct UnsupportedBackend;
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn unsupported<T>() -> Result<T> {
+    Err(Error::not_supported("This platform is not supported"))
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+impl MediaBackend for UnsupportedBackend {
+    fn current_session(&self) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    fn list_sessions(&self) -> Result<Vec<String>> {
+        Ok(vec![])
+    }
+
+    fn source_app_id(&self, _session_id: &str) -> Option<String> {
+        None
+    }
+
+    fn media_info(&self, _session_id: &str) -> Result<MediaInfo> {
+        unsupported()
+    }
+
+    fn thumbnail(&self, _session_id: &str) -> Result<Option<Vec<u8>>> {
+        unsupported()
+    }
+
+    fn send_command(&self, _session_id: &str, _command: TransportCommand) -> Result<()> {
+        unsupported()
+    }
+
+    fn seek(&self, _session_id: &str, _offset_ms: f64) -> Result<()> {
+        unsupported()
+    }
+
+    fn set_position(&self, _session_id: &str, _position_ms: f64) -> Result<()> {
+        unsupported()
+    }
+
+    fn set_shuffle(&self, _session_id: &str, _shuffle: bool) -> Result<()> {
+        unsupported()
+    }
+
+    fn set_repeat_mode(&self, _session_id: &str, _mode: RepeatMode) -> Result<()> {
+        unsupported()
+    }
+
+    fn set_playback_rate(&self, _session_id: &str, _rate: f64) -> Result<()> {
+        unsupported()
+    }
+
+    fn watch(&self, _watcher: Watcher) -> bool {
+        false
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+impl AudioBackend for UnsupportedBackend {
+    fn device_id(&self, _device: DeviceRef) -> Result<String> {
+        unsupported()
+    }
+
+    fn volume(&self, _device: DeviceRef) -> Result<f64> {
+        unsupported()
+    }
+
+    fn set_volume(&self, _device: DeviceRef, _level: f64) -> Result<()> {
+        unsupported()
+    }
+
+    fn muted(&self, _device: DeviceRef) -> Result<bool> {
+        unsupported()
+    }
+
+    fn set_muted(&self, _device: DeviceRef, _muted: bool) -> Result<()> {
+        unsupported()
+    }
+
+    fn channel_volumes(&self, _device: DeviceRef) -> Result<Vec<ChannelVolume>> {
+        unsupported()
+    }
+
+    fn set_channel_volumes(&self, _device: DeviceRef, _levels: &[f64]) -> Result<()> {
+        unsupported()
+    }
+
+    fn volume_range(&self, _device: DeviceRef) -> Result<VolumeRange> {
+        unsupported()
+    }
+
+    fn volume_db(&self, _device: DeviceRef) -> Result<f64> {
+        unsupported()
+    }
+
+    fn set_volume_db(&self, _device: DeviceRef, _db: f64) -> Result<()> {
+        unsupported()
+    }
+
+    fn list_devices(&self, _direction: Option<DeviceDirection>) -> Result<Vec<AudioDevice>> {
+        Ok(vec![])
+    }
+
+    fn set_default_device(&self, _device_id: &str) -> Result<()> {
+        unsupported()
+    }
+
+    fn list_sessions(&self) -> Result<Vec<AudioSession>> {
+        Ok(vec![])
+    }
+
+    fn session_volume(&self, _session_id: &str) -> Result<f64> {
+        unsupported()
+    }
+
+    fn set_session_volume(&self, _session_id: &str, _level: f64) -> Result<()> {
+        unsupported()
+    }
+
+    fn session_muted(&self, _session_id: &str) -> Result<bool> {
+        unsupported()
+    }
+
+    fn set_session_muted(&self, _session_id: &str, _muted: bool) -> Result<()> {
+        unsupported()
+    }
+
+    fn session_channel_volumes(&self, _session_id: &str) -> Result<Vec<ChannelVolume>> {
+        unsupported()
+    }
+
+    fn set_session_channel_volumes(&self, _session_id: &str, _levels: &[f64]) -> Result<()> {
+        unsupported()
+    }
+
+    fn session_volume_range(&self, _session_id: &str) -> Result<VolumeRange> {
+        unsupported()
+    }
+
+    fn session_volume_db(&self, _session_id: &str) -> Result<f64> {
+        unsupported()
+    }
+
+    fn set_session_volume_db(&self, _session_id: &str, _db: f64) -> Result<()> {
+        unsupported()
+    }
+
+    fn watch(&self, _watcher: Watcher) -> bool {
+        false
+    }
+}
+
+/// The backend currently in use
+#[derive(Clone)]
+struct Backends {
+    media: Arc<dyn MediaBackend>,
+    audio: Arc<dyn AudioBackend>,
+    fake: Option<Arc<FakeBackend>>,
+}
+
+impl Backends {
+    fn system() -> Self {
+        #[cfg(windows)]
+        let media: Arc<dyn MediaBackend> = Arc::new(self::windows::GsmtcBackend);
+        #[cfg(target_os = "linux")]
+        let media: Arc<dyn MediaBackend> = Arc::new(self::mpris::MprisBackend);
+        #[cfg(not(any(windows, target_os = "linux")))]
+        let media: Arc<dyn MediaBackend> = Arc::new(UnsupportedBackend);
+
+        #[cfg(windows)]
+        let audio: Arc<dyn AudioBackend> = Arc::new(self::windows::CoreAudioBackend);
+        #[cfg(target_os = "linux")]
+        let audio: Arc<dyn AudioBackend> = Arc::new(self::pulse::PulseBackend);
+        #[cfg(not(any(windows, target_os = "linux")))]
+        let audio: Arc<dyn AudioBackend> = Arc::new(UnsupportedBackend);
+
+        Self { media, audio, fake: None }
+    }
+
+    fn fake(scenario: FakeScenario) -> Self {
+        let fake = Arc::new(FakeBackend::new(scenario));
+        Self {
+            media: fake.clone(),
+            audio: fake.clone(),
+            fake: Some(fake),
+        }
+    }
+}
+
+static BACKENDS: RwLock<Option<Backends>> = RwLock::new(None);
+
+fn backends() -> Backends {
+    if let Some(backends) = BACKENDS.read().unwrap_or_else(|err| err.into_inner()).as_ref() {
+        return backends.clone();
+    }
+
+    BACKENDS
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .get_or_insert_with(Backends::system)
+        .clone()
+}
+
+/// Returns the media backend in use
+pub fn media() -> Arc<dyn MediaBackend> {
+    backends().media
+}
+
+/// Returns the audio backend in use
+pub fn audio() -> Arc<dyn AudioBackend> {
+    backends().audio
+}
+
+/// Returns the fake backend, if it is the one in use
+pub fn fake() -> Option<Arc<FakeBackend>> {
+    backends().fake
+}
+
+/// Has the backends in use report their changes to `signal`, returning
+/// whether both of them can push changes rather than having to be polled
+pub fn watch(signal: &Arc<ChangeSignal>) -> bool {
+    let watcher = watch::watcher(signal);
+    let backends = backends();
+    let media = backends.media.watch(watcher.clone());
+    let audio = backends.audio.watch(watcher);
+    media && audio
+}
+
+/// Switches to the platform backend, or to a fresh fake backend when a
+/// scenario is given
+pub fn use_backend(scenario: Option<FakeScenario>) {
+    let backends = match scenario {
+        Some(scenario) => Backends::fake(scenario),
+        None => Backends::system(),
+    };
+    *BACKENDS.write().unwrap_or_else(|err| err.into_inner()) = Some(backends);
+    watch::backends_swapped();
+}
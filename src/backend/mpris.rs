@@ -0,0 +1,596 @@
+use std::{collections::HashMap, sync::Mutex};
+use futures_util::{stream::select_all, FutureExt, StreamExt};
+use zbus::{
+    blocking::{
+        fdo::{DBusProxy, PropertiesProxy},
+        Connection, MessageIterator,
+    },
+    names::InterfaceName,
+    proxy,
+    proxy::CacheProperties,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+    DBusError, MessageStream,
+};
+use crate::{
+    error::{Error, ErrorStatus, Result},
+    MediaCapabilities, MediaInfo, RepeatMode,
+};
+use super::{unix_now_ms, watch::WATCHER_CHECK_INTERVAL, MediaBackend, TransportCommand, Watcher};
+
+/// Every MPRIS player owns a bus name starting with this prefix
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+/// Object path every MPRIS player publishes its interfaces on
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+
+/// Interface holding the playback state of a player
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Signal telling that a player claimed or released its bus name
+const NAME_OWNER_RULE: &str =
+    "type='signal',sender='org.freedesktop.DBus',interface='org.freedesktop.DBus',member='NameOwnerChanged',arg0namespace='org.mpris.MediaPlayer2'";
+
+/// Signals telling that a player changed, appeared or went away
+const WATCH_RULES: [&str; 3] = [
+    "type='signal',interface='org.freedesktop.DBus.Properties',member='PropertiesChanged',path='/org/mpris/MediaPlayer2'",
+    "type='signal',interface='org.mpris.MediaPlayer2.Player',member='Seeked',path='/org/mpris/MediaPlayer2'",
+    NAME_OWNER_RULE,
+];
+
+#[proxy(
+    interface = "org.mpris.MediaPlayer2",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait MediaPlayer2 {
+    #[zbus(property)]
+    fn desktop_entry(&self) -> zbus::Result<String>;
+}
+
+#[proxy(
+    interface = "org.mpris.MediaPlayer2.Player",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait Player {
+    fn play(&self) -> zbus::Result<()>;
+    fn pause(&self) -> zbus::Result<()>;
+    fn next(&self) -> zbus::Result<()>;
+    fn previous(&self) -> zbus::Result<()>;
+    fn stop(&self) -> zbus::Result<()>;
+    fn seek(&self, offset: i64) -> zbus::Result<()>;
+    fn set_position(&self, track_id: &ObjectPath<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn can_play(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn can_pause(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn can_go_next(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn can_go_previous(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn can_seek(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn can_control(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn volume(&self) -> zbus::Result<f64>;
+    #[zbus(property)]
+    fn shuffle(&self) -> zbus::Result<bool>;
+    #[zbus(property)]
+    fn set_shuffle(&self, value: bool) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn loop_status(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn set_loop_status(&self, value: &str) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn rate(&self) -> zbus::Result<f64>;
+    #[zbus(property)]
+    fn set_rate(&self, value: f64) -> zbus::Result<()>;
+    #[zbus(property)]
+    fn minimum_rate(&self) -> zbus::Result<f64>;
+    #[zbus(property)]
+    fn maximum_rate(&self) -> zbus::Result<f64>;
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+    #[zbus(property)]
+    fn position(&self) -> zbus::Result<i64>;
+}
+
+/// Maps a failed D-Bus call to the crate's error, going by the name of the
+/// D-Bus error where the bus or the player replied with one
+fn dbus_error(err: zbus::Error) -> Error {
+    let (name, description) = match &err {
+        zbus::Error::MethodError(name, description, _) => (name.to_string(), description.clone()),
+        zbus::Error::FDO(err) => (err.name().to_string(), err.description().map(str::to_owned)),
+        zbus::Error::InputOutput(_) | zbus::Error::Connection(..) | zbus::Error::Address(_) | zbus::Error::Handshake(_) => {
+            return Error::new(ErrorStatus::BackendUnavailable, format!("The session bus is unavailable: {err}"));
+        }
+        _ => return Error::new(ErrorStatus::PlatformError, err.to_string()),
+    };
+
+    let status = match name.strip_prefix("org.freedesktop.DBus.Error.").unwrap_or_default() {
+        "ServiceUnknown" | "NameHasNoOwner" => ErrorStatus::NoSession,
+        "UnknownMethod" | "UnknownProperty" | "UnknownInterface" | "NotSupported" | "PropertyReadOnly" => {
+            ErrorStatus::NotSupported
+        }
+        "AccessDenied" | "AuthFailed" | "InteractiveAuthorizationRequired" => ErrorStatus::PermissionDenied,
+        "NoReply" | "Timeout" | "TimedOut" => ErrorStatus::Timeout,
+        "InvalidArgs" => ErrorStatus::InvalidArgument,
+        "NoServer" | "Disconnected" => ErrorStatus::BackendUnavailable,
+        _ => ErrorStatus::PlatformError,
+    };
+    match description {
+        Some(description) => Error::new(status, format!("{name}: {description}")),
+        None => Error::new(status, name),
+    }
+}
+
+static CONNECTION: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// Returns the shared session bus connection, connecting on first use
+fn connection() -> Result<Connection> {
+    let mut guard = CONNECTION.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(connection) = guard.as_ref() {
+        return Ok(connection.clone());
+    }
+    let connection = Connection::session().map_err(dbus_error)?;
+    *guard = Some(connection.clone());
+    Ok(connection)
+}
+
+/// Bus names of the running players, listed once and then kept up to date
+/// from the `NameOwnerChanged` signals queued since
+struct PlayerList {
+    names: Vec<String>,
+    changes: MessageStream,
+}
+
+impl PlayerList {
+    fn new(connection: &Connection) -> Result<Self> {
+        // Subscribing first means no player appearing during the listing is
+        // missed. Changes the listing already holds are applied harmlessly.
+        let changes = MessageIterator::for_match_rule(NAME_OWNER_RULE, connection, None)
+            .map_err(dbus_error)?
+            .into_inner();
+        let names = DBusProxy::new(connection)
+            .and_then(|dbus| Ok(dbus.list_names()?))
+            .map_err(dbus_error)?;
+
+        let mut names: Vec<String> = names
+            .into_iter()
+            .map(|name| name.to_string())
+            .filter(|name| name.starts_with(MPRIS_PREFIX))
+            .collect();
+        names.sort();
+        Ok(Self { names, changes })
+    }
+
+    /// Applies the name changes received so far, returning false once the
+    /// signals stopped coming because the connection closed
+    fn catch_up(&mut self) -> bool {
+        loop {
+            let message = match self.changes.next().now_or_never() {
+                None => return true,
+                Some(None) => return false,
+                Some(Some(message)) => message,
+            };
+            let change = message.and_then(|message| message.body().deserialize::<(String, String, String)>());
+            let Ok((name, _, new_owner)) = change else {
+                continue;
+            };
+            if !name.starts_with(MPRIS_PREFIX) {
+                continue;
+            }
+            match (self.names.binary_search(&name), new_owner.is_empty()) {
+                (Err(index), false) => self.names.insert(index, name),
+                (Ok(index), true) => {
+                    self.names.remove(index);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+static PLAYERS: Mutex<Option<PlayerList>> = Mutex::new(None);
+
+/// Lists the bus names of the running players, only asking the bus for them
+/// on first use or after the connection closed
+fn list_players(connection: &Connection) -> Result<Vec<String>> {
+    let mut players = PLAYERS.lock().unwrap_or_else(|err| err.into_inner());
+    if !players.as_mut().is_some_and(PlayerList::catch_up) {
+        *players = Some(PlayerList::new(connection)?);
+    }
+    Ok(players.as_ref().map(|players| players.names.clone()).unwrap_or_default())
+}
+
+fn player_proxy(connection: &Connection, name: String) -> Result<PlayerProxyBlocking<'static>> {
+    PlayerProxyBlocking::builder(connection)
+        .destination(name)
+        .and_then(|builder| builder.cache_properties(CacheProperties::No).build())
+        .map_err(dbus_error)
+}
+
+/// Derives an app id from a bus name such as
+/// `org.mpris.MediaPlayer2.firefox.instance_1_42`, for players that publish
+/// no desktop entry
+fn app_id_from_bus_name(name: &str) -> String {
+    let suffix = name.strip_prefix(MPRIS_PREFIX).unwrap_or(name);
+    suffix.split(".instance").next().unwrap_or(suffix).to_string()
+}
+
+/// The `org.mpris.MediaPlayer2.Player` properties of a player, read in one
+/// `GetAll` call rather than one call per property
+struct PlayerProperties(HashMap<String, OwnedValue>);
+
+impl PlayerProperties {
+    fn read(connection: &Connection, name: &str) -> Result<Self> {
+        let properties = PropertiesProxy::builder(connection)
+            .destination(name.to_string())
+            .and_then(|builder| builder.path(MPRIS_PATH))
+            .and_then(|builder| builder.cache_properties(CacheProperties::No).build())
+            .map_err(dbus_error)?;
+        properties
+            .get_all(InterfaceName::from_static_str_unchecked(PLAYER_INTERFACE))
+            .map(Self)
+            .map_err(|err| dbus_error(err.into()))
+    }
+
+    fn has(&self, property: &str) -> bool {
+        self.0.contains_key(property)
+    }
+
+    fn bool(&self, property: &str) -> Option<bool> {
+        bool::try_from(&**self.0.get(property)?).ok()
+    }
+
+    fn f64(&self, property: &str) -> Option<f64> {
+        f64::try_from(&**self.0.get(property)?).ok()
+    }
+
+    fn i64(&self, property: &str) -> Option<i64> {
+        i64::try_from(&**self.0.get(property)?).ok()
+    }
+
+    fn string(&self, property: &str) -> Option<String> {
+        <&str>::try_from(&**self.0.get(property)?).ok().map(str::to_owned)
+    }
+
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        self.0
+            .get("Metadata")
+            .and_then(|value| value.try_clone().ok())
+            .and_then(|value| HashMap::try_from(value).ok())
+            .unwrap_or_default()
+    }
+}
+
+fn metadata_string(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    let value = metadata.get(key)?;
+    <&str>::try_from(&**value).ok().map(str::to_owned)
+}
+
+/// Reads an `as` entry such as `xesam:artist` and joins it into one string
+fn metadata_list(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    let value = metadata.get(key)?.try_clone().ok()?;
+    let list = Vec::<String>::try_from(value).ok()?;
+    if list.is_empty() {
+        None
+    } else {
+        Some(list.join(", "))
+    }
+}
+
+/// Reads an integer entry, which players publish with varying widths and
+/// signedness
+fn metadata_integer(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<i64> {
+    match &**metadata.get(key)? {
+        Value::I64(value) => Some(*value),
+        Value::U64(value) => i64::try_from(*value).ok(),
+        Value::I32(value) => Some(i64::from(*value)),
+        Value::U32(value) => Some(i64::from(*value)),
+        _ => None,
+    }
+}
+
+/// Maps the `Can*` properties of a player. MPRIS has no dedicated property
+/// for stop, shuffle, loop status, rate or volume, so those count as enabled
+/// when the player can be controlled and publishes the property.
+fn capabilities(player: &PlayerProperties) -> MediaCapabilities {
+    let can_control = player.bool("CanControl").unwrap_or(false);
+    let rate_range = player.f64("MinimumRate").zip(player.f64("MaximumRate"));
+
+    MediaCapabilities {
+        play: player.bool("CanPlay").unwrap_or(false),
+        pause: player.bool("CanPause").unwrap_or(false),
+        stop: can_control,
+        next: player.bool("CanGoNext").unwrap_or(false),
+        previous: player.bool("CanGoPrevious").unwrap_or(false),
+        seek: player.bool("CanSeek").unwrap_or(false),
+        shuffle: can_control && player.has("Shuffle"),
+        repeat: can_control && player.has("LoopStatus"),
+        rate: can_control && rate_range.is_some_and(|(minimum, maximum)| minimum < maximum),
+        volume: can_control && player.has("Volume"),
+    }
+}
+
+/// Reads `mpris:trackid`, which `SetPosition` needs to target the right track.
+/// Some players publish it as a plain string rather than an object path.
+fn metadata_track_id(metadata: &HashMap<String, OwnedValue>) -> Option<OwnedObjectPath> {
+    let track_id = match &**metadata.get("mpris:trackid")? {
+        Value::ObjectPath(path) => path.to_owned(),
+        Value::Str(path) => ObjectPath::try_from(path.as_str()).ok()?.into_owned(),
+        _ => return None,
+    };
+    if track_id.as_str() == "/org/mpris/MediaPlayer2/TrackList/NoTrack" {
+        return None;
+    }
+    Some(track_id.into())
+}
+
+/// Decodes the `%XX` escapes of a `file://` art URL into a filesystem path
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Media sessions exposed by MPRIS players on the session bus.
+///
+/// Sessions are identified by the bus name of their player, such as
+/// `org.mpris.MediaPlayer2.spotify`, and their source app id is the desktop
+/// entry the player publishes.
+pub struct MprisBackend;
+
+impl MprisBackend {
+    /// Connects to a player, failing with [`ErrorStatus::NoSession`] when no
+    /// player owns the name
+    fn player(&self, session_id: &str) -> Result<PlayerProxyBlocking<'static>> {
+        let connection = connection()?;
+        if !list_players(&connection)?.iter().any(|name| name == session_id) {
+            return Err(Error::no_session(session_id));
+        }
+        player_proxy(&connection, session_id.to_string())
+    }
+
+    /// Connects to a player that can seek, or says why it cannot be used
+    fn seekable_player(&self, session_id: &str) -> Result<PlayerProxyBlocking<'static>> {
+        let player = self.player(session_id)?;
+        match player.can_seek().map_err(dbus_error)? {
+            true => Ok(player),
+            false => Err(Error::not_supported("The player cannot seek")),
+        }
+    }
+}
+
+impl MediaBackend for MprisBackend {
+    /// MPRIS has no notion of a current player, so this picks the first one
+    /// that is playing, then the first paused one, then whatever is left
+    fn current_session(&self) -> Result<Option<String>> {
+        let connection = connection()?;
+        let mut players: Vec<(String, String)> = list_players(&connection)?
+            .into_iter()
+            .map(|name| {
+                let status = player_proxy(&connection, name.clone())
+                    .ok()
+                    .and_then(|player| player.playback_status().ok())
+                    .unwrap_or_default();
+                (name, status)
+            })
+            .collect();
+
+        let rank = |status: &str| match status {
+            "Playing" => 0,
+            "Paused" => 1,
+            _ => 2,
+        };
+        players.sort_by_key(|(_, status)| rank(status));
+        Ok(players.into_iter().next().map(|(name, _)| name))
+    }
+
+    fn list_sessions(&self) -> Result<Vec<String>> {
+        list_players(&connection()?)
+    }
+
+    fn source_app_id(&self, session_id: &str) -> Option<String> {
+        let connection = connection().ok()?;
+        if !list_players(&connection).ok()?.iter().any(|name| name == session_id) {
+            return None;
+        }
+
+        let desktop_entry = MediaPlayer2ProxyBlocking::builder(&connection)
+            .destination(session_id.to_string())
+            .ok()
+            .and_then(|builder| builder.cache_properties(CacheProperties::No).build().ok())
+            .and_then(|player| player.desktop_entry().ok())
+            .filter(|entry| !entry.is_empty());
+        Some(desktop_entry.unwrap_or_else(|| app_id_from_bus_name(session_id)))
+    }
+
+    fn media_info(&self, session_id: &str) -> Result<MediaInfo> {
+        let connection = connection()?;
+        if !list_players(&connection)?.iter().any(|name| name == session_id) {
+            return Err(Error::no_session(session_id));
+        }
+        let player = PlayerProperties::read(&connection, session_id)?;
+        let metadata = player.metadata();
+
+        let playback_status = player
+            .string("PlaybackStatus")
+            .ok_or_else(|| Error::not_supported("The player publishes no playback status"))?;
+        let status_str = match playback_status.as_str() {
+            "Playing" => "playing",
+            "Paused" => "paused",
+            "Stopped" => "stopped",
+            _ => "unknown",
+        };
+
+        // Lengths and positions are in microseconds
+        let position_ms = player.i64("Position").map(|position| position as f64 / 1000.0);
+        let genres = metadata
+            .get("xesam:genre")
+            .and_then(|value| value.try_clone().ok())
+            .and_then(|value| Vec::<String>::try_from(value).ok())
+            .filter(|genres| !genres.is_empty());
+
+        Ok(MediaInfo {
+            title: metadata_string(&metadata, "xesam:title"),
+            artist: metadata_list(&metadata, "xesam:artist"),
+            album: metadata_string(&metadata, "xesam:album"),
+            album_artist: metadata_list(&metadata, "xesam:albumArtist"),
+            playback_status: status_str.to_string(),
+            has_thumbnail: metadata_string(&metadata, "mpris:artUrl").is_some(),
+            // MPRIS has no subtitle, track count or media kind
+            subtitle: None,
+            track_number: metadata_integer(&metadata, "xesam:trackNumber")
+                .and_then(|number| u32::try_from(number).ok())
+                .filter(|&number| number > 0),
+            track_count: None,
+            genres,
+            playback_type: None,
+            duration_ms: metadata_integer(&metadata, "mpris:length")
+                .filter(|&length| length > 0)
+                .map(|length| length as f64 / 1000.0),
+            last_updated: position_ms.map(|_| unix_now_ms()),
+            position_ms,
+            source_app_id: self.source_app_id(session_id),
+            shuffle: player.bool("Shuffle"),
+            repeat_mode: player.string("LoopStatus").and_then(|status| match status.as_str() {
+                "None" => Some(RepeatMode::None),
+                "Track" => Some(RepeatMode::Track),
+                "Playlist" => Some(RepeatMode::List),
+                _ => None,
+            }),
+            playback_rate: player.f64("Rate"),
+            capabilities: Some(capabilities(&player)),
+            track_id: metadata_track_id(&metadata).map(|track_id| track_id.as_str().to_string()),
+        })
+    }
+
+    /// Reads the artwork behind `mpris:artUrl`. Only local `file://` URLs are
+    /// supported, which is what browsers and most desktop players publish
+    fn thumbnail(&self, session_id: &str) -> Result<Option<Vec<u8>>> {
+        let metadata = self.player(session_id)?.metadata().map_err(dbus_error)?;
+        let path = metadata_string(&metadata, "mpris:artUrl")
+            .and_then(|art_url| art_url.strip_prefix("file://").map(percent_decode));
+
+        // Artwork that cannot be read counts as no artwork
+        Ok(path.and_then(|path| std::fs::read(path).ok()))
+    }
+
+    fn send_command(&self, session_id: &str, command: TransportCommand) -> Result<()> {
+        let player = self.player(session_id)?;
+        let result = match command {
+            TransportCommand::Play => player.play(),
+            TransportCommand::Pause => player.pause(),
+            TransportCommand::Next => player.next(),
+            TransportCommand::Previous => player.previous(),
+            TransportCommand::Stop => player.stop(),
+        };
+        result.map_err(dbus_error)
+    }
+
+    fn seek(&self, session_id: &str, offset_ms: f64) -> Result<()> {
+        let player = self.seekable_player(session_id)?;
+        player.seek((offset_ms * 1000.0) as i64).map_err(dbus_error)
+    }
+
+    fn set_position(&self, session_id: &str, position_ms: f64) -> Result<()> {
+        let player = self.seekable_player(session_id)?;
+        let metadata = player.metadata().unwrap_or_default();
+
+        // Players ignore positions past the end of the track
+        let mut position = (position_ms * 1000.0) as i64;
+        if let Some(length) = metadata_integer(&metadata, "mpris:length").filter(|&length| length > 0) {
+            position = position.min(length);
+        }
+
+        let result = match metadata_track_id(&metadata) {
+            Some(track_id) => player.set_position(&track_id, position),
+            // Without a track id the position can only be reached relatively
+            None => match player.position() {
+                Ok(current) => player.seek(position - current),
+                Err(_) => return Err(Error::not_supported("The player reports neither a track id nor its position")),
+            },
+        };
+        result.map_err(dbus_error)
+    }
+
+    fn set_shuffle(&self, session_id: &str, shuffle: bool) -> Result<()> {
+        self.player(session_id)?.set_shuffle(shuffle).map_err(dbus_error)
+    }
+
+    fn set_repeat_mode(&self, session_id: &str, mode: RepeatMode) -> Result<()> {
+        let status = match mode {
+            RepeatMode::None => "None",
+            RepeatMode::Track => "Track",
+            RepeatMode::List => "Playlist",
+        };
+        self.player(session_id)?.set_loop_status(status).map_err(dbus_error)
+    }
+
+    /// Players only accept rates between their `MinimumRate` and
+    /// `MaximumRate`, which default to 1.0 when not published
+    fn set_playback_rate(&self, session_id: &str, rate: f64) -> Result<()> {
+        let player = self.player(session_id)?;
+        let minimum = player.minimum_rate().unwrap_or(1.0);
+        let maximum = player.maximum_rate().unwrap_or(1.0);
+        if !(minimum..=maximum).contains(&rate) {
+            return Err(Error::not_supported(format!("The player only plays at rates from {minimum} to {maximum}")));
+        }
+        player.set_rate(rate).map_err(dbus_error)
+    }
+
+    /// Notifies on `PropertiesChanged` and `Seeked` from any player, and on
+    /// players claiming or releasing their bus name
+    fn watch(&self, watcher: Watcher) -> bool {
+        let connection = match connection() {
+            Ok(connection) => connection.inner().clone(),
+            Err(_) => return false,
+        };
+        let runtime = match tokio::runtime::Builder::new_current_thread().enable_time().build() {
+            Ok(runtime) => runtime,
+            Err(_) => return false,
+        };
+        let streams = runtime.block_on(async {
+            let mut streams = Vec::with_capacity(WATCH_RULES.len());
+            for rule in WATCH_RULES {
+                streams.push(zbus::MessageStream::for_match_rule(rule, &connection, None).await?);
+            }
+            zbus::Result::Ok(streams)
+        });
+        let mut messages = match streams {
+            Ok(streams) => select_all(streams),
+            Err(_) => return false,
+        };
+
+        std::thread::spawn(move || {
+            runtime.block_on(async {
+                while watcher.is_active() {
+                    match tokio::time::timeout(WATCHER_CHECK_INTERVAL, messages.next()).await {
+                        Ok(Some(_)) => {
+                            watcher.notify();
+                        }
+                        Ok(None) => break,
+                        Err(_) => {}
+                    }
+                }
+            })
+        });
+        true
+    }
+}
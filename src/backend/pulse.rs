@@ -0,0 +1,674 @@
+use std::{
+    ffi::{CStr, CString},
+    io::{BufRead, BufReader, ErrorKind},
+    os::unix::net::UnixStream,
+    sync::Mutex,
+    time::Duration,
+};
+use pulseaudio::protocol::{
+    self, port_info::PortAvailable, ChannelMap, ChannelVolume, Command, CommandReply, GetSinkInfo,
+    GetSourceInfo, Prop, Props, ProtocolError, PulseError, ServerInfo, SetClientNameReply,
+    SetDeviceMuteParams, SetDeviceVolumeParams, SetStreamMuteParams, SetStreamVolumeParams, SinkInfo,
+    SinkInfoList, SinkInputInfo, SinkInputInfoList, SourceInfo, SourceInfoList, SubscriptionMask, Volume,
+};
+use crate::{
+    channels::check_levels,
+    error::{Error, ErrorStatus, Result},
+    session::{AudioSessionKind, AudioSessionState, Session},
+    AudioDevice, AudioSession, ChannelPosition, ChannelVolume as ChannelLevel, DeviceDirection, DeviceState,
+    VolumeCurve, VolumeRange,
+};
+use super::{watch::WATCHER_CHECK_INTERVAL, AudioBackend, DeviceRef, Watcher};
+
+/// Name Pulse resolves to whichever sink is currently the default
+const DEFAULT_SINK: &CStr = c"@DEFAULT_SINK@";
+
+/// Name Pulse resolves to whichever source is currently the default
+const DEFAULT_SOURCE: &CStr = c"@DEFAULT_SOURCE@";
+
+/// The levels of Pulse are the cube root of the amplitude, which is how
+/// `pactl` and pavucontrol show them as percentages
+const SCALE: VolumeCurve = VolumeCurve::Cubic;
+
+/// How long to wait for the server before giving up on a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A blocking connection speaking the native PulseAudio protocol, which
+/// PipeWire also serves through pipewire-pulse
+struct PulseConnection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    version: u16,
+    seq: u32,
+}
+
+impl PulseConnection {
+    fn connect() -> Result<Self, ProtocolError> {
+        let socket_path = pulseaudio::socket_path_from_env().ok_or_else(|| {
+            ProtocolError::Invalid("PulseAudio socket not found".to_string())
+        })?;
+        let stream = UnixStream::connect(socket_path)?;
+        stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+        let mut connection = Self {
+            writer: stream.try_clone()?,
+            reader: BufReader::new(stream),
+            version: protocol::MAX_VERSION,
+            seq: 0,
+        };
+
+        let cookie = pulseaudio::cookie_path_from_env()
+            .and_then(|path| std::fs::read(path).ok())
+            .unwrap_or_default();
+        let auth: protocol::AuthReply = connection.request(Command::Auth(protocol::AuthParams {
+            version: protocol::MAX_VERSION,
+            supports_shm: false,
+            supports_memfd: false,
+            cookie,
+        }))?;
+        connection.version = protocol::MAX_VERSION.min(auth.version);
+
+        let mut props = Props::new();
+        props.set(Prop::ApplicationName, c"universal-media");
+        let _: SetClientNameReply = connection.request(Command::SetClientName(props))?;
+
+        Ok(connection)
+    }
+
+    fn send(&mut self, command: Command) -> Result<(), ProtocolError> {
+        self.seq = self.seq.wrapping_add(1);
+        protocol::write_command_message(&mut self.writer, self.seq, &command, self.version)
+    }
+
+    /// Sends a command and reads its typed reply
+    fn request<T: CommandReply>(&mut self, command: Command) -> Result<T, ProtocolError> {
+        self.send(command)?;
+        let (_, reply) = protocol::read_reply_message(&mut self.reader, self.version)?;
+        Ok(reply)
+    }
+
+    /// Sends a command whose reply carries no data
+    fn request_ack(&mut self, command: Command) -> Result<(), ProtocolError> {
+        self.send(command)?;
+        protocol::read_ack_message(&mut self.reader)?;
+        Ok(())
+    }
+
+    /// Subscribes to changes of sinks, sources, sink inputs and the server,
+    /// then notifies `watcher` on every event until it retires
+    fn watch(mut self, watcher: Watcher) -> Result<(), ProtocolError> {
+        self.request_ack(Command::Subscribe(
+            SubscriptionMask::SINK
+                | SubscriptionMask::SOURCE
+                | SubscriptionMask::SINK_INPUT
+                | SubscriptionMask::SERVER,
+        ))?;
+        self.writer.set_read_timeout(Some(WATCHER_CHECK_INTERVAL))?;
+
+        while watcher.is_active() {
+            // Wait for a message to start arriving so a timeout never cuts
+            // one in half
+            match self.reader.fill_buf() {
+                Ok([]) => break,
+                Ok(_) => {}
+                Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+                Err(err) => return Err(err.into()),
+            }
+            if let (_, Command::SubscribeEvent(_)) = protocol::read_command_message(&mut self.reader, self.version)? {
+                watcher.notify();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Maps a failed request to the crate's error, keeping the error code of
+/// the server as the native code
+fn pulse_error(err: ProtocolError) -> Error {
+    let code = match err {
+        ProtocolError::ServerError(code) => code,
+        ProtocolError::Timeout => return Error::new(ErrorStatus::Timeout, err.to_string()),
+        ProtocolError::Io(ref io) if matches!(io.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            return Error::new(ErrorStatus::Timeout, err.to_string());
+        }
+        ProtocolError::Io(_) => return Error::new(ErrorStatus::BackendUnavailable, err.to_string()),
+        _ => return Error::new(ErrorStatus::PlatformError, err.to_string()),
+    };
+
+    let status = match code {
+        PulseError::NoEntity => ErrorStatus::NoSession,
+        PulseError::AccessDenied | PulseError::AuthKey => ErrorStatus::PermissionDenied,
+        PulseError::Invalid => ErrorStatus::InvalidArgument,
+        PulseError::Timeout => ErrorStatus::Timeout,
+        PulseError::Command
+        | PulseError::NotSupported
+        | PulseError::NotImplemented
+        | PulseError::NoExtension
+        | PulseError::Obsolete => ErrorStatus::NotSupported,
+        PulseError::ConnectionRefused | PulseError::ConnectionTerminated | PulseError::InvalidServer => {
+            ErrorStatus::BackendUnavailable
+        }
+        _ => ErrorStatus::PlatformError,
+    };
+    Error {
+        status,
+        message: code.to_string(),
+        native_code: Some(code as i64),
+    }
+}
+
+static CONNECTION: Mutex<Option<PulseConnection>> = Mutex::new(None);
+
+/// Runs `f` on the shared connection, connecting on first use. A failed
+/// request drops the connection so the next call starts from a fresh one.
+fn with_connection<T>(f: impl FnOnce(&mut PulseConnection) -> Result<T, ProtocolError>) -> Result<T> {
+    let mut guard = CONNECTION.lock().unwrap_or_else(|err| err.into_inner());
+    let connection = match guard.as_mut() {
+        Some(connection) => connection,
+        None => guard.insert(PulseConnection::connect().map_err(|err| {
+            Error::new(ErrorStatus::BackendUnavailable, format!("Cannot reach the sound server: {err}"))
+        })?),
+    };
+
+    let result = f(connection);
+    if result.is_err() {
+        *guard = None;
+    }
+    result.map_err(pulse_error)
+}
+
+/// Turns the server not knowing the requested entity into `None`
+fn optional<T>(result: Result<T, ProtocolError>) -> Result<Option<T>, ProtocolError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ProtocolError::ServerError(PulseError::NoEntity)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Finds a sink input by its index on the server
+fn sink_input_info(connection: &mut PulseConnection, index: u32) -> Result<Option<SinkInputInfo>, ProtocolError> {
+    let inputs: SinkInputInfoList = connection.request(Command::GetSinkInputInfoList)?;
+    Ok(inputs.into_iter().find(|input| input.index == index))
+}
+
+/// Reads a string property, dropping the NUL terminator Pulse stores with it
+fn prop_string(props: &Props, prop: Prop) -> Option<String> {
+    let bytes = props.get(prop)?;
+    let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// An application playing audio, which Pulse calls a sink input.
+///
+/// The id is the index of the sink input, which the server never hands out
+/// twice while it runs.
+pub struct PulseSession {
+    index: u32,
+    id: String,
+    pid: Option<u32>,
+    name: String,
+}
+
+impl PulseSession {
+    fn new(input: &SinkInputInfo) -> Self {
+        let name = prop_string(&input.props, Prop::ApplicationName)
+            .or_else(|| prop_string(&input.props, Prop::ApplicationProcessBinary))
+            .unwrap_or_else(|| input.name.to_string_lossy().into_owned());
+
+        PulseSession {
+            index: input.index,
+            id: input.index.to_string(),
+            pid: prop_string(&input.props, Prop::ApplicationProcessId).and_then(|pid| pid.parse().ok()),
+            name,
+        }
+    }
+
+    /// Looks up the session by the id handed out in [`AudioSession::id`]
+    fn find(session_id: &str) -> Result<Self> {
+        let index = session_id.parse::<u32>().map_err(|_| Error::no_session(session_id))?;
+        with_connection(|connection| sink_input_info(connection, index))?
+            .map(|input| PulseSession::new(&input))
+            .ok_or_else(|| Error::no_session(session_id))
+    }
+
+    /// Reads the current state of the sink input, failing once it is gone
+    fn info(&self) -> Result<SinkInputInfo> {
+        with_connection(|connection| sink_input_info(connection, self.index))?
+            .ok_or_else(|| Error::no_session(&self.id))
+    }
+}
+
+impl Session for PulseSession {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> AudioSessionKind {
+        AudioSessionKind::Application
+    }
+
+    fn state(&self) -> Result<AudioSessionState> {
+        let input = match with_connection(|connection| sink_input_info(connection, self.index))? {
+            Some(input) => input,
+            None => return Ok(AudioSessionState::Expired),
+        };
+        // A corked stream is paused by its application
+        Ok(match input.corked {
+            true => AudioSessionState::Inactive,
+            false => AudioSessionState::Active,
+        })
+    }
+
+    fn volume(&self) -> Result<f64> {
+        Ok(volume_level(&self.info()?.cvolume))
+    }
+
+    fn set_volume(&self, level: f64) -> Result<()> {
+        let input = self.info()?;
+        with_connection(|connection| {
+            connection.request_ack(Command::SetSinkInputVolume(SetStreamVolumeParams {
+                index: input.index,
+                volume: scale_volume(&input.cvolume, level),
+            }))
+        })
+    }
+
+    fn muted(&self) -> Result<bool> {
+        Ok(self.info()?.muted)
+    }
+
+    fn set_muted(&self, muted: bool) -> Result<()> {
+        with_connection(|connection| {
+            connection.request_ack(Command::SetSinkInputMute(SetStreamMuteParams {
+                index: self.index,
+                mute: muted,
+            }))
+        })
+    }
+
+    fn channel_volumes(&self) -> Result<Vec<ChannelLevel>> {
+        let input = self.info()?;
+        Ok(channel_levels(&input.cvolume, &input.channel_map))
+    }
+
+    fn set_channel_volumes(&self, levels: &[f64]) -> Result<()> {
+        let input = self.info()?;
+        check_levels(input.cvolume.channels().len(), levels)?;
+        with_connection(|connection| {
+            connection.request_ack(Command::SetSinkInputVolume(SetStreamVolumeParams {
+                index: input.index,
+                volume: channel_volume(levels),
+            }))
+        })
+    }
+
+    fn volume_range(&self) -> Result<VolumeRange> {
+        Ok(VolumeRange::software(None))
+    }
+
+    fn volume_db(&self) -> Result<f64> {
+        Ok(SCALE.db(self.volume()?, &VolumeRange::software(None)))
+    }
+
+    fn set_volume_db(&self, db: f64) -> Result<()> {
+        self.set_volume(SCALE.level(db, &VolumeRange::software(None)))
+    }
+}
+
+/// An output or input device, which Pulse calls a sink or a source.
+///
+/// The id of a device is its name, which unlike its index stays the same
+/// when the server restarts.
+enum PulseDevice {
+    Sink(SinkInfo),
+    Source(SourceInfo),
+}
+
+/// Reads a field that sinks and sources both have
+macro_rules! device_field {
+    ($device:expr, $field:ident) => {
+        match $device {
+            PulseDevice::Sink(sink) => &sink.$field,
+            PulseDevice::Source(source) => &source.$field,
+        }
+    };
+}
+
+impl PulseDevice {
+    /// Looks up a sink or source by name, or the default of a direction
+    fn find(device: DeviceRef) -> Result<Self> {
+        let (name, sink, source) = match device {
+            DeviceRef::Default(DeviceDirection::Output) => (CString::from(DEFAULT_SINK), true, false),
+            DeviceRef::Default(DeviceDirection::Input) => (CString::from(DEFAULT_SOURCE), false, true),
+            DeviceRef::Id(device_id) => (CString::new(device_id).map_err(|_| device.missing())?, true, true),
+        };
+
+        with_connection(|connection| {
+            if sink {
+                let sink = optional(connection.request(Command::GetSinkInfo(GetSinkInfo {
+                    index: None,
+                    name: Some(name.clone()),
+                })))?;
+                if let Some(sink) = sink {
+                    return Ok(Some(PulseDevice::Sink(sink)));
+                }
+            }
+            if source {
+                let source = optional(connection.request(Command::GetSourceInfo(GetSourceInfo {
+                    index: None,
+                    name: Some(name),
+                })))?;
+                return Ok(source.map(PulseDevice::Source));
+            }
+            Ok(None)
+        })?
+        .ok_or_else(|| device.missing())
+    }
+
+    fn direction(&self) -> DeviceDirection {
+        match self {
+            PulseDevice::Sink(_) => DeviceDirection::Output,
+            PulseDevice::Source(_) => DeviceDirection::Input,
+        }
+    }
+
+    fn set_volume_command(&self, volume: ChannelVolume) -> Command {
+        let params = SetDeviceVolumeParams {
+            device_index: Some(*device_field!(self, index)),
+            device_name: None,
+            volume,
+        };
+        match self {
+            PulseDevice::Sink(_) => Command::SetSinkVolume(params),
+            PulseDevice::Source(_) => Command::SetSourceVolume(params),
+        }
+    }
+
+    fn set_mute_command(&self, mute: bool) -> Command {
+        let params = SetDeviceMuteParams {
+            device_index: Some(*device_field!(self, index)),
+            device_name: None,
+            mute,
+        };
+        match self {
+            PulseDevice::Sink(_) => Command::SetSinkMute(params),
+            PulseDevice::Source(_) => Command::SetSourceMute(params),
+        }
+    }
+
+    /// The volume range, with steps only for hardware that cannot be set to
+    /// any volume. The others report a step for every raw volume up to
+    /// the normal one.
+    fn range(&self) -> VolumeRange {
+        VolumeRange::software(device_field!(self, volume_steps).filter(|&steps| steps <= Volume::NORM.as_u32()))
+    }
+
+    fn set_default_command(&self) -> Command {
+        let name = device_field!(self, name).clone();
+        match self {
+            PulseDevice::Sink(_) => Command::SetDefaultSink(name),
+            PulseDevice::Source(_) => Command::SetDefaultSource(name),
+        }
+    }
+
+    /// Describes the device, `server` telling which devices are the defaults
+    fn to_audio_device(&self, server: &ServerInfo) -> AudioDevice {
+        let name = device_field!(self, name);
+        let sample_spec = device_field!(self, sample_spec);
+        let default_name = match self {
+            PulseDevice::Sink(_) => &server.default_sink_name,
+            PulseDevice::Source(_) => &server.default_source_name,
+        };
+
+        // The active port tells what is plugged in, such as headphones
+        let port = device_field!(self, ports).get(*device_field!(self, active_port));
+        let state = match port {
+            Some(port) if port.available == PortAvailable::No => DeviceState::Unplugged,
+            _ => DeviceState::Active,
+        };
+        // Pulse has a single default per direction, which calls use as well
+        let is_default = default_name.as_ref() == Some(name);
+
+        AudioDevice {
+            id: name.to_string_lossy().into_owned(),
+            name: device_field!(self, description)
+                .as_ref()
+                .unwrap_or(name)
+                .to_string_lossy()
+                .into_owned(),
+            description: port
+                .and_then(|port| port.description.as_ref())
+                .map(|description| description.to_string_lossy().into_owned()),
+            direction: self.direction(),
+            state,
+            is_default,
+            is_default_communication: is_default,
+            channels: Some(sample_spec.channels as u32),
+            sample_rate: Some(sample_spec.sample_rate),
+        }
+    }
+}
+
+/// Reads the volume of a channel as a 0.0..1.0 level
+fn level(volume: &Volume) -> f64 {
+    (volume.as_u32() as f64 / Volume::NORM.as_u32() as f64).min(1.0)
+}
+
+/// Reads a channel volume as a 0.0..1.0 level, the loudest channel winning
+/// like it does in `pactl` and pavucontrol
+fn volume_level(volume: &ChannelVolume) -> f64 {
+    volume.channels().iter().map(level).fold(0.0, f64::max)
+}
+
+/// Where a channel of a Pulse channel map plays
+fn channel_position(position: protocol::ChannelPosition) -> ChannelPosition {
+    use protocol::ChannelPosition as Pulse;
+
+    match position {
+        Pulse::Mono => ChannelPosition::Mono,
+        Pulse::FrontLeft => ChannelPosition::FrontLeft,
+        Pulse::FrontRight => ChannelPosition::FrontRight,
+        Pulse::FrontCenter => ChannelPosition::FrontCenter,
+        Pulse::RearCenter => ChannelPosition::RearCenter,
+        Pulse::RearLeft => ChannelPosition::RearLeft,
+        Pulse::RearRight => ChannelPosition::RearRight,
+        Pulse::Lfe => ChannelPosition::Lfe,
+        Pulse::FrontLeftOfCenter => ChannelPosition::FrontLeftOfCenter,
+        Pulse::FrontRightOfCenter => ChannelPosition::FrontRightOfCenter,
+        Pulse::SideLeft => ChannelPosition::SideLeft,
+        Pulse::SideRight => ChannelPosition::SideRight,
+        _ => ChannelPosition::Other,
+    }
+}
+
+/// Reads the level of each channel of a channel volume, next to where the
+/// channel map says it plays
+fn channel_levels(volume: &ChannelVolume, map: &ChannelMap) -> Vec<ChannelLevel> {
+    volume
+        .channels()
+        .iter()
+        .zip(map)
+        .map(|(channel, position)| ChannelLevel {
+            position: channel_position(position),
+            volume: level(channel),
+        })
+        .collect()
+}
+
+/// Builds a channel volume out of a 0.0..1.0 level for each channel
+fn channel_volume(levels: &[f64]) -> ChannelVolume {
+    let mut volume = ChannelVolume::empty();
+    for level in levels {
+        volume.push(Volume::from_u32_clamped((level * Volume::NORM.as_u32() as f64).round() as u32));
+    }
+    volume
+}
+
+/// Scales a channel volume so its loudest channel sits at `level`, keeping
+/// the balance between channels
+fn scale_volume(volume: &ChannelVolume, level: f64) -> ChannelVolume {
+    let target = level * Volume::NORM.as_u32() as f64;
+    let loudest = volume.channels().iter().map(Volume::as_u32).max().unwrap_or(0);
+
+    let mut scaled = ChannelVolume::empty();
+    for channel in volume.channels() {
+        let raw = if loudest == 0 {
+            target
+        } else {
+            channel.as_u32() as f64 * target / loudest as f64
+        };
+        scaled.push(Volume::from_u32_clamped(raw.round() as u32));
+    }
+    scaled
+}
+
+/// Volume of the sinks and sources through the PulseAudio protocol.
+///
+/// Application sessions are the sink inputs of the server, identified by
+/// their index.
+pub struct PulseBackend;
+
+impl AudioBackend for PulseBackend {
+    fn device_id(&self, device: DeviceRef) -> Result<String> {
+        Ok(device_field!(&PulseDevice::find(device)?, name).to_string_lossy().into_owned())
+    }
+
+    fn volume(&self, device: DeviceRef) -> Result<f64> {
+        Ok(volume_level(device_field!(&PulseDevice::find(device)?, cvolume)))
+    }
+
+    fn set_volume(&self, device: DeviceRef, level: f64) -> Result<()> {
+        let device = PulseDevice::find(device)?;
+        let volume = scale_volume(device_field!(&device, cvolume), level);
+        with_connection(|connection| connection.request_ack(device.set_volume_command(volume)))
+    }
+
+    fn muted(&self, device: DeviceRef) -> Result<bool> {
+        Ok(*device_field!(&PulseDevice::find(device)?, muted))
+    }
+
+    fn set_muted(&self, device: DeviceRef, muted: bool) -> Result<()> {
+        let device = PulseDevice::find(device)?;
+        with_connection(|connection| connection.request_ack(device.set_mute_command(muted)))
+    }
+
+    fn channel_volumes(&self, device: DeviceRef) -> Result<Vec<ChannelLevel>> {
+        let device = PulseDevice::find(device)?;
+        Ok(channel_levels(device_field!(&device, cvolume), device_field!(&device, channel_map)))
+    }
+
+    fn set_channel_volumes(&self, device: DeviceRef, levels: &[f64]) -> Result<()> {
+        let device = PulseDevice::find(device)?;
+        check_levels(device_field!(&device, cvolume).channels().len(), levels)?;
+        with_connection(|connection| connection.request_ack(device.set_volume_command(channel_volume(levels))))
+    }
+
+    fn volume_range(&self, device: DeviceRef) -> Result<VolumeRange> {
+        Ok(PulseDevice::find(device)?.range())
+    }
+
+    fn volume_db(&self, device: DeviceRef) -> Result<f64> {
+        let device = PulseDevice::find(device)?;
+        Ok(SCALE.db(volume_level(device_field!(&device, cvolume)), &device.range()))
+    }
+
+    fn set_volume_db(&self, device: DeviceRef, db: f64) -> Result<()> {
+        let device = PulseDevice::find(device)?;
+        let volume = scale_volume(device_field!(&device, cvolume), SCALE.level(db, &device.range()));
+        with_connection(|connection| connection.request_ack(device.set_volume_command(volume)))
+    }
+
+    /// Leaves out the monitor sources, which record what a sink plays
+    /// rather than an input of their own
+    fn list_devices(&self, direction: Option<DeviceDirection>) -> Result<Vec<AudioDevice>> {
+        with_connection(|connection| {
+            let server: ServerInfo = connection.request(Command::GetServerInfo)?;
+            let mut devices = vec![];
+            if direction != Some(DeviceDirection::Input) {
+                let sinks: SinkInfoList = connection.request(Command::GetSinkInfoList)?;
+                devices.extend(sinks.into_iter().map(PulseDevice::Sink));
+            }
+            if direction != Some(DeviceDirection::Output) {
+                let sources: SourceInfoList = connection.request(Command::GetSourceInfoList)?;
+                devices.extend(
+                    sources
+                        .into_iter()
+                        .filter(|source| source.monitor_of_sink_index.is_none())
+                        .map(PulseDevice::Source),
+                );
+            }
+            Ok(devices.iter().map(|device| device.to_audio_device(&server)).collect())
+        })
+    }
+
+    fn set_default_device(&self, device_id: &str) -> Result<()> {
+        let device = PulseDevice::find(DeviceRef::Id(device_id))?;
+        with_connection(|connection| connection.request_ack(device.set_default_command()))
+    }
+
+    fn list_sessions(&self) -> Result<Vec<AudioSession>> {
+        with_connection(|connection| {
+            let inputs: SinkInputInfoList = connection.request(Command::GetSinkInputInfoList)?;
+            Ok(inputs
+                .iter()
+                .map(|input| AudioSession::from(&PulseSession::new(input)))
+                .collect())
+        })
+    }
+
+    fn session_volume(&self, session_id: &str) -> Result<f64> {
+        PulseSession::find(session_id)?.volume()
+    }
+
+    fn set_session_volume(&self, session_id: &str, level: f64) -> Result<()> {
+        PulseSession::find(session_id)?.set_volume(level)
+    }
+
+    fn session_muted(&self, session_id: &str) -> Result<bool> {
+        PulseSession::find(session_id)?.muted()
+    }
+
+    fn set_session_muted(&self, session_id: &str, muted: bool) -> Result<()> {
+        PulseSession::find(session_id)?.set_muted(muted)
+    }
+
+    fn session_channel_volumes(&self, session_id: &str) -> Result<Vec<ChannelLevel>> {
+        PulseSession::find(session_id)?.channel_volumes()
+    }
+
+    fn set_session_channel_volumes(&self, session_id: &str, levels: &[f64]) -> Result<()> {
+        PulseSession::find(session_id)?.set_channel_volumes(levels)
+    }
+
+    fn session_volume_range(&self, session_id: &str) -> Result<VolumeRange> {
+        PulseSession::find(session_id)?.volume_range()
+    }
+
+    fn session_volume_db(&self, session_id: &str) -> Result<f64> {
+        PulseSession::find(session_id)?.volume_db()
+    }
+
+    fn set_session_volume_db(&self, session_id: &str, db: f64) -> Result<()> {
+        PulseSession::find(session_id)?.set_volume_db(db)
+    }
+
+    /// Uses a connection of its own, as the shared one only expects replies
+    fn watch(&self, watcher: Watcher) -> bool {
+        let connection = match PulseConnection::connect() {
+            Ok(connection) => connection,
+            Err(_) => return false,
+        };
+        std::thread::spawn(move || connection.watch(watcher));
+        true
+    }
+}
@@ -0,0 +1,111 @@
+//! Change notification from the backends to event subscriptions.
+//!
+//! An event subscription owns a [`ChangeSignal`] and waits on it between
+//! reads of the media and audio state. Backends get a [`Watcher`] for it and
+//! raise the signal from whatever push mechanism their platform has, so the
+//! subscription only re-reads the state when something actually changed.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex, Weak,
+    },
+    time::Duration,
+};
+
+/// How often watcher threads check whether anybody still listens
+pub const WATCHER_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Raised by watchers when the state behind a backend changed
+#[derive(Default)]
+pub struct ChangeSignal {
+    changed: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl ChangeSignal {
+    pub fn raise(&self) {
+        *self.changed.lock().unwrap_or_else(|err| err.into_inner()) = true;
+        self.condvar.notify_all();
+    }
+
+    /// Waits for the signal to be raised, for at most `timeout`. Returns
+    /// whether it was raised, and lowers it again.
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let changed = self.changed.lock().unwrap_or_else(|err| err.into_inner());
+        let (mut changed, _) = self
+            .condvar
+            .wait_timeout_while(changed, timeout, |changed| !*changed)
+            .unwrap_or_else(|err| err.into_inner());
+        std::mem::take(&mut *changed)
+    }
+}
+
+/// Bumped whenever `use_backend` swaps the backends, which retires every
+/// watcher started for the previous ones
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Signals of the live subscriptions, raised when the backends are swapped
+static LISTENERS: Mutex<Vec<Weak<ChangeSignal>>> = Mutex::new(Vec::new());
+
+/// Handle a backend uses to report changes to one subscription.
+///
+/// It stays valid while the subscription exists and the backend it was
+/// handed to is still the one in use, so watcher threads can poll
+/// [`Watcher::is_active`] to know when to exit.
+#[derive(Clone)]
+pub struct Watcher {
+    signal: Weak<ChangeSignal>,
+    generation: u64,
+}
+
+impl Watcher {
+    pub fn is_active(&self) -> bool {
+        self.generation == GENERATION.load(Ordering::SeqCst) && self.signal.strong_count() > 0
+    }
+
+    /// Reports a change, returning false once nobody listens anymore
+    pub fn notify(&self) -> bool {
+        if !self.is_active() {
+            return false;
+        }
+        match self.signal.upgrade() {
+            Some(signal) => {
+                signal.raise();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Returns the generation of the backends currently in use
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::SeqCst)
+}
+
+/// Creates a watcher for `signal` bound to the backends currently in use,
+/// and registers the signal to be raised when they get swapped
+pub fn watcher(signal: &Arc<ChangeSignal>) -> Watcher {
+    let mut listeners = LISTENERS.lock().unwrap_or_else(|err| err.into_inner());
+    listeners.retain(|listener| listener.strong_count() > 0);
+    if !listeners.iter().any(|listener| listener.as_ptr() == Arc::as_ptr(signal)) {
+        listeners.push(Arc::downgrade(signal));
+    }
+
+    Watcher {
+        signal: Arc::downgrade(signal),
+        generation: generation(),
+    }
+}
+
+/// Retires the current watchers and wakes every subscription so it can
+/// watch the new backends
+pub fn backends_swapped() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+
+    let listeners = LISTENERS.lock().unwrap_or_else(|err| err.into_inner());
+    for signal in listeners.iter().filter_map(Weak::upgrade) {
+        signal.raise();
+    }
+}
@@ -0,0 +1,967 @@
+use std::{
+    sync::{mpsc::{self, RecvTimeoutError}, Arc, Mutex},
+    thread,
+};
+use windows::{
+    Foundation::{DateTime, EventRegistrationToken, IAsyncOperation, TimeSpan, TypedEventHandler},
+    Media::{MediaPlaybackAutoRepeatMode, MediaPlaybackType},
+    Media::Control::{
+        GlobalSystemMediaTransportControlsSession, GlobalSystemMediaTransportControlsSessionManager,
+        GlobalSystemMediaTransportControlsSessionPlaybackStatus,
+    },
+    Storage::Streams::{Buffer, IRandomAccessStreamReference, InputStreamOptions, IBuffer},
+    core::{implement, Interface, RuntimeType, GUID, HSTRING, PCWSTR},
+    Win32::{
+        Devices::FunctionDiscovery::{PKEY_Device_DeviceDesc, PKEY_Device_FriendlyName},
+        Foundation::BOOL,
+        Media::Audio::{
+            eCapture, eCommunications, eConsole, eMultimedia, eRender, AudioSessionDisconnectReason,
+            AudioSessionState, AudioSessionStateExpired, EDataFlow, ERole, IAudioClient, IAudioSessionControl,
+            IAudioSessionEvents, IAudioSessionEvents_Impl, IAudioSessionManager2, IAudioSessionNotification,
+            IAudioSessionNotification_Impl, IMMDevice, IMMDeviceEnumerator, MMDeviceEnumerator,
+            AUDIO_VOLUME_NOTIFICATION_DATA, DEVICE_STATE, DEVICE_STATE_ACTIVE, DEVICE_STATE_DISABLED,
+            DEVICE_STATE_UNPLUGGED, WAVEFORMATEX,
+            Endpoints::{IAudioEndpointVolume, IAudioEndpointVolumeCallback, IAudioEndpointVolumeCallback_Impl},
+        },
+        System::Com::{
+            CoCreateInstance, CoInitializeEx, CoTaskMemFree, CLSCTX_ALL, COINIT_MULTITHREADED, STGM_READ,
+        }
+    },
+};
+use crate::{
+    audio_controller::AudioController,
+    error::{Error, ErrorStatus, Result},
+    session::{
+        endpoint_channel_volumes, endpoint_volume_range, set_endpoint_channel_volumes, set_endpoint_volume_db,
+        take_string, AudioSessionKind, AudioSessionState as SessionState, Session,
+    },
+    AudioDevice, AudioSession, ChannelVolume, DeviceDirection, DeviceState, MediaCapabilities, MediaInfo, RepeatMode,
+    VolumeRange,
+};
+use self::policy_config::IPolicyConfig;
+use super::{
+    unix_now_ms, watch::WATCHER_CHECK_INTERVAL, AudioBackend, DeviceRef, MediaBackend, TransportCommand, Watcher,
+};
+
+/// Converts a WinRT time span, counted in 100ns ticks, to milliseconds
+fn timespan_ms(span: TimeSpan) -> f64 {
+    span.Duration as f64 / 10_000.0
+}
+
+/// Converts a WinRT date, counted in 100ns ticks since 1601, to milliseconds
+/// since the Unix epoch
+fn unix_ms(date: DateTime) -> f64 {
+    const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+    (date.UniversalTime - UNIX_EPOCH_TICKS) as f64 / 10_000.0
+}
+
+/// Waits for a `Try...Async` request, which resolves with false when the
+/// session turns it down
+fn accepted(request: windows::core::Result<IAsyncOperation<bool>>, refusal: Error) -> Result<()> {
+    match request.and_then(|op| op.get())? {
+        true => Ok(()),
+        false => Err(refusal),
+    }
+}
+
+fn request_session_manager() -> Result<GlobalSystemMediaTransportControlsSessionManager> {
+    GlobalSystemMediaTransportControlsSessionManager::RequestAsync()
+        .and_then(|async_op| async_op.get())
+        .map_err(|err| Error {
+            status: ErrorStatus::BackendUnavailable,
+            ..Error::from(err)
+        })
+}
+
+/// Ids handed out to the GSMTC sessions seen so far, kept for as long as
+/// each session lives
+static SESSION_IDS: Mutex<Vec<(String, GlobalSystemMediaTransportControlsSession)>> = Mutex::new(Vec::new());
+
+/// Media sessions exposed through the Global System Media Transport Controls.
+///
+/// Every window or tab of an app shares the AppUserModelId of its source
+/// app, so a session is identified by that id alone while it is the only one
+/// of its app, and by the id plus `#n` for the nth concurrent one. A session
+/// keeps its id for as long as it lives.
+pub struct GsmtcBackend;
+
+impl GsmtcBackend {
+    /// Returns the live sessions in the manager's order, each with its id
+    fn identified_sessions(
+        &self,
+        manager: &GlobalSystemMediaTransportControlsSessionManager,
+    ) -> Result<Vec<(String, GlobalSystemMediaTransportControlsSession)>> {
+        let sessions: Vec<_> = manager.GetSessions()?.into_iter().collect();
+        let mut known = SESSION_IDS.lock().unwrap_or_else(|err| err.into_inner());
+
+        // Ended sessions give their ids up to later sessions of the same app
+        known.retain(|(_, seen)| sessions.contains(seen));
+        for session in &sessions {
+            if known.iter().any(|(_, seen)| seen == session) {
+                continue;
+            }
+            let Ok(app_id) = session.SourceAppUserModelId().map(|id| id.to_string()) else {
+                continue;
+            };
+            let id = (1..)
+                .map(|occurrence| match occurrence {
+                    1 => app_id.clone(),
+                    n => format!("{app_id}#{n}"),
+                })
+                .find(|id| known.iter().all(|(taken, _)| taken != id))
+                .unwrap_or(app_id);
+            known.push((id, session.clone()));
+        }
+
+        Ok(sessions
+            .into_iter()
+            .filter_map(|session| {
+                let (id, _) = known.iter().find(|(_, seen)| *seen == session)?;
+                Some((id.clone(), session))
+            })
+            .collect())
+    }
+
+    fn find_session(&self, session_id: &str) -> Result<GlobalSystemMediaTransportControlsSession> {
+        let manager = request_session_manager()?;
+        self.identified_sessions(&manager)?
+            .into_iter()
+            .find(|(id, _)| id == session_id)
+            .map(|(_, session)| session)
+            .ok_or_else(|| Error::no_session(session_id))
+    }
+}
+
+impl MediaBackend for GsmtcBackend {
+    fn current_session(&self) -> Result<Option<String>> {
+        let manager = request_session_manager()?;
+        let current = match manager.GetCurrentSession() {
+            Ok(current) => current,
+            // The manager hands out null while no session is current
+            Err(err) if err.code().is_ok() => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(self
+            .identified_sessions(&manager)?
+            .into_iter()
+            .find(|(_, session)| *session == current)
+            .map(|(id, _)| id))
+    }
+
+    fn list_sessions(&self) -> Result<Vec<String>> {
+        let manager = request_session_manager()?;
+        Ok(self
+            .identified_sessions(&manager)?
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect())
+    }
+
+    fn source_app_id(&self, session_id: &str) -> Option<String> {
+        let session = self.find_session(session_id).ok()?;
+        session.SourceAppUserModelId().ok().map(|id| id.to_string())
+    }
+
+    fn media_info(&self, session_id: &str) -> Result<MediaInfo> {
+        let session = self.find_session(session_id)?;
+
+        let media_props = session
+            .TryGetMediaPropertiesAsync()
+            .and_then(|async_props| async_props.get())
+            ?;
+
+        let playback_info = session.GetPlaybackInfo()?;
+        let status = playback_info.PlaybackStatus()?;
+        let status_str = match status {
+            GlobalSystemMediaTransportControlsSessionPlaybackStatus::Closed => "closed",
+            GlobalSystemMediaTransportControlsSessionPlaybackStatus::Opened => "opened",
+            GlobalSystemMediaTransportControlsSessionPlaybackStatus::Changing => "changing",
+            GlobalSystemMediaTransportControlsSessionPlaybackStatus::Stopped => "stopped",
+            GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing => "playing",
+            GlobalSystemMediaTransportControlsSessionPlaybackStatus::Paused => "paused",
+            _ => "unknown",
+        };
+
+        let thumbnail: Option<IRandomAccessStreamReference> = media_props.Thumbnail().ok();
+
+        let repeat_mode = playback_info
+            .AutoRepeatMode()
+            .and_then(|mode| mode.Value())
+            .ok()
+            .map(|mode| match mode {
+                MediaPlaybackAutoRepeatMode::Track => RepeatMode::Track,
+                MediaPlaybackAutoRepeatMode::List => RepeatMode::List,
+                _ => RepeatMode::None,
+            });
+
+        let playback_type = media_props
+            .PlaybackType()
+            .and_then(|playback_type| playback_type.Value())
+            .ok()
+            .map(|playback_type| match playback_type {
+                MediaPlaybackType::Music => "music",
+                MediaPlaybackType::Video => "video",
+                MediaPlaybackType::Image => "image",
+                _ => "unknown",
+            });
+
+        let genres: Option<Vec<String>> = media_props
+            .Genres()
+            .ok()
+            .map(|genres| genres.into_iter().map(|genre| genre.to_string()).collect());
+
+        // Apps that publish no timeline report an end time of zero
+        let timeline = session
+            .GetTimelineProperties()
+            .ok()
+            .filter(|timeline| timeline.EndTime().is_ok_and(|end| end.Duration > 0));
+        let duration_ms = timeline.as_ref().and_then(|timeline| {
+            let start = timeline.StartTime().ok()?;
+            let end = timeline.EndTime().ok()?;
+            Some(timespan_ms(end) - timespan_ms(start))
+        });
+
+        Ok(MediaInfo {
+            title: media_props.Title().ok().map(|s| s.to_string()),
+            artist: media_props.Artist().ok().map(|s| s.to_string()),
+            album: media_props.AlbumTitle().ok().map(|s| s.to_string()),
+            album_artist: media_props.AlbumArtist().ok().map(|s| s.to_string()),
+            playback_status: status_str.to_string(),
+            has_thumbnail: thumbnail.is_some(),
+            subtitle: media_props.Subtitle().ok().map(|s| s.to_string()).filter(|s| !s.is_empty()),
+            track_number: media_props.TrackNumber().ok().and_then(|n| u32::try_from(n).ok()).filter(|&n| n > 0),
+            track_count: media_props.AlbumTrackCount().ok().and_then(|n| u32::try_from(n).ok()).filter(|&n| n > 0),
+            genres: genres.filter(|genres| !genres.is_empty()),
+            playback_type: playback_type.map(str::to_string),
+            duration_ms,
+            position_ms: timeline.as_ref().and_then(|timeline| timeline.Position().ok()).map(timespan_ms),
+            last_updated: timeline.as_ref().and_then(|timeline| timeline.LastUpdatedTime().ok()).map(unix_ms),
+            source_app_id: session.SourceAppUserModelId().ok().map(|id| id.to_string()),
+            shuffle: playback_info.IsShuffleActive().and_then(|shuffle| shuffle.Value()).ok(),
+            repeat_mode,
+            playback_rate: playback_info.PlaybackRate().and_then(|rate| rate.Value()).ok(),
+            capabilities: playback_info.Controls().ok().map(|controls| MediaCapabilities {
+                play: controls.IsPlayEnabled().unwrap_or(false),
+                pause: controls.IsPauseEnabled().unwrap_or(false),
+                stop: controls.IsStopEnabled().unwrap_or(false),
+                next: controls.IsNextEnabled().unwrap_or(false),
+                previous: controls.IsPreviousEnabled().unwrap_or(false),
+                seek: controls.IsPlaybackPositionEnabled().unwrap_or(false),
+                shuffle: controls.IsShuffleEnabled().unwrap_or(false),
+                repeat: controls.IsRepeatEnabled().unwrap_or(false),
+                rate: controls.IsPlaybackRateEnabled().unwrap_or(false),
+                // GSMTC sessions have no volume of their own
+                volume: false,
+            }),
+            // GSMTC gives tracks no id
+            track_id: None,
+        })
+    }
+
+    fn thumbnail(&self, session_id: &str) -> Result<Option<Vec<u8>>> {
+        let session = self.find_session(session_id)?;
+
+        let media_props = session
+            .TryGetMediaPropertiesAsync()
+            .and_then(|async_props| async_props.get())
+            ?;
+
+        // Media without artwork has no thumbnail to open
+        let thumbnail = match media_props.Thumbnail() {
+            Ok(thumbnail) => thumbnail,
+            Err(_) => return Ok(None),
+        };
+        read_thumbnail(&thumbnail).map(Some).map_err(Error::from)
+    }
+
+    fn send_command(&self, session_id: &str, command: TransportCommand) -> Result<()> {
+        let session = self.find_session(session_id)?;
+
+        let async_op = match command {
+            TransportCommand::Play => session.TryPlayAsync(),
+            TransportCommand::Pause => session.TryPauseAsync(),
+            TransportCommand::Next => session.TrySkipNextAsync(),
+            TransportCommand::Previous => session.TrySkipPreviousAsync(),
+            TransportCommand::Stop => session.TryStopAsync(),
+        };
+
+        accepted(async_op, Error::not_supported(format!("The session does not accept {command:?}")))
+    }
+
+    fn seek(&self, session_id: &str, offset_ms: f64) -> Result<()> {
+        let session = self.find_session(session_id)?;
+        match current_position_ms(&session) {
+            Some(position) => change_position(&session, position + offset_ms),
+            None => Err(Error::not_supported("The session reports no position")),
+        }
+    }
+
+    fn set_position(&self, session_id: &str, position_ms: f64) -> Result<()> {
+        change_position(&self.find_session(session_id)?, position_ms)
+    }
+
+    fn set_shuffle(&self, session_id: &str, shuffle: bool) -> Result<()> {
+        let session = self.find_session(session_id)?;
+        accepted(
+            session.TryChangeShuffleActiveAsync(shuffle),
+            Error::not_supported("The session does not accept shuffle changes"),
+        )
+    }
+
+    fn set_repeat_mode(&self, session_id: &str, mode: RepeatMode) -> Result<()> {
+        let mode = match mode {
+            RepeatMode::None => MediaPlaybackAutoRepeatMode::None,
+            RepeatMode::Track => MediaPlaybackAutoRepeatMode::Track,
+            RepeatMode::List => MediaPlaybackAutoRepeatMode::List,
+        };
+        let session = self.find_session(session_id)?;
+        accepted(
+            session.TryChangeAutoRepeatModeAsync(mode),
+            Error::not_supported("The session does not accept repeat mode changes"),
+        )
+    }
+
+    fn set_playback_rate(&self, session_id: &str, rate: f64) -> Result<()> {
+        let session = self.find_session(session_id)?;
+        accepted(
+            session.TryChangePlaybackRateAsync(rate),
+            Error::not_supported(format!("The session does not accept a playback rate of {rate}")),
+        )
+    }
+
+    /// Notifies on `SessionsChanged` and `CurrentSessionChanged` from the
+    /// manager, and on the media, playback and timeline events of every
+    /// session, which get handlers again whenever the sessions change
+    fn watch(&self, watcher: Watcher) -> bool {
+        let (ready, started) = mpsc::channel();
+        thread::spawn(move || {
+            let manager = match request_session_manager() {
+                Ok(manager) => manager,
+                Err(_) => return ready.send(false).unwrap_or(()),
+            };
+
+            let (sessions_changed, changes) = mpsc::channel();
+            let sessions_handler = {
+                let watcher = watcher.clone();
+                TypedEventHandler::new(move |_, _| {
+                    watcher.notify();
+                    let _ = sessions_changed.send(());
+                    Ok(())
+                })
+            };
+            let tokens = (
+                manager.SessionsChanged(&sessions_handler),
+                manager.CurrentSessionChanged(&notifier(&watcher)),
+            );
+            let (sessions_token, current_token) = match tokens {
+                (Ok(sessions_token), Ok(current_token)) => (sessions_token, current_token),
+                (sessions_token, current_token) => {
+                    let _ = sessions_token.and_then(|token| manager.RemoveSessionsChanged(token));
+                    let _ = current_token.and_then(|token| manager.RemoveCurrentSessionChanged(token));
+                    return ready.send(false).unwrap_or(());
+                }
+            };
+            let _ = ready.send(true);
+
+            let mut watched = watch_sessions(&manager, &watcher);
+            while watcher.is_active() {
+                match changes.recv_timeout(WATCHER_CHECK_INTERVAL) {
+                    Ok(()) => {
+                        unwatch_sessions(watched);
+                        watched = watch_sessions(&manager, &watcher);
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+
+            unwatch_sessions(watched);
+            let _ = manager.RemoveSessionsChanged(sessions_token);
+            let _ = manager.RemoveCurrentSessionChanged(current_token);
+        });
+        started.recv().unwrap_or(false)
+    }
+}
+
+/// Event handler that only reports a change to `watcher`
+fn notifier<S: RuntimeType + 'static, A: RuntimeType + 'static>(watcher: &Watcher) -> TypedEventHandler<S, A> {
+    let watcher = watcher.clone();
+    TypedEventHandler::new(move |_, _| {
+        watcher.notify();
+        Ok(())
+    })
+}
+
+/// A session along with the tokens of the handlers added to it
+struct WatchedSession {
+    session: GlobalSystemMediaTransportControlsSession,
+    media: EventRegistrationToken,
+    playback: EventRegistrationToken,
+    timeline: EventRegistrationToken,
+}
+
+/// Adds handlers reporting to `watcher` to every session of `manager`
+fn watch_sessions(
+    manager: &GlobalSystemMediaTransportControlsSessionManager,
+    watcher: &Watcher,
+) -> Vec<WatchedSession> {
+    let sessions = match manager.GetSessions() {
+        Ok(sessions) => sessions,
+        Err(_) => return vec![],
+    };
+
+    let mut watched = vec![];
+    for session in sessions {
+        let tokens = (
+            session.MediaPropertiesChanged(&notifier(watcher)),
+            session.PlaybackInfoChanged(&notifier(watcher)),
+            session.TimelinePropertiesChanged(&notifier(watcher)),
+        );
+        match tokens {
+            (Ok(media), Ok(playback), Ok(timeline)) => watched.push(WatchedSession {
+                session,
+                media,
+                playback,
+                timeline,
+            }),
+            (media, playback, timeline) => {
+                let _ = media.and_then(|token| session.RemoveMediaPropertiesChanged(token));
+                let _ = playback.and_then(|token| session.RemovePlaybackInfoChanged(token));
+                let _ = timeline.and_then(|token| session.RemoveTimelinePropertiesChanged(token));
+            }
+        }
+    }
+    watched
+}
+
+fn unwatch_sessions(watched: Vec<WatchedSession>) {
+    for watched in watched {
+        let session = watched.session;
+        let _ = session.RemoveMediaPropertiesChanged(watched.media);
+        let _ = session.RemovePlaybackInfoChanged(watched.playback);
+        let _ = session.RemoveTimelinePropertiesChanged(watched.timeline);
+    }
+}
+
+/// Reads the whole image behind a thumbnail reference
+fn read_thumbnail(thumbnail: &IRandomAccessStreamReference) -> windows::core::Result<Vec<u8>> {
+    let stream = thumbnail.OpenReadAsync()?.get()?;
+
+    let size = stream.Size()? as u32;
+    let buffer = Buffer::Create(size)?;
+
+    let input_stream = stream.GetInputStreamAt(0)?;
+    let async_read = input_stream.ReadAsync(
+        &buffer,
+        size,
+        InputStreamOptions::default(),
+    )?;
+
+    let _bytes_read = async_read.get()?;
+
+    // Get bytes from buffer
+    let ibuffer: IBuffer = buffer.cast()?;
+    let len = ibuffer.Length()? as usize;
+    let mut vec = vec![0u8; len];
+
+    // Create a new DataReader to read from the buffer
+    let reader = windows::Storage::Streams::DataReader::FromBuffer(&ibuffer)?;
+    reader.ReadBytes(&mut vec)?;
+
+    Ok(vec)
+}
+
+/// Reads the playback position, moving the last reported one forward by the
+/// time spent playing since it was reported
+fn current_position_ms(session: &GlobalSystemMediaTransportControlsSession) -> Option<f64> {
+    let timeline = session.GetTimelineProperties().ok()?;
+    let mut position = timespan_ms(timeline.Position().ok()?);
+
+    let playing = session
+        .GetPlaybackInfo()
+        .and_then(|info| info.PlaybackStatus())
+        .is_ok_and(|status| status == GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing);
+    if let (true, Ok(updated)) = (playing, timeline.LastUpdatedTime()) {
+        position += (unix_now_ms() - unix_ms(updated)).max(0.0);
+    }
+    Some(position)
+}
+
+/// Asks the session to move to `position_ms`, clamped to its seekable range
+fn change_position(session: &GlobalSystemMediaTransportControlsSession, position_ms: f64) -> Result<()> {
+    let supported = session
+        .GetPlaybackInfo()
+        .and_then(|info| info.Controls())
+        .and_then(|controls| controls.IsPlaybackPositionEnabled())
+        .unwrap_or(false);
+    if !supported {
+        return Err(Error::not_supported("The session cannot seek"));
+    }
+
+    let mut ticks = (position_ms.max(0.0) * 10_000.0) as i64;
+    if let Ok(timeline) = session.GetTimelineProperties() {
+        if let (Ok(min), Ok(max)) = (timeline.MinSeekTime(), timeline.MaxSeekTime()) {
+            if max.Duration > min.Duration {
+                ticks = ticks.clamp(min.Duration, max.Duration);
+            }
+        }
+    }
+
+    accepted(
+        session.TryChangePlaybackPositionAsync(ticks),
+        Error::new(ErrorStatus::PlatformError, "The session turned down the new position"),
+    )
+}
+
+fn device_enumerator() -> Result<IMMDeviceEnumerator> {
+    unsafe {
+        // Skip CoInitializeEx if already initialized
+        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+        CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).map_err(|err| Error {
+            status: ErrorStatus::BackendUnavailable,
+            ..Error::from(err)
+        })
+    }
+}
+
+/// The data flow of the endpoints of a direction
+fn data_flow(direction: DeviceDirection) -> EDataFlow {
+    match direction {
+        DeviceDirection::Output => eRender,
+        DeviceDirection::Input => eCapture,
+    }
+}
+
+/// Finds an endpoint by its id, or the default endpoint of a direction
+fn get_device(device: DeviceRef) -> Result<IMMDevice> {
+    let device_enumerator = device_enumerator()?;
+    unsafe {
+        match device {
+            DeviceRef::Id(device_id) => device_enumerator.GetDevice(&HSTRING::from(device_id)),
+            DeviceRef::Default(direction) => device_enumerator.GetDefaultAudioEndpoint(data_flow(direction), eConsole),
+        }
+        .map_err(Error::from)
+    }
+}
+
+fn get_endpoint_volume(device: DeviceRef) -> Result<IAudioEndpointVolume> {
+    unsafe { get_device(device)?.Activate(CLSCTX_ALL, None).map_err(Error::from) }
+}
+
+/// Id of the default endpoint of a direction for a role, if there is one
+fn default_device_id(device_enumerator: &IMMDeviceEnumerator, flow: EDataFlow, role: ERole) -> Option<String> {
+    unsafe {
+        let id = device_enumerator.GetDefaultAudioEndpoint(flow, role).and_then(|device| device.GetId()).ok()?;
+        Some(take_string(id))
+    }
+}
+
+/// Reads the channel count and sample rate the audio engine mixes at,
+/// which only active endpoints have
+fn mix_format(device: &IMMDevice) -> Option<(u32, u32)> {
+    unsafe {
+        let client: IAudioClient = device.Activate(CLSCTX_ALL, None).ok()?;
+        let format = client.GetMixFormat().ok().filter(|format| !format.is_null())?;
+        let WAVEFORMATEX { nChannels, nSamplesPerSec, .. } = format.read_unaligned();
+        CoTaskMemFree(Some(format as *const _));
+        Some((nChannels as u32, nSamplesPerSec))
+    }
+}
+
+/// Describes an endpoint, telling whether it is the default by comparing
+/// its id with those of the default endpoints
+fn describe_device(
+    device: &IMMDevice,
+    direction: DeviceDirection,
+    default_id: Option<&str>,
+    communication_id: Option<&str>,
+) -> Result<AudioDevice> {
+    unsafe {
+        let id = take_string(device.GetId()?);
+        let state = match device.GetState()? {
+            DEVICE_STATE_ACTIVE => DeviceState::Active,
+            DEVICE_STATE_DISABLED => DeviceState::Disabled,
+            _ => DeviceState::Unplugged,
+        };
+
+        let properties = device.OpenPropertyStore(STGM_READ)?;
+        let property = |key| {
+            let value = properties.GetValue(key).ok()?.to_string();
+            Some(value).filter(|value| !value.is_empty())
+        };
+        let format = mix_format(device);
+
+        Ok(AudioDevice {
+            name: property(&PKEY_Device_FriendlyName).unwrap_or_else(|| id.clone()),
+            description: property(&PKEY_Device_DeviceDesc),
+            direction,
+            state,
+            is_default: default_id == Some(id.as_str()),
+            is_default_communication: communication_id == Some(id.as_str()),
+            channels: format.map(|(channels, _)| channels),
+            sample_rate: format.map(|(_, sample_rate)| sample_rate),
+            id,
+        })
+    }
+}
+
+/// The undocumented interface the sound settings of Windows change the
+/// default endpoints through. Only `SetDefaultEndpoint` is called, the
+/// methods before it are declared to keep the vtable in order.
+mod policy_config {
+    #![allow(non_snake_case, dead_code)]
+
+    use std::ffi::c_void;
+    use windows::{
+        core::{interface, IUnknown, IUnknown_Vtbl, HRESULT, PCWSTR},
+        Win32::{Foundation::BOOL, Media::Audio::{ERole, WAVEFORMATEX}},
+    };
+
+    #[interface("f8679f50-850a-41cf-9c72-430f290290c8")]
+    pub unsafe trait IPolicyConfig: IUnknown {
+        fn GetMixFormat(&self, device_id: PCWSTR, format: *mut *mut WAVEFORMATEX) -> HRESULT;
+        fn GetDeviceFormat(&self, device_id: PCWSTR, default: BOOL, format: *mut *mut WAVEFORMATEX) -> HRESULT;
+        fn ResetDeviceFormat(&self, device_id: PCWSTR) -> HRESULT;
+        fn SetDeviceFormat(&self, device_id: PCWSTR, endpoint: *const WAVEFORMATEX, mix: *const WAVEFORMATEX) -> HRESULT;
+        fn GetProcessingPeriod(&self, device_id: PCWSTR, default: BOOL, period: *mut i64, minimum: *mut i64) -> HRESULT;
+        fn SetProcessingPeriod(&self, device_id: PCWSTR, period: *const i64) -> HRESULT;
+        fn GetShareMode(&self, device_id: PCWSTR, mode: *mut c_void) -> HRESULT;
+        fn SetShareMode(&self, device_id: PCWSTR, mode: *const c_void) -> HRESULT;
+        fn GetPropertyValue(&self, device_id: PCWSTR, key: *const c_void, value: *mut c_void) -> HRESULT;
+        fn SetPropertyValue(&self, device_id: PCWSTR, key: *const c_void, value: *const c_void) -> HRESULT;
+        pub fn SetDefaultEndpoint(&self, device_id: PCWSTR, role: ERole) -> HRESULT;
+        fn SetEndpointVisibility(&self, device_id: PCWSTR, visible: BOOL) -> HRESULT;
+    }
+}
+
+/// Class id of the object implementing [`IPolicyConfig`]
+const POLICY_CONFIG_CLIENT: GUID = GUID::from_u128(0x870af99c_171d_4f9e_af0d_e63df40c2bc9);
+
+/// Reports volume and mute changes of an endpoint to a watcher
+#[implement(IAudioEndpointVolumeCallback)]
+struct VolumeCallback {
+    watcher: Watcher,
+}
+
+impl IAudioEndpointVolumeCallback_Impl for VolumeCallback_Impl {
+    fn OnNotify(&self, _data: *mut AUDIO_VOLUME_NOTIFICATION_DATA) -> windows::core::Result<()> {
+        self.watcher.notify();
+        Ok(())
+    }
+}
+
+/// Reports an audio session expiring or disconnecting to a watcher
+#[implement(IAudioSessionEvents)]
+struct SessionEventsCallback {
+    watcher: Watcher,
+}
+
+impl IAudioSessionEvents_Impl for SessionEventsCallback_Impl {
+    fn OnDisplayNameChanged(&self, _name: &PCWSTR, _context: *const GUID) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnIconPathChanged(&self, _path: &PCWSTR, _context: *const GUID) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnSimpleVolumeChanged(&self, _volume: f32, _mute: BOOL, _context: *const GUID) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnChannelVolumeChanged(
+        &self,
+        _count: u32,
+        _volumes: *const f32,
+        _changed: u32,
+        _context: *const GUID,
+    ) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnGroupingParamChanged(&self, _param: *const GUID, _context: *const GUID) -> windows::core::Result<()> {
+        Ok(())
+    }
+
+    fn OnStateChanged(&self, state: AudioSessionState) -> windows::core::Result<()> {
+        if state == AudioSessionStateExpired {
+            self.watcher.notify();
+        }
+        Ok(())
+    }
+
+    fn OnSessionDisconnected(&self, _reason: AudioSessionDisconnectReason) -> windows::core::Result<()> {
+        self.watcher.notify();
+        Ok(())
+    }
+}
+
+/// Audio sessions registered for their events, with the callback to
+/// unregister once the watcher retires
+type WatchedAudioSessions = Arc<Mutex<Vec<(IAudioSessionControl, IAudioSessionEvents)>>>;
+
+fn watch_audio_session(watched: &WatchedAudioSessions, watcher: &Watcher, session: IAudioSessionControl) {
+    let events: IAudioSessionEvents = SessionEventsCallback { watcher: watcher.clone() }.into();
+    if unsafe { session.RegisterAudioSessionNotification(&events) }.is_ok() {
+        watched.lock().unwrap_or_else(|err| err.into_inner()).push((session, events));
+    }
+}
+
+/// Reports new audio sessions to a watcher, and watches them for expiry
+#[implement(IAudioSessionNotification)]
+struct SessionCreatedCallback {
+    watcher: Watcher,
+    watched: WatchedAudioSessions,
+}
+
+impl IAudioSessionNotification_Impl for SessionCreatedCallback_Impl {
+    fn OnSessionCreated(&self, session: Option<&IAudioSessionControl>) -> windows::core::Result<()> {
+        if let Some(session) = session {
+            watch_audio_session(&self.watched, &self.watcher, session.clone());
+        }
+        self.watcher.notify();
+        Ok(())
+    }
+}
+
+/// Enumerates the endpoint and application sessions of the default device
+fn load_audio_controller() -> Result<AudioController> {
+    unsafe {
+        let mut controller = AudioController::init(None)?;
+        controller.get_sessions()?;
+        controller.get_default_audio_endpoint_volume_control()?;
+        controller.get_all_process_sessions()?;
+        Ok(controller)
+    }
+}
+
+/// Volume of the render and capture endpoints through Core Audio.
+///
+/// Application sessions come from the [`AudioController`] and are identified
+/// by their session instance id, which tells apart processes of the same
+/// executable.
+pub struct CoreAudioBackend;
+
+impl CoreAudioBackend {
+    /// Runs `f` on the application session with the id `session_id`,
+    /// skipping the endpoint session the controller also holds
+    fn with_session<T>(&self, session_id: &str, f: impl FnOnce(&dyn Session) -> Result<T>) -> Result<T> {
+        let controller = load_audio_controller()?;
+        match controller.get_session_by_id(session_id) {
+            Some(session) if session.kind() == AudioSessionKind::Application => f(session),
+            _ => Err(Error::no_session(session_id)),
+        }
+    }
+}
+
+impl AudioBackend for CoreAudioBackend {
+    fn device_id(&self, device: DeviceRef) -> Result<String> {
+        let device = get_device(device)?;
+        Ok(unsafe { take_string(device.GetId()?) })
+    }
+
+    fn volume(&self, device: DeviceRef) -> Result<f64> {
+        let volume = get_endpoint_volume(device)?;
+        unsafe { volume.GetMasterVolumeLevelScalar().map(|v| v as f64).map_err(Error::from) }
+    }
+
+    fn set_volume(&self, device: DeviceRef, level: f64) -> Result<()> {
+        let volume = get_endpoint_volume(device)?;
+        unsafe { volume.SetMasterVolumeLevelScalar(level as f32, &GUID::zeroed()).map_err(Error::from) }
+    }
+
+    fn muted(&self, device: DeviceRef) -> Result<bool> {
+        let volume = get_endpoint_volume(device)?;
+        unsafe { volume.GetMute().map(|m| m.as_bool()).map_err(Error::from) }
+    }
+
+    fn set_muted(&self, device: DeviceRef, muted: bool) -> Result<()> {
+        let volume = get_endpoint_volume(device)?;
+        unsafe { volume.SetMute(muted, &GUID::zeroed()).map_err(Error::from) }
+    }
+
+    fn channel_volumes(&self, device: DeviceRef) -> Result<Vec<ChannelVolume>> {
+        let device = get_device(device)?;
+        let volume: IAudioEndpointVolume = unsafe { device.Activate(CLSCTX_ALL, None)? };
+        endpoint_channel_volumes(&device, &volume)
+    }
+
+    fn set_channel_volumes(&self, device: DeviceRef, levels: &[f64]) -> Result<()> {
+        set_endpoint_channel_volumes(&get_endpoint_volume(device)?, levels, &GUID::zeroed())
+    }
+
+    fn volume_range(&self, device: DeviceRef) -> Result<VolumeRange> {
+        endpoint_volume_range(&get_endpoint_volume(device)?)
+    }
+
+    fn volume_db(&self, device: DeviceRef) -> Result<f64> {
+        let volume = get_endpoint_volume(device)?;
+        Ok(unsafe { volume.GetMasterVolumeLevel()? } as f64)
+    }
+
+    fn set_volume_db(&self, device: DeviceRef, db: f64) -> Result<()> {
+        set_endpoint_volume_db(&get_endpoint_volume(device)?, db, &GUID::zeroed())
+    }
+
+    /// Leaves out endpoints that are not present, which Windows remembers
+    /// for every device ever plugged in
+    fn list_devices(&self, direction: Option<DeviceDirection>) -> Result<Vec<AudioDevice>> {
+        let device_enumerator = device_enumerator()?;
+        let directions = match direction {
+            Some(direction) => vec![direction],
+            None => vec![DeviceDirection::Output, DeviceDirection::Input],
+        };
+        let states = DEVICE_STATE(DEVICE_STATE_ACTIVE.0 | DEVICE_STATE_DISABLED.0 | DEVICE_STATE_UNPLUGGED.0);
+
+        let mut devices = vec![];
+        for direction in directions {
+            let flow = data_flow(direction);
+            let default_id = default_device_id(&device_enumerator, flow, eConsole);
+            let communication_id = default_device_id(&device_enumerator, flow, eCommunications);
+            let collection = unsafe { device_enumerator.EnumAudioEndpoints(flow, states)? };
+            for index in 0..unsafe { collection.GetCount()? } {
+                // Skip endpoints that go away while being read
+                let device = match unsafe { collection.Item(index) } {
+                    Ok(device) => device,
+                    Err(_) => continue,
+                };
+                if let Ok(device) = describe_device(&device, direction, default_id.as_deref(), communication_id.as_deref()) {
+                    devices.push(device);
+                }
+            }
+        }
+        Ok(devices)
+    }
+
+    /// Makes the endpoint the default for every role, like the sound
+    /// settings do
+    fn set_default_device(&self, device_id: &str) -> Result<()> {
+        // The policy config takes any id, so check the endpoint exists first
+        get_device(DeviceRef::Id(device_id))?;
+        let policy_config: IPolicyConfig = unsafe { CoCreateInstance(&POLICY_CONFIG_CLIENT, None, CLSCTX_ALL) }
+            .map_err(|err| Error {
+                status: ErrorStatus::NotSupported,
+                ..Error::from(err)
+            })?;
+
+        let device_id = HSTRING::from(device_id);
+        for role in [eConsole, eMultimedia, eCommunications] {
+            unsafe { policy_config.SetDefaultEndpoint(PCWSTR(device_id.as_ptr()), role) }.ok()?;
+        }
+        Ok(())
+    }
+
+    fn list_sessions(&self) -> Result<Vec<AudioSession>> {
+        let controller = load_audio_controller()?;
+        Ok(controller
+            .get_all_sessions()
+            .iter()
+            .map(Box::as_ref)
+            .filter(|session| session.kind() == AudioSessionKind::Application)
+            .filter(|session| session.state().is_ok_and(|state| state != SessionState::Expired))
+            .map(AudioSession::from)
+            .collect())
+    }
+
+    fn session_volume(&self, session_id: &str) -> Result<f64> {
+        self.with_session(session_id, |session| session.volume())
+    }
+
+    fn set_session_volume(&self, session_id: &str, level: f64) -> Result<()> {
+        self.with_session(session_id, |session| session.set_volume(level))
+    }
+
+    fn session_muted(&self, session_id: &str) -> Result<bool> {
+        self.with_session(session_id, |session| session.muted())
+    }
+
+    fn set_session_muted(&self, session_id: &str, muted: bool) -> Result<()> {
+        self.with_session(session_id, |session| session.set_muted(muted))
+    }
+
+    fn session_channel_volumes(&self, session_id: &str) -> Result<Vec<ChannelVolume>> {
+        self.with_session(session_id, |session| session.channel_volumes())
+    }
+
+    fn set_session_channel_volumes(&self, session_id: &str, levels: &[f64]) -> Result<()> {
+        self.with_session(session_id, |session| session.set_channel_volumes(levels))
+    }
+
+    fn session_volume_range(&self, session_id: &str) -> Result<VolumeRange> {
+        self.with_session(session_id, |session| session.volume_range())
+    }
+
+    fn session_volume_db(&self, session_id: &str) -> Result<f64> {
+        self.with_session(session_id, |session| session.volume_db())
+    }
+
+    fn set_session_volume_db(&self, session_id: &str, db: f64) -> Result<()> {
+        self.with_session(session_id, |session| session.set_volume_db(db))
+    }
+
+    /// Registers for the control change notifications of the default render
+    /// and capture endpoints, which fire on volume and mute changes, and for
+    /// application sessions being created or expiring
+    fn watch(&self, watcher: Watcher) -> bool {
+        let (ready, started) = mpsc::channel();
+        thread::spawn(move || {
+            let device = get_device(DeviceRef::Default(DeviceDirection::Output)).ok();
+            let volume: IAudioEndpointVolume = match device.as_ref().and_then(|device| unsafe {
+                device.Activate(CLSCTX_ALL, None).ok()
+            }) {
+                Some(volume) => volume,
+                None => return ready.send(false).unwrap_or(()),
+            };
+            let callback: IAudioEndpointVolumeCallback = VolumeCallback { watcher: watcher.clone() }.into();
+            if unsafe { volume.RegisterControlChangeNotify(&callback) }.is_err() {
+                return ready.send(false).unwrap_or(());
+            }
+            // Plenty of machines have no microphone, so only the render
+            // endpoint is required
+            let input_volume: Option<IAudioEndpointVolume> = get_device(DeviceRef::Default(DeviceDirection::Input))
+                .ok()
+                .and_then(|device| unsafe { device.Activate(CLSCTX_ALL, None).ok() })
+                .filter(|volume: &IAudioEndpointVolume| unsafe { volume.RegisterControlChangeNotify(&callback) }.is_ok());
+
+            // Session changes are still picked up by the fallback reads when
+            // this fails, so it does not fail the watch
+            let watched = WatchedAudioSessions::default();
+            let created: IAudioSessionNotification = SessionCreatedCallback {
+                watcher: watcher.clone(),
+                watched: watched.clone(),
+            }
+            .into();
+            let manager: Option<IAudioSessionManager2> = device.and_then(|device| unsafe {
+                device.Activate(CLSCTX_ALL, None).ok()
+            });
+            let manager = manager.filter(|manager| unsafe {
+                // Creation is only reported once the sessions were enumerated
+                if let Ok(sessions) = manager.GetSessionEnumerator() {
+                    for index in 0..sessions.GetCount().unwrap_or(0) {
+                        if let Ok(session) = sessions.GetSession(index) {
+                            watch_audio_session(&watched, &watcher, session);
+                        }
+                    }
+                }
+                manager.RegisterSessionNotification(&created).is_ok()
+            });
+            let _ = ready.send(true);
+
+            while watcher.is_active() {
+                thread::sleep(WATCHER_CHECK_INTERVAL);
+            }
+            let _ = unsafe { volume.UnregisterControlChangeNotify(&callback) };
+            if let Some(input_volume) = input_volume {
+                let _ = unsafe { input_volume.UnregisterControlChangeNotify(&callback) };
+            }
+            if let Some(manager) = manager {
+                let _ = unsafe { manager.UnregisterSessionNotification(&created) };
+            }
+            for (session, events) in watched.lock().unwrap_or_else(|err| err.into_inner()).drain(..) {
+                let _ = unsafe { session.UnregisterAudioSessionNotification(&events) };
+            }
+        });
+        started.recv().unwrap_or(false)
+    }
+}
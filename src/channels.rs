@@ -0,0 +1,200 @@
+//! Per-channel volume and the balance between left and right.
+//!
+//! Backends only read and write the level of each channel. The balance is
+//! worked out from those levels here, the way `pa_cvolume_get_balance` and
+//! `pa_cvolume_set_balance` do it, so it comes out the same on every
+//! platform.
+
+use crate::{
+    error::{Error, Result},
+    ChannelPosition, ChannelVolume,
+};
+
+/// Which side of the listener a channel plays on
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+    Middle,
+}
+
+impl ChannelPosition {
+    fn side(self) -> Side {
+        match self {
+            ChannelPosition::FrontLeft
+            | ChannelPosition::RearLeft
+            | ChannelPosition::FrontLeftOfCenter
+            | ChannelPosition::SideLeft => Side::Left,
+            ChannelPosition::FrontRight
+            | ChannelPosition::RearRight
+            | ChannelPosition::FrontRightOfCenter
+            | ChannelPosition::SideRight => Side::Right,
+            _ => Side::Middle,
+        }
+    }
+
+    /// Positions of the channels of a stream that does not say where they
+    /// play, in the order of the default channel masks of Windows
+    pub(crate) fn layout(count: usize) -> Vec<Self> {
+        const SPEAKERS: [ChannelPosition; 8] = [
+            ChannelPosition::FrontLeft,
+            ChannelPosition::FrontRight,
+            ChannelPosition::FrontCenter,
+            ChannelPosition::Lfe,
+            ChannelPosition::RearLeft,
+            ChannelPosition::RearRight,
+            ChannelPosition::SideLeft,
+            ChannelPosition::SideRight,
+        ];
+
+        match count {
+            1 => vec![ChannelPosition::Mono],
+            // Quadraphonic sound has no center or subwoofer
+            4 => vec![
+                ChannelPosition::FrontLeft,
+                ChannelPosition::FrontRight,
+                ChannelPosition::RearLeft,
+                ChannelPosition::RearRight,
+            ],
+            _ => (0..count)
+                .map(|index| SPEAKERS.get(index).copied().unwrap_or(ChannelPosition::Other))
+                .collect(),
+        }
+    }
+}
+
+/// Fails unless `levels` holds one level for each of `count` channels
+pub(crate) fn check_levels(count: usize, levels: &[f64]) -> Result<()> {
+    match levels.len() == count {
+        true => Ok(()),
+        false => Err(Error::invalid_argument(format!(
+            "Expected a volume level for each of the {count} channels, got {}",
+            levels.len()
+        ))),
+    }
+}
+
+/// Level of the loudest channel on one side
+fn loudest(channels: &[ChannelVolume], side: Side) -> Option<f64> {
+    channels
+        .iter()
+        .filter(|channel| channel.position.side() == side)
+        .map(|channel| channel.volume)
+        .reduce(f64::max)
+}
+
+/// The balance between -1.0 (left only) and 1.0 (right only), which is 0.0
+/// when the channels are not split into left and right
+pub(crate) fn balance(channels: &[ChannelVolume]) -> f64 {
+    let (Some(left), Some(right)) = (loudest(channels, Side::Left), loudest(channels, Side::Right)) else {
+        return 0.0;
+    };
+
+    if left == right {
+        0.0
+    } else if left > right {
+        right / left - 1.0
+    } else {
+        1.0 - left / right
+    }
+}
+
+/// Levels of the channels moved to `balance`. The louder side keeps its
+/// level, so the overall loudness stays the same, and the channels in the
+/// middle are left alone.
+pub(crate) fn balanced(channels: &[ChannelVolume], balance: f64) -> Result<Vec<f64>> {
+    if !(-1.0..=1.0).contains(&balance) {
+        return Err(Error::invalid_argument(format!("Balance must be between -1.0 and 1.0, got {balance}")));
+    }
+    let (Some(left), Some(right)) = (loudest(channels, Side::Left), loudest(channels, Side::Right)) else {
+        return Err(Error::not_supported("There are no left and right channels to balance"));
+    };
+
+    let level = left.max(right);
+    let (new_left, new_right) = match balance < 0.0 {
+        true => (level, level * (1.0 + balance)),
+        false => (level * (1.0 - balance), level),
+    };
+    // Channels of a side keep their levels relative to each other, unless
+    // the side was silent
+    let scale = |volume: f64, old: f64, new: f64| if old == 0.0 { new } else { volume * (new / old) };
+
+    Ok(channels
+        .iter()
+        .map(|channel| match channel.position.side() {
+            Side::Left => scale(channel.volume, left, new_left),
+            Side::Right => scale(channel.volume, right, new_right),
+            Side::Middle => channel.volume,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stereo(left: f64, right: f64) -> Vec<ChannelVolume> {
+        vec![
+            ChannelVolume { position: ChannelPosition::FrontLeft, volume: left },
+            ChannelVolume { position: ChannelPosition::FrontRight, volume: right },
+        ]
+    }
+
+    fn with_levels(channels: &[ChannelVolume], levels: Vec<f64>) -> Vec<ChannelVolume> {
+        channels
+            .iter()
+            .zip(levels)
+            .map(|(channel, volume)| ChannelVolume { position: channel.position, volume })
+            .collect()
+    }
+
+    #[test]
+    fn balance_follows_the_quieter_side() {
+        assert_eq!(balance(&stereo(0.8, 0.8)), 0.0);
+        assert_eq!(balance(&stereo(0.8, 0.4)), -0.5);
+        assert_eq!(balance(&stereo(0.4, 0.8)), 0.5);
+        assert_eq!(balance(&stereo(0.8, 0.0)), -1.0);
+        assert_eq!(balance(&stereo(0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn balance_round_trips() {
+        let channels = vec![
+            ChannelVolume { position: ChannelPosition::FrontLeft, volume: 0.6 },
+            ChannelVolume { position: ChannelPosition::FrontRight, volume: 0.9 },
+            ChannelVolume { position: ChannelPosition::FrontCenter, volume: 0.3 },
+            ChannelVolume { position: ChannelPosition::RearLeft, volume: 0.3 },
+            ChannelVolume { position: ChannelPosition::RearRight, volume: 0.45 },
+        ];
+        for target in [-1.0, -0.75, -0.25, 0.0, 0.3, 0.9, 1.0] {
+            let levels = balanced(&channels, target).unwrap();
+            let moved = with_levels(&channels, levels);
+            assert!((balance(&moved) - target).abs() < 1e-9, "balance {target}");
+        }
+    }
+
+    #[test]
+    fn rebalancing_keeps_the_louder_side_and_the_middle() {
+        let channels = vec![
+            ChannelVolume { position: ChannelPosition::FrontLeft, volume: 0.6 },
+            ChannelVolume { position: ChannelPosition::FrontRight, volume: 0.8 },
+            ChannelVolume { position: ChannelPosition::Lfe, volume: 0.5 },
+        ];
+        assert_eq!(balanced(&channels, -0.5).unwrap(), vec![0.8, 0.4, 0.5]);
+        assert_eq!(balanced(&channels, 0.0).unwrap(), vec![0.8, 0.8, 0.5]);
+    }
+
+    #[test]
+    fn silent_sides_come_back_up() {
+        assert_eq!(balanced(&stereo(0.0, 0.6), 0.0).unwrap(), vec![0.6, 0.6]);
+    }
+
+    #[test]
+    fn rejects_what_cannot_be_balanced() {
+        assert!(balanced(&stereo(0.5, 0.5), 1.5).is_err());
+        assert!(balanced(&stereo(0.5, 0.5), f64::NAN).is_err());
+        let mono = vec![ChannelVolume { position: ChannelPosition::Mono, volume: 0.5 }];
+        assert_eq!(balance(&mono), 0.0);
+        assert!(balanced(&mono, 0.5).is_err());
+    }
+}
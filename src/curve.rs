@@ -0,0 +1,285 @@
+//! Perceptual volume curves.
+//!
+//! The 0.0 to 1.0 level of each platform follows a scale of its own: Windows
+//! tapers endpoint volumes and applies application volumes as they are,
+//! while PulseAudio uses a cubic scale. Every backend can also read and
+//! write volumes in dB within the range the device reports, so once a curve
+//! is picked with `setVolumeCurve`, levels are worked out from the dB value
+//! here and a level means the same loudness on every platform.
+
+use std::sync::RwLock;
+use napi_derive::napi;
+use crate::{
+    backend::{AudioBackend, DeviceRef},
+    error::{Error, Result},
+    StepDirection, VolumeRange,
+};
+
+/// How volume levels between 0.0 and 1.0 map to loudness
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum VolumeCurve {
+    /// The level is the amplitude the signal is scaled by
+    #[napi(value = "linear")]
+    Linear,
+    /// The level is the cube root of the amplitude, like the percentages of
+    /// PulseAudio, which is close to how loud people hear it
+    #[napi(value = "cubic")]
+    Cubic,
+    /// The level moves evenly through the dB range of the device
+    #[napi(value = "db")]
+    Db,
+}
+
+static CURVE: RwLock<Option<VolumeCurve>> = RwLock::new(None);
+
+/// Chooses the curve the volume levels of devices and applications follow,
+/// or goes back to the scale of the platform, which is the default
+/// @param curve - `linear`, `cubic` or `db`, or nothing for the platform scale
+#[napi]
+pub fn set_volume_curve(curve: Option<VolumeCurve>) {
+    *CURVE.write().unwrap_or_else(|err| err.into_inner()) = curve;
+}
+
+/// Gets the curve the volume levels follow
+/// @returns The curve, or null while levels follow the scale of the platform
+#[napi]
+pub fn get_volume_curve() -> Option<VolumeCurve> {
+    *CURVE.read().unwrap_or_else(|err| err.into_inner())
+}
+
+/// How far `volumeStep` moves a volume that has no steps of its own
+const DEFAULT_STEP: f64 = 0.05;
+
+/// Quietest volume of the range reported for volumes applied in software,
+/// which go on down to silence
+const SOFTWARE_MIN_DB: f64 = -60.0;
+
+impl VolumeRange {
+    /// Range of a volume applied in software, which is never amplified
+    pub(crate) fn software(steps: Option<u32>) -> Self {
+        VolumeRange {
+            min_db: SOFTWARE_MIN_DB,
+            max_db: 0.0,
+            increment_db: None,
+            steps,
+        }
+    }
+}
+
+impl VolumeRange {
+    /// Whether the range holds a single volume, as on devices that cannot
+    /// change theirs, which has the dB curve go straight from silence to full
+    fn is_fixed(&self) -> bool {
+        self.max_db <= self.min_db
+    }
+}
+
+impl VolumeCurve {
+    /// The level of a volume at `db`, which is 0.0 for silence
+    pub(crate) fn level(self, db: f64, range: &VolumeRange) -> f64 {
+        let level = match self {
+            VolumeCurve::Linear => 10f64.powf((db - range.max_db) / 20.0),
+            VolumeCurve::Cubic => 10f64.powf((db - range.max_db) / 60.0),
+            VolumeCurve::Db if range.is_fixed() => match db < range.min_db {
+                true => 0.0,
+                false => 1.0,
+            },
+            VolumeCurve::Db => (db - range.min_db) / (range.max_db - range.min_db),
+        };
+        level.clamp(0.0, 1.0)
+    }
+
+    /// The volume in dB a level stands for. A level of 0.0 is silence,
+    /// which is negative infinity.
+    pub(crate) fn db(self, level: f64, range: &VolumeRange) -> f64 {
+        if level <= 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        match self {
+            VolumeCurve::Linear => range.max_db + 20.0 * level.log10(),
+            VolumeCurve::Cubic => range.max_db + 60.0 * level.log10(),
+            VolumeCurve::Db if range.is_fixed() => range.max_db,
+            VolumeCurve::Db => range.min_db + level * (range.max_db - range.min_db),
+        }
+    }
+}
+
+/// A device or an application, whose volume is read and written through
+/// the curve in use
+#[derive(Clone, Copy)]
+pub(crate) enum Target<'a> {
+    Device(DeviceRef<'a>),
+    Session(&'a str),
+}
+
+impl Target<'_> {
+    pub(crate) fn range(self, audio: &dyn AudioBackend) -> Result<VolumeRange> {
+        match self {
+            Target::Device(device) => audio.volume_range(device),
+            Target::Session(session_id) => audio.session_volume_range(session_id),
+        }
+    }
+
+    pub(crate) fn db(self, audio: &dyn AudioBackend) -> Result<f64> {
+        match self {
+            Target::Device(device) => audio.volume_db(device),
+            Target::Session(session_id) => audio.session_volume_db(session_id),
+        }
+    }
+
+    /// Sets the volume in dB, which may not go above the range. Lower values
+    /// give the quietest volume there is.
+    pub(crate) fn set_db(self, audio: &dyn AudioBackend, db: f64) -> Result<()> {
+        let range = self.range(audio)?;
+        if db.is_nan() || db > range.max_db {
+            return Err(Error::invalid_argument(format!(
+                "Volume must be at most {} dB, got {db}",
+                range.max_db
+            )));
+        }
+        self.write_db(audio, db)
+    }
+
+    fn write_db(self, audio: &dyn AudioBackend, db: f64) -> Result<()> {
+        match self {
+            Target::Device(device) => audio.set_volume_db(device, db),
+            Target::Session(session_id) => audio.set_session_volume_db(session_id, db),
+        }
+    }
+
+    /// The volume level on the curve in use
+    pub(crate) fn level(self, audio: &dyn AudioBackend) -> Result<f64> {
+        match get_volume_curve() {
+            Some(curve) => Ok(curve.level(self.db(audio)?, &self.range(audio)?)),
+            None => match self {
+                Target::Device(device) => audio.volume(device),
+                Target::Session(session_id) => audio.session_volume(session_id),
+            },
+        }
+    }
+
+    /// Sets the volume level on the curve in use
+    pub(crate) fn set_level(self, audio: &dyn AudioBackend, level: f64) -> Result<()> {
+        match get_volume_curve() {
+            Some(curve) => self.write_db(audio, curve.db(level, &self.range(audio)?)),
+            None => match self {
+                Target::Device(device) => audio.set_volume(device, level),
+                Target::Session(session_id) => audio.set_session_volume(session_id, level),
+            },
+        }
+    }
+
+    /// Moves the volume level up or down by `step`, which defaults to one
+    /// step of the device. On devices with a fixed number of steps the level
+    /// lands on one of them, at least one step away from where it was.
+    /// Returns the new level.
+    pub(crate) fn step(self, audio: &dyn AudioBackend, direction: StepDirection, step: Option<f64>) -> Result<f64> {
+        if let Some(step) = step.filter(|&step| step == 0.0 || !(0.0..=1.0).contains(&step)) {
+            return Err(Error::invalid_argument(format!(
+                "Volume step must be above 0.0 and at most 1.0, got {step}"
+            )));
+        }
+        let intervals = self.range(audio)?.steps.filter(|&steps| steps > 1).map(|steps| f64::from(steps - 1));
+        let step = step.or(intervals.map(|intervals| 1.0 / intervals)).unwrap_or(DEFAULT_STEP);
+
+        let level = stepped(self.level(audio)?, direction, step, intervals);
+        self.set_level(audio, level)?;
+        Ok(level)
+    }
+}
+
+/// The level `step` up or down from `current`. With `intervals` between the
+/// steps of a device, the level lands on one of them, at least one step away
+/// from where it was.
+fn stepped(current: f64, direction: StepDirection, step: f64, intervals: Option<f64>) -> f64 {
+    let mut level = match direction {
+        StepDirection::Up => current + step,
+        StepDirection::Down => current - step,
+    };
+    if let Some(intervals) = intervals {
+        let from = (current * intervals).round();
+        let to = match direction {
+            StepDirection::Up => (level * intervals).round().max(from + 1.0),
+            StepDirection::Down => (level * intervals).round().min(from - 1.0),
+        };
+        level = to / intervals;
+    }
+    level.clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURVES: [VolumeCurve; 3] = [VolumeCurve::Linear, VolumeCurve::Cubic, VolumeCurve::Db];
+
+    fn hardware() -> VolumeRange {
+        VolumeRange {
+            min_db: -65.25,
+            max_db: 0.0,
+            increment_db: Some(0.03125),
+            steps: None,
+        }
+    }
+
+    #[test]
+    fn levels_round_trip_through_db() {
+        for curve in CURVES {
+            for range in [hardware(), VolumeRange::software(None)] {
+                for level in [0.01, 0.25, 0.5, 0.75, 1.0] {
+                    let db = curve.db(level, &range);
+                    assert!((curve.level(db, &range) - level).abs() < 1e-9, "{curve:?} at {level}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn silence_is_level_zero() {
+        for curve in CURVES {
+            assert_eq!(curve.db(0.0, &hardware()), f64::NEG_INFINITY);
+            assert_eq!(curve.level(f64::NEG_INFINITY, &hardware()), 0.0);
+        }
+    }
+
+    #[test]
+    fn curves_grow_louder_differently() {
+        let range = VolumeRange::software(None);
+        assert!((VolumeCurve::Linear.db(0.5, &range) - -6.0206).abs() < 1e-3);
+        assert!((VolumeCurve::Cubic.db(0.5, &range) - -18.0618).abs() < 1e-3);
+        assert_eq!(VolumeCurve::Db.db(0.5, &range), -30.0);
+    }
+
+    #[test]
+    fn fixed_ranges_give_no_nan() {
+        let range = VolumeRange {
+            min_db: -10.0,
+            max_db: -10.0,
+            increment_db: None,
+            steps: None,
+        };
+        assert_eq!(VolumeCurve::Db.level(-10.0, &range), 1.0);
+        assert_eq!(VolumeCurve::Db.level(f64::NEG_INFINITY, &range), 0.0);
+        assert_eq!(VolumeCurve::Db.db(0.5, &range), -10.0);
+        assert_eq!(VolumeCurve::Db.level(VolumeCurve::Db.db(0.5, &range), &range), 1.0);
+    }
+
+    #[test]
+    fn steps_land_on_device_steps() {
+        // A device with 11 steps, 0.1 apart
+        let intervals = Some(10.0);
+        assert_eq!(stepped(0.5, StepDirection::Up, 0.1, intervals), 0.6);
+        assert_eq!(stepped(0.52, StepDirection::Up, 0.01, intervals), 0.6);
+        assert_eq!(stepped(0.5, StepDirection::Down, 0.01, intervals), 0.4);
+        assert_eq!(stepped(0.95, StepDirection::Up, 0.1, intervals), 1.0);
+        assert_eq!(stepped(0.0, StepDirection::Down, 0.1, intervals), 0.0);
+    }
+
+    #[test]
+    fn steps_without_device_steps_stay_within_range() {
+        assert!((stepped(0.5, StepDirection::Up, 0.05, None) - 0.55).abs() < 1e-9);
+        assert_eq!(stepped(0.98, StepDirection::Up, 0.05, None), 1.0);
+        assert_eq!(stepped(0.02, StepDirection::Down, 0.05, None), 0.0);
+    }
+}
@@ -0,0 +1,267 @@
+//! Errors of the exported functions.
+//!
+//! Backends report why a request failed as an [`Error`], whose
+//! [`ErrorStatus`] becomes the `code` of the error JavaScript receives.
+//! Exported functions hand their result over as an [`Outcome`], which
+//! rejects or throws that error, or resolves with the `false` or `null` of
+//! the boolean API while the compatibility mode is on.
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use napi::{
+    bindgen_prelude::ToNapiValue, sys, Env, JsUnknown, NapiRaw, NapiValue, Status,
+};
+use napi_derive::napi;
+use crate::SeekResult;
+
+/// Why a call failed, given as the `code` of the error
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorStatus {
+    /// There is no such session or device, or no session at all
+    #[napi(value = "ERR_NO_SESSION")]
+    NoSession,
+    /// The player, device or platform does not support the request
+    #[napi(value = "ERR_NOT_SUPPORTED")]
+    NotSupported,
+    /// The system refused access
+    #[napi(value = "ERR_PERMISSION_DENIED")]
+    PermissionDenied,
+    /// The platform service cannot be reached, such as the D-Bus session
+    /// bus or the sound server
+    #[napi(value = "ERR_BACKEND_UNAVAILABLE")]
+    BackendUnavailable,
+    /// The platform did not answer in time
+    #[napi(value = "ERR_TIMEOUT")]
+    Timeout,
+    /// An argument is out of range or malformed
+    #[napi(value = "ERR_INVALID_ARGUMENT")]
+    InvalidArgument,
+    /// The platform failed with an error of its own, given as `nativeCode`
+    /// where it has one
+    #[napi(value = "ERR_PLATFORM")]
+    PlatformError,
+}
+
+impl AsRef<str> for ErrorStatus {
+    fn as_ref(&self) -> &str {
+        match self {
+            ErrorStatus::NoSession => "ERR_NO_SESSION",
+            ErrorStatus::NotSupported => "ERR_NOT_SUPPORTED",
+            ErrorStatus::PermissionDenied => "ERR_PERMISSION_DENIED",
+            ErrorStatus::BackendUnavailable => "ERR_BACKEND_UNAVAILABLE",
+            ErrorStatus::Timeout => "ERR_TIMEOUT",
+            ErrorStatus::InvalidArgument => "ERR_INVALID_ARGUMENT",
+            ErrorStatus::PlatformError => "ERR_PLATFORM",
+        }
+    }
+}
+
+/// A failed request, with the reason and the code of the platform error
+/// behind it
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub status: ErrorStatus,
+    pub message: String,
+    /// HRESULT on Windows, or the error code of the sound server on Linux
+    pub native_code: Option<i64>,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn new(status: ErrorStatus, message: impl Into<String>) -> Self {
+        Error {
+            status,
+            message: message.into(),
+            native_code: None,
+        }
+    }
+
+    pub fn platform(native_code: i64, message: impl Into<String>) -> Self {
+        Error {
+            status: ErrorStatus::PlatformError,
+            message: message.into(),
+            native_code: Some(native_code),
+        }
+    }
+
+    pub fn no_session(session_id: &str) -> Self {
+        Error::new(ErrorStatus::NoSession, format!("No session {session_id}"))
+    }
+
+    pub fn no_device(device_id: &str) -> Self {
+        Error::new(ErrorStatus::NoSession, format!("No device {device_id}"))
+    }
+
+    pub fn not_supported(message: impl Into<String>) -> Self {
+        Error::new(ErrorStatus::NotSupported, message)
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Error::new(ErrorStatus::InvalidArgument, message)
+    }
+
+    /// Keeps the status and native code, putting `context` in front of the
+    /// message
+    pub fn context(self, context: &str) -> Self {
+        Error {
+            message: format!("{context}: {}", self.message),
+            ..self
+        }
+    }
+
+    /// Creates the JavaScript error, with `code` and `nativeCode` set
+    unsafe fn to_js(&self, env: sys::napi_env) -> napi::Result<JsUnknown> {
+        let env = unsafe { Env::from_raw(env) };
+        let mut error = env.create_error(napi::Error::new(Status::GenericFailure, self.message.clone()))?;
+        error.set_named_property("code", env.create_string(self.status.as_ref())?)?;
+        if let Some(native_code) = self.native_code {
+            error.set_named_property("nativeCode", env.create_int64(native_code)?)?;
+        }
+        Ok(unsafe { JsUnknown::from_raw_unchecked(env.raw(), error.raw()) })
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.native_code {
+            Some(native_code) => write!(f, "{} ({:#x})", self.message, native_code),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Keeps the HRESULT as the native code, going by it for the status where
+/// it has a meaning of its own
+#[cfg(windows)]
+impl From<windows::core::Error> for Error {
+    fn from(err: windows::core::Error) -> Self {
+        use windows::Win32::{
+            Foundation::{E_ACCESSDENIED, E_INVALIDARG, E_NOINTERFACE, E_NOTIMPL, E_POINTER, ERROR_NOT_FOUND},
+            Media::Audio::AUDCLNT_E_DEVICE_INVALIDATED,
+        };
+        // What Core Audio returns for device ids it does not know
+        const E_NOTFOUND: windows::core::HRESULT = windows::core::HRESULT::from_win32(ERROR_NOT_FOUND.0);
+
+        let code = err.code();
+        let status = match code {
+            E_ACCESSDENIED => ErrorStatus::PermissionDenied,
+            E_NOTIMPL | E_NOINTERFACE => ErrorStatus::NotSupported,
+            E_INVALIDARG | E_POINTER => ErrorStatus::InvalidArgument,
+            AUDCLNT_E_DEVICE_INVALIDATED | E_NOTFOUND => ErrorStatus::NoSession,
+            _ => ErrorStatus::PlatformError,
+        };
+        Error {
+            status,
+            message: err.message(),
+            native_code: Some(code.0 as u32 as i64),
+        }
+    }
+}
+
+/// How failed calls report to JavaScript
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorMode {
+    /// Promises reject and functions throw with the status as `code`
+    #[napi(value = "throw")]
+    Throw,
+    /// Calls resolve with `false`, `null` or an empty list on failure, as
+    /// before errors had a status
+    #[napi(value = "compat")]
+    Compat,
+}
+
+static COMPAT: AtomicBool = AtomicBool::new(false);
+
+/// Chooses whether failures reject and throw with a coded error, which is
+/// the default, or resolve with `false` and `null` like the boolean API
+/// @param mode - `throw` or `compat`
+#[napi]
+pub fn set_error_mode(mode: ErrorMode) {
+    COMPAT.store(mode == ErrorMode::Compat, Ordering::Relaxed);
+}
+
+/// The value standing for a failure in the compatibility mode
+pub trait Fallback {
+    fn fallback(error: &Error) -> Self;
+}
+
+impl Fallback for bool {
+    fn fallback(_error: &Error) -> Self {
+        false
+    }
+}
+
+impl<T> Fallback for Option<T> {
+    fn fallback(_error: &Error) -> Self {
+        None
+    }
+}
+
+impl<T> Fallback for Vec<T> {
+    fn fallback(_error: &Error) -> Self {
+        vec![]
+    }
+}
+
+impl Fallback for SeekResult {
+    fn fallback(error: &Error) -> Self {
+        match error.status {
+            ErrorStatus::NoSession => SeekResult::NoSession,
+            ErrorStatus::NotSupported => SeekResult::Unsupported,
+            _ => SeekResult::Failed,
+        }
+    }
+}
+
+/// The result of an exported function on its way to JavaScript
+pub struct Outcome<T>(pub Result<T>);
+
+impl<T> From<Result<T>> for Outcome<T> {
+    fn from(result: Result<T>) -> Self {
+        Outcome(result)
+    }
+}
+
+impl<T: ToNapiValue + Fallback> ToNapiValue for Outcome<T> {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> napi::Result<sys::napi_value> {
+        match val.0 {
+            Ok(value) => unsafe { T::to_napi_value(env, value) },
+            Err(error) if COMPAT.load(Ordering::Relaxed) => unsafe { T::to_napi_value(env, T::fallback(&error)) },
+            // An error holding a JavaScript value is thrown or rejected as is
+            Err(error) => Err(napi::Error::from(unsafe { error.to_js(env)? })),
+        }
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use windows::Win32::{
+        Foundation::{E_ACCESSDENIED, E_FAIL, E_INVALIDARG, E_NOTIMPL, ERROR_NOT_FOUND},
+        Media::Audio::AUDCLNT_E_DEVICE_INVALIDATED,
+    };
+    use super::*;
+
+    #[test]
+    fn maps_hresults_to_statuses() {
+        let cases = [
+            (E_ACCESSDENIED, ErrorStatus::PermissionDenied),
+            (E_NOTIMPL, ErrorStatus::NotSupported),
+            (E_INVALIDARG, ErrorStatus::InvalidArgument),
+            (AUDCLNT_E_DEVICE_INVALIDATED, ErrorStatus::NoSession),
+            (windows::core::HRESULT::from_win32(ERROR_NOT_FOUND.0), ErrorStatus::NoSession),
+            (E_FAIL, ErrorStatus::PlatformError),
+        ];
+        for (code, status) in cases {
+            let error = Error::from(windows::core::Error::from(code));
+            assert_eq!(error.status, status);
+            assert_eq!(error.native_code, Some(code.0 as u32 as i64));
+        }
+    }
+}
@@ -0,0 +1,848 @@
+//! The native event hub behind `subscribeToEvents`.
+//!
+//! One thread reads the media and audio state, or waits for the backends to
+//! push their changes, and fans the resulting events out to every
+//! subscriber whose options accept them. Subscribers attach and detach while
+//! it runs. The thread starts with the first subscriber and exits with the
+//! last one, which also retires the backend watchers it started.
+
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc, Mutex, MutexGuard, Weak,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use crate::{
+    backend::{self, unix_now_ms, AudioBackend, ChangeSignal, DeviceRef, MediaBackend},
+    events::{EventData, EventType, SessionKind, SubscriptionOptions, TrackEndReason},
+    DeviceDirection, MediaInfo,
+};
+
+/// How often the state is read when a backend cannot push its changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often the state is read anyway when the backends push their
+/// changes, in case a platform drops a notification
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the position is reported when a subscriber asks for position
+/// events without giving an interval
+const POSITION_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest position interval, which keeps the hub from spinning
+const MIN_POSITION_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Shortest read interval, which keeps the hub from spinning on backends
+/// that have to be polled
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long stopping the last subscriber waits for the hub thread to exit.
+/// A thread still stuck in a read of an unresponsive backend by then exits
+/// on its own once the read returns.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How far a reported position may drift from where playback should be
+/// before it counts as a seek
+const SEEK_TOLERANCE_MS: f64 = 1500.0;
+
+/// How long before the end of a track `TrackNearEnd` is sent when a
+/// subscriber gives no threshold
+const NEAR_END_THRESHOLD_MS: f64 = 10_000.0;
+
+/// How close to its end a track must have played to count as completed
+/// rather than skipped, which covers players moving on a bit early
+const END_TOLERANCE_MS: f64 = 2000.0;
+
+pub type EventCallback = ThreadsafeFunction<EventData, ErrorStrategy::Fatal>;
+
+/// The media a subscriber follows
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Scope {
+    Current,
+    Session(String),
+    SourceApp(String),
+}
+
+impl Scope {
+    fn of(options: &SubscriptionOptions) -> Self {
+        match (&options.session_id, &options.source_app_id) {
+            (Some(session_id), _) => Scope::Session(session_id.clone()),
+            (None, Some(source_app_id)) => Scope::SourceApp(source_app_id.clone()),
+            (None, None) => Scope::Current,
+        }
+    }
+
+    /// Picks the session the scope currently points at
+    fn session(&self, media: &dyn MediaBackend) -> Option<String> {
+        match self {
+            Scope::Current => media.current_session().ok().flatten(),
+            Scope::Session(session_id) => Some(session_id.clone()),
+            Scope::SourceApp(source_app_id) => {
+                // Several sessions may share an app, in which case the
+                // current one wins
+                let mut sessions = crate::media_sessions().ok()?;
+                sessions.sort_by_key(|session| !session.is_current);
+                let session = sessions.into_iter().find(|session| &session.source_app_id == source_app_id)?;
+                Some(session.id)
+            }
+        }
+    }
+
+    /// Reads the media of the scope. The thumbnail is hashed on every read,
+    /// as artwork can change within a track, which is affordable since
+    /// the backends are only read on changes and polls.
+    fn read(&self) -> Option<Media> {
+        let media = backend::media();
+        let session_id = self.session(media.as_ref())?;
+        let info = media.media_info(&session_id).ok()?;
+        let artwork = match info.has_thumbnail {
+            true => media.thumbnail(&session_id).ok().flatten().map(|thumbnail| {
+                let mut hasher = DefaultHasher::new();
+                thumbnail.hash(&mut hasher);
+                hasher.finish()
+            }),
+            false => None,
+        };
+        Some(Media { session_id, info, artwork })
+    }
+}
+
+/// The media of a scope as of one read
+#[derive(Clone)]
+struct Media {
+    /// Session the media was read from, which a scope following the
+    /// current session or an app can switch between reads
+    session_id: String,
+    info: MediaInfo,
+    /// Hash of the thumbnail, which tells when the artwork changed
+    artwork: Option<u64>,
+}
+
+/// A media or audio session as of one read
+#[derive(Clone)]
+struct ListedSession {
+    kind: SessionKind,
+    id: String,
+    source_app_id: String,
+}
+
+impl ListedSession {
+    fn is(&self, other: &ListedSession) -> bool {
+        self.kind == other.kind && self.id == other.id
+    }
+}
+
+/// Every session as of one read
+#[derive(Clone)]
+struct Sessions {
+    listed: Vec<ListedSession>,
+    current: Option<ListedSession>,
+}
+
+impl Sessions {
+    fn read() -> Self {
+        let mut listed = vec![];
+        let mut current = None;
+        for session in crate::media_sessions().unwrap_or_default() {
+            let session_is_current = session.is_current;
+            let session = ListedSession {
+                kind: SessionKind::Media,
+                id: session.id,
+                source_app_id: session.source_app_id,
+            };
+            if session_is_current {
+                current = Some(session.clone());
+            }
+            listed.push(session);
+        }
+        listed.extend(backend::audio().list_sessions().unwrap_or_default().into_iter().map(|session| ListedSession {
+            kind: SessionKind::Audio,
+            id: session.id,
+            source_app_id: session.name,
+        }));
+        Sessions { listed, current }
+    }
+
+    /// Lists the session events telling `last` apart from this read
+    fn changes(&self, last: &Sessions) -> Vec<EventData> {
+        let mut events = vec![];
+        for session in &last.listed {
+            if !self.listed.iter().any(|listed| listed.is(session)) {
+                events.push(session_event(EventType::SessionRemoved, Some(session)));
+            }
+        }
+        for session in &self.listed {
+            if !last.listed.iter().any(|listed| listed.is(session)) {
+                events.push(session_event(EventType::SessionAdded, Some(session)));
+            }
+        }
+        let current_id = |sessions: &Sessions| sessions.current.as_ref().map(|session| session.id.clone());
+        if current_id(self) != current_id(last) {
+            events.push(session_event(EventType::CurrentSessionChanged, self.current.as_ref()));
+        }
+        events
+    }
+}
+
+/// Lists the fields telling apart two reads of the same media, by their
+/// name in JavaScript, grouped by the event reporting them
+fn media_changes(last: &Media, current: &Media) -> Vec<(EventType, Vec<String>)> {
+    let (last_info, info) = (&last.info, &current.info);
+    let mut metadata = vec![];
+    macro_rules! compare {
+        ($($field:ident => $name:literal),* $(,)?) => {
+            $(
+                if last_info.$field != info.$field {
+                    metadata.push($name.to_string());
+                }
+            )*
+        };
+    }
+    compare! {
+        title => "title",
+        artist => "artist",
+        album => "album",
+        album_artist => "albumArtist",
+        has_thumbnail => "hasThumbnail",
+        subtitle => "subtitle",
+        track_number => "trackNumber",
+        track_count => "trackCount",
+        genres => "genres",
+        playback_type => "playbackType",
+        duration_ms => "durationMs",
+        source_app_id => "sourceAppId",
+        track_id => "trackId",
+    }
+    if last.artwork != current.artwork {
+        metadata.push("thumbnail".to_string());
+    }
+
+    let mut changes = vec![];
+    if !metadata.is_empty() {
+        changes.push((EventType::MediaChange, metadata));
+    }
+    if last_info.playback_status != info.playback_status {
+        changes.push((EventType::PlaybackChange, vec!["playbackStatus".to_string()]));
+    }
+    if last_info.shuffle != info.shuffle {
+        changes.push((EventType::ShuffleChange, vec!["shuffle".to_string()]));
+    }
+    if last_info.repeat_mode != info.repeat_mode {
+        changes.push((EventType::RepeatModeChange, vec!["repeatMode".to_string()]));
+    }
+    if last_info.playback_rate != info.playback_rate {
+        changes.push((EventType::PlaybackRateChange, vec!["playbackRate".to_string()]));
+    }
+    if last_info.capabilities != info.capabilities {
+        changes.push((EventType::CapabilitiesChange, vec!["capabilities".to_string()]));
+    }
+    changes
+}
+
+/// Where playback of `info` is at `at_ms`, in milliseconds since the Unix
+/// epoch, moving the reported position forward at the playback rate while
+/// playing
+fn position_at(info: &MediaInfo, at_ms: f64) -> Option<f64> {
+    let position = info.position_ms?;
+    if info.playback_status != "playing" {
+        return Some(position);
+    }
+    let elapsed = info.last_updated.map_or(0.0, |updated| (at_ms - updated).max(0.0));
+    let position = position + elapsed * info.playback_rate.unwrap_or(1.0);
+    Some(info.duration_ms.map_or(position, |duration| position.min(duration)))
+}
+
+/// Whether both reads are of the same track, going by the track ids when
+/// the player gives them
+fn same_track(last: &MediaInfo, current: &MediaInfo) -> bool {
+    if let (Some(last_id), Some(current_id)) = (&last.track_id, &current.track_id) {
+        return last_id == current_id;
+    }
+    last.title == current.title
+        && last.artist == current.artist
+        && last.album == current.album
+        && last.duration_ms == current.duration_ms
+}
+
+/// Whether the player reported a position that normal playback since the
+/// last read does not explain
+fn seeked(last: &MediaInfo, current: &MediaInfo) -> bool {
+    let reported = match current.position_ms {
+        Some(position) => position,
+        None => return false,
+    };
+    let updated = current.position_ms != last.position_ms || current.last_updated != last.last_updated;
+    if !updated || !same_track(last, current) {
+        return false;
+    }
+    let expected = position_at(last, current.last_updated.unwrap_or_else(unix_now_ms));
+    expected.is_some_and(|expected| (reported - expected).abs() > SEEK_TOLERANCE_MS)
+}
+
+/// Tells whether the track of `last` ended by the read of `current`, and
+/// where it stopped playing. It ended when another track took its place,
+/// when playback stopped or paused at its end, or when it started over
+/// after playing to its end.
+fn track_end(last: &MediaInfo, current: &MediaInfo) -> Option<(TrackEndReason, Option<f64>)> {
+    // Nothing was in progress
+    if last.playback_status == "stopped" {
+        return None;
+    }
+    let position = position_at(last, unix_now_ms());
+    let at_end = |position: Option<f64>| {
+        matches!((position, last.duration_ms), (Some(position), Some(duration)) if position >= duration - END_TOLERANCE_MS)
+    };
+
+    if !same_track(last, current) {
+        let reason = match (position, last.duration_ms) {
+            (Some(_), Some(_)) if at_end(position) => TrackEndReason::Completed,
+            (Some(_), Some(_)) => TrackEndReason::Skipped,
+            _ => TrackEndReason::Unknown,
+        };
+        return Some((reason, position));
+    }
+    if last.playback_status != "playing" {
+        return None;
+    }
+
+    let ended = if current.playback_status != "playing" {
+        // Some players rewind when they stop, others stay at the end
+        at_end(position) || at_end(current.position_ms)
+    } else {
+        // Repeating the track or a single-track playlist
+        let expected = position_at(last, current.last_updated.unwrap_or_else(unix_now_ms));
+        at_end(expected) && current.position_ms.is_some_and(|position| position < END_TOLERANCE_MS)
+    };
+    ended.then(|| (TrackEndReason::Completed, last.duration_ms.or(position)))
+}
+
+/// An event of `event_type` carrying nothing yet
+fn event(event_type: EventType) -> EventData {
+    EventData {
+        event_type,
+        media_info: None,
+        volume: None,
+        muted: None,
+        position_ms: None,
+        changed_fields: None,
+        end_reason: None,
+        session_id: None,
+        source_app_id: None,
+        session_kind: None,
+        device_id: None,
+        device_direction: None,
+    }
+}
+
+fn position_event(event_type: EventType, media: &Media, position_ms: f64) -> EventData {
+    EventData {
+        media_info: Some(media.info.clone()),
+        position_ms: Some(position_ms),
+        ..event(event_type)
+    }
+}
+
+fn media_event(event_type: EventType, media: &Media, changed_fields: Option<Vec<String>>) -> EventData {
+    EventData {
+        media_info: Some(media.info.clone()),
+        changed_fields,
+        ..event(event_type)
+    }
+}
+
+fn track_end_event(media: &Media, reason: TrackEndReason, position_ms: Option<f64>) -> EventData {
+    EventData {
+        media_info: Some(media.info.clone()),
+        position_ms,
+        end_reason: Some(reason),
+        ..event(EventType::TrackEnded)
+    }
+}
+
+fn session_event(event_type: EventType, session: Option<&ListedSession>) -> EventData {
+    EventData {
+        session_id: session.map(|session| session.id.clone()),
+        source_app_id: session.map(|session| session.source_app_id.clone()),
+        session_kind: session.map(|session| session.kind),
+        ..event(event_type)
+    }
+}
+
+/// Volume and mute state of the default device of a direction
+#[derive(Clone)]
+struct Levels {
+    direction: DeviceDirection,
+    device_id: Option<String>,
+    volume: Option<f64>,
+    muted: Option<bool>,
+}
+
+impl Levels {
+    fn read(audio: &dyn AudioBackend, direction: DeviceDirection) -> Self {
+        let device = DeviceRef::Default(direction);
+        Levels {
+            direction,
+            device_id: audio.device_id(device).ok(),
+            volume: audio.volume(device).ok(),
+            muted: audio.muted(device).ok(),
+        }
+    }
+
+    /// Events for the levels as they are, or for how they changed since
+    /// `seen`
+    fn events(&self, seen: Option<&Levels>) -> Vec<EventData> {
+        let switched = seen.is_some_and(|seen| seen.device_id.is_some() && self.device_id != seen.device_id);
+        let changed_fields = |field: &str, changed: bool| {
+            let mut fields = vec![];
+            if changed {
+                fields.push(field.to_string());
+            }
+            if switched {
+                fields.push("deviceId".to_string());
+            }
+            Some(fields).filter(|fields| !fields.is_empty())
+        };
+        let level_event = |event_type| EventData {
+            device_id: self.device_id.clone(),
+            device_direction: Some(self.direction),
+            ..event(event_type)
+        };
+
+        let mut events = vec![];
+        if let Some(volume) = self.volume {
+            let changed = seen.is_some_and(|seen| seen.volume != Some(volume));
+            if seen.is_none() || changed || switched {
+                events.push(EventData {
+                    volume: Some(volume),
+                    changed_fields: changed_fields("volume", changed),
+                    ..level_event(EventType::VolumeChange)
+                });
+            }
+        }
+        if let Some(muted) = self.muted {
+            let changed = seen.is_some_and(|seen| seen.muted != Some(muted));
+            if seen.is_none() || changed || switched {
+                events.push(EventData {
+                    muted: Some(muted),
+                    changed_fields: changed_fields("muted", changed),
+                    ..level_event(EventType::MuteChange)
+                });
+            }
+        }
+        events
+    }
+
+    /// What was last seen sticks around while a value cannot be read
+    fn or(self, seen: Levels) -> Levels {
+        Levels {
+            direction: self.direction,
+            device_id: self.device_id.or(seen.device_id),
+            volume: self.volume.or(seen.volume),
+            muted: self.muted.or(seen.muted),
+        }
+    }
+}
+
+/// The state as of one read of the backends, which the hub works out the
+/// position from until the next read
+struct Read {
+    at: Instant,
+    media: Vec<(Scope, Option<Media>)>,
+    output: Levels,
+    input: Levels,
+    /// Only read while a subscriber asks for session events
+    sessions: Option<Sessions>,
+}
+
+impl Read {
+    fn take(scopes: Vec<Scope>, wants_sessions: bool) -> Self {
+        let media = scopes
+            .into_iter()
+            .map(|scope| {
+                let media = scope.read();
+                (scope, media)
+            })
+            .collect();
+        let audio = backend::audio();
+        let output = Levels::read(audio.as_ref(), DeviceDirection::Output);
+        let input = Levels::read(audio.as_ref(), DeviceDirection::Input);
+        let sessions = wants_sessions.then(Sessions::read);
+        // Counted from the end of the read, so a slow backend is not read
+        // back to back
+        Read { at: Instant::now(), media, output, input, sessions }
+    }
+
+    /// The media read for `scope`, if the scope was read at all
+    fn media(&self, scope: &Scope) -> Option<Option<&Media>> {
+        self.media.iter().find(|(read, _)| read == scope).map(|(_, media)| media.as_ref())
+    }
+
+    /// Whether the read covers everything the subscribers ask for
+    fn covers(&self, scopes: &[Scope], wants_sessions: bool) -> bool {
+        scopes.iter().all(|scope| self.media(scope).is_some()) && (self.sessions.is_some() || !wants_sessions)
+    }
+}
+
+/// What a subscriber was last told about
+struct Seen {
+    media: Option<Media>,
+    output: Levels,
+    input: Levels,
+    /// Only read while a subscriber asks for session events
+    sessions: Option<Sessions>,
+}
+
+struct Subscriber {
+    id: u64,
+    callback: EventCallback,
+    event_types: Option<Vec<EventType>>,
+    scope: Scope,
+    interval: Option<Duration>,
+    initial_snapshot: bool,
+    seen: Option<Seen>,
+    /// When the subscriber last compared a read with what it had seen
+    compared_at: Option<Instant>,
+    /// How often to report the position while playing, if at all
+    position_interval: Option<Duration>,
+    /// When the position was last reported, cleared while not playing
+    position_reported_at: Option<Instant>,
+    /// How long before the end of a track to report it is near
+    near_end_threshold_ms: f64,
+    /// The track last reported near its end, cleared when playback moves
+    /// back before the threshold
+    near_end_reported: Option<MediaInfo>,
+}
+
+impl Subscriber {
+    fn accepts(&self, event_type: EventType) -> bool {
+        self.event_types
+            .as_ref()
+            .is_none_or(|event_types| event_types.iter().any(|&accepted| accepted as i32 == event_type as i32))
+    }
+
+    fn wants_sessions(&self) -> bool {
+        [EventType::SessionAdded, EventType::SessionRemoved, EventType::CurrentSessionChanged]
+            .into_iter()
+            .any(|event_type| self.accepts(event_type))
+    }
+
+    fn emit(&self, event: EventData) {
+        if self.accepts(event.event_type) {
+            let _ = self.callback.call(event, ThreadsafeFunctionCallMode::NonBlocking);
+        }
+    }
+
+    /// When the subscriber may compare a read again, given its interval
+    fn due_at(&self) -> Option<Instant> {
+        Some(self.compared_at? + self.interval?)
+    }
+
+    /// Reports how `current` differs from what the subscriber has seen, or
+    /// all of it when this is its first read and it asked for a snapshot
+    fn compare(&mut self, current: Seen) {
+        let seen = match self.seen.take() {
+            Some(seen) => seen,
+            None => {
+                if self.initial_snapshot {
+                    if let Some(media) = &current.media {
+                        self.emit(media_event(EventType::MediaChange, media, None));
+                    }
+                    for event in current.output.events(None).into_iter().chain(current.input.events(None)) {
+                        self.emit(event);
+                    }
+                }
+                self.seen = Some(current);
+                return;
+            }
+        };
+
+        if let (Some(last), Some(sessions)) = (&seen.sessions, &current.sessions) {
+            for event in sessions.changes(last) {
+                self.emit(event);
+            }
+        }
+        // Another session becoming the one followed ends no track, its media
+        // just shows up as changed
+        let last_media = seen.media.as_ref().filter(|last| {
+            current.media.as_ref().is_some_and(|media| media.session_id == last.session_id)
+        });
+        if let (Some(last), Some(media)) = (last_media, &current.media) {
+            // A track starting over jumps back, which is no seek
+            if let Some((reason, position)) = track_end(&last.info, &media.info) {
+                self.emit(track_end_event(last, reason, position));
+            } else if seeked(&last.info, &media.info) {
+                if let Some(position) = position_at(&media.info, unix_now_ms()) {
+                    self.emit(position_event(EventType::Seeked, media, position));
+                }
+            }
+        }
+        if let Some(media) = &current.media {
+            let changes = match &seen.media {
+                Some(last) => media_changes(last, media),
+                // Media showing up is new as a whole
+                None => vec![(EventType::MediaChange, vec![])],
+            };
+            for (event_type, changed_fields) in changes {
+                let changed_fields = (!changed_fields.is_empty()).then_some(changed_fields);
+                self.emit(media_event(event_type, media, changed_fields));
+            }
+        }
+        let levels = current.output.events(Some(&seen.output));
+        for event in levels.into_iter().chain(current.input.events(Some(&seen.input))) {
+            self.emit(event);
+        }
+
+        // What was last seen sticks around while a value cannot be read
+        self.seen = Some(Seen {
+            media: current.media.or(seen.media),
+            output: current.output.or(seen.output),
+            input: current.input.or(seen.input),
+            sessions: current.sessions.or(seen.sessions),
+        });
+    }
+
+    /// Reports the position if the media plays and the subscriber's
+    /// position interval passed. Returns how long until the next report.
+    fn report_position(&mut self, media: Option<&Media>, now: Instant) -> Option<Duration> {
+        let interval = self.position_interval?;
+        let media = match media.filter(|media| media.info.playback_status == "playing") {
+            Some(media) => media,
+            None => {
+                self.position_reported_at = None;
+                return None;
+            }
+        };
+
+        if let Some(next) = self.position_reported_at.map(|reported_at| reported_at + interval) {
+            if next > now {
+                return Some(next - now);
+            }
+        }
+        let position = position_at(&media.info, unix_now_ms())?;
+        self.emit(position_event(EventType::PositionChange, media, position));
+        self.position_reported_at = Some(now);
+        Some(interval)
+    }
+
+    /// Reports once per track when the media plays into its last
+    /// `near_end_threshold_ms`. Returns how long until it will.
+    fn report_near_end(&mut self, media: Option<&Media>) -> Option<Duration> {
+        if !self.accepts(EventType::TrackNearEnd) {
+            return None;
+        }
+        let media = media?;
+        let info = &media.info;
+        let position = position_at(info, unix_now_ms())?;
+        let remaining = info.duration_ms? - position;
+
+        if remaining > self.near_end_threshold_ms {
+            self.near_end_reported = None;
+            if info.playback_status != "playing" {
+                return None;
+            }
+            let rate = info.playback_rate.filter(|&rate| rate > 0.0).unwrap_or(1.0);
+            return Some(Duration::from_secs_f64((remaining - self.near_end_threshold_ms) / rate / 1000.0));
+        }
+        let reported = self.near_end_reported.as_ref().is_some_and(|reported| same_track(reported, info));
+        if info.playback_status == "playing" && !reported {
+            self.emit(position_event(EventType::TrackNearEnd, media, position));
+            self.near_end_reported = Some(info.clone());
+        }
+        None
+    }
+}
+
+struct Hub {
+    subscribers: Vec<Subscriber>,
+    next_id: u64,
+    /// Bumped whenever a hub thread is started or retired, so a retired one
+    /// knows to exit
+    run: u64,
+    signal: Weak<ChangeSignal>,
+    thread: Option<HubThread>,
+}
+
+/// The running hub thread
+struct HubThread {
+    handle: JoinHandle<()>,
+    /// Disconnects once the thread exits
+    exited: Receiver<()>,
+}
+
+static HUB: Mutex<Hub> = Mutex::new(Hub {
+    subscribers: Vec::new(),
+    next_id: 0,
+    run: 0,
+    signal: Weak::new(),
+    thread: None,
+});
+
+fn hub() -> MutexGuard<'static, Hub> {
+    HUB.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Adds a subscriber, starting the hub thread when it is the first one.
+/// Returns the id to detach it with.
+pub fn attach(callback: EventCallback, options: SubscriptionOptions) -> u64 {
+    let mut hub = hub();
+    let id = hub.next_id;
+    hub.next_id += 1;
+    let wants_position = options.event_types.as_ref().is_some_and(|event_types| {
+        event_types.iter().any(|&event_type| event_type as i32 == EventType::PositionChange as i32)
+    });
+    let position_interval = match options.position_interval_ms {
+        Some(ms) => Some(Duration::from_millis(ms as u64).max(MIN_POSITION_INTERVAL)),
+        None => wants_position.then_some(POSITION_INTERVAL),
+    };
+    hub.subscribers.push(Subscriber {
+        id,
+        callback,
+        scope: Scope::of(&options),
+        event_types: options.event_types,
+        interval: options.interval_ms.map(|ms| Duration::from_millis(ms as u64).max(MIN_INTERVAL)),
+        initial_snapshot: options.initial_snapshot.unwrap_or(true),
+        seen: None,
+        compared_at: None,
+        position_interval,
+        position_reported_at: None,
+        near_end_threshold_ms: options.near_end_threshold_ms.map_or(NEAR_END_THRESHOLD_MS, f64::from),
+        near_end_reported: None,
+    });
+
+    match hub.signal.upgrade() {
+        // Wake the running thread so it sends the snapshot right away
+        Some(signal) => signal.raise(),
+        None => {
+            hub.run += 1;
+            let run = hub.run;
+            let signal = Arc::new(ChangeSignal::default());
+            hub.signal = Arc::downgrade(&signal);
+            let (exiting, exited) = mpsc::channel::<()>();
+            let handle = thread::spawn(move || {
+                let _exiting = exiting;
+                watch(run, signal);
+            });
+            hub.thread = Some(HubThread { handle, exited });
+        }
+    }
+    id
+}
+
+/// Removes a subscriber. When it was the last one, the hub thread exits and
+/// this joins it, waiting at most [`STOP_TIMEOUT`] for a read in progress.
+pub fn detach(id: u64) {
+    let mut hub = hub();
+    hub.subscribers.retain(|subscriber| subscriber.id != id);
+    if !hub.subscribers.is_empty() {
+        return;
+    }
+
+    hub.run += 1;
+    let thread = hub.thread.take();
+    let signal = std::mem::take(&mut hub.signal);
+    drop(hub);
+
+    if let Some(signal) = signal.upgrade() {
+        signal.raise();
+    }
+    if let Some(thread) = thread {
+        // The thread drops its sender as it exits
+        if let Err(RecvTimeoutError::Disconnected) = thread.exited.recv_timeout(STOP_TIMEOUT) {
+            let _ = thread.handle.join();
+        }
+    }
+}
+
+/// Body of the hub thread, which runs until `run` is retired.
+///
+/// The backends are only read when they report a change, when the poll
+/// interval passes, or when a subscriber needs something the last read
+/// lacks. Wake-ups in between, such as for position reports, work from the
+/// last read.
+fn watch(run: u64, signal: Arc<ChangeSignal>) {
+    let mut generation = None;
+    let mut pushed = false;
+    let mut last_read: Option<Read> = None;
+    let mut changed = true;
+
+    loop {
+        // What to read and how often depends on who is subscribed right now
+        let (scopes, polled, wants_sessions) = {
+            let hub = hub();
+            if hub.run != run {
+                return;
+            }
+            let mut scopes: Vec<Scope> = vec![];
+            for subscriber in &hub.subscribers {
+                if !scopes.contains(&subscriber.scope) {
+                    scopes.push(subscriber.scope.clone());
+                }
+            }
+            let polled = hub
+                .subscribers
+                .iter()
+                .map(|subscriber| subscriber.interval.unwrap_or(POLL_INTERVAL))
+                .min();
+            let wants_sessions = hub.subscribers.iter().any(Subscriber::wants_sessions);
+            (scopes, polled.unwrap_or(POLL_INTERVAL), wants_sessions)
+        };
+
+        // Watch the backends again whenever use_backend swapped them
+        if generation != Some(backend::generation()) {
+            generation = Some(backend::generation());
+            pushed = backend::watch(&signal);
+            changed = true;
+        }
+
+        let read_interval = if pushed { FALLBACK_POLL_INTERVAL } else { polled };
+        let read = match last_read.take() {
+            Some(read) if !changed && read.at.elapsed() < read_interval && read.covers(&scopes, wants_sessions) => read,
+            _ => Read::take(scopes, wants_sessions),
+        };
+
+        let mut wait = read_interval.saturating_sub(read.at.elapsed());
+        {
+            let mut hub = hub();
+            if hub.run != run {
+                return;
+            }
+
+            let now = Instant::now();
+            for subscriber in hub.subscribers.iter_mut() {
+                // Subscribers attached during the read wait for the next one
+                let media = match read.media(&subscriber.scope) {
+                    Some(media) => media.cloned(),
+                    None => continue,
+                };
+                if let Some(next) = subscriber.report_position(media.as_ref(), now) {
+                    wait = wait.min(next);
+                }
+                if let Some(next) = subscriber.report_near_end(media.as_ref()) {
+                    wait = wait.min(next);
+                }
+                // Changes within the interval of a subscriber are picked up
+                // together once it is due again
+                if let Some(due_at) = subscriber.due_at().filter(|&due_at| due_at > now) {
+                    wait = wait.min(due_at - now);
+                    continue;
+                }
+
+                let sessions = read.sessions.clone().filter(|_| subscriber.wants_sessions());
+                subscriber.compare(Seen {
+                    media,
+                    output: read.output.clone(),
+                    input: read.input.clone(),
+                    sessions,
+                });
+                subscriber.compared_at = Some(now);
+            }
+        }
+
+        last_read = Some(read);
+        changed = signal.wait(wait);
+    }
+}
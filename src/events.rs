@@ -1,11 +1,10 @@
 use napi::{
-    threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode},
-    JsFunction
+    bindgen_prelude::{Either, ObjectFinalize},
+    threadsafe_function::{ErrorStrategy, ThreadsafeFunction},
+    CleanupEnvHook, Env, JsFunction, Ref, Result
 };
 use napi_derive::napi;
-use tokio::runtime::Runtime;
-use std::{thread, time::Duration};
-use crate::MediaInfo;
+use crate::{event_hub, DeviceDirection, MediaInfo};
 
 /// The type of event that occurred
 #[napi]
@@ -14,6 +13,44 @@ pub enum EventType {
     PlaybackChange,
     VolumeChange,
     MuteChange,
+    ShuffleChange,
+    RepeatModeChange,
+    PlaybackRateChange,
+    CapabilitiesChange,
+    PositionChange,
+    Seeked,
+    TrackEnded,
+    TrackNearEnd,
+    SessionAdded,
+    SessionRemoved,
+    CurrentSessionChanged,
+}
+
+/// Which list a session of a session event belongs to
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum SessionKind {
+    /// A media session, as listed by `listMediaSessions`
+    #[napi(value = "media")]
+    Media,
+    /// An application with its own volume, as listed by `listAudioSessions`
+    #[napi(value = "audio")]
+    Audio,
+}
+
+/// How the track of a `TrackEnded` event came to an end
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrackEndReason {
+    /// The track played to its end
+    #[napi(value = "completed")]
+    Completed,
+    /// Another track was started before the end, such as through `next`
+    #[napi(value = "skipped")]
+    Skipped,
+    /// The player reports no position or duration to tell either way
+    #[napi(value = "unknown")]
+    Unknown,
 }
 
 /// The data for an event
@@ -23,119 +60,150 @@ pub struct EventData {
     pub media_info: Option<MediaInfo>,
     pub volume: Option<f64>,
     pub muted: Option<bool>,
+    /// The playback position in milliseconds, moved forward from the last
+    /// position the player reported, for `PositionChange`, `Seeked` and
+    /// `TrackNearEnd`. For `TrackEnded`, where the track stopped playing.
+    pub position_ms: Option<f64>,
+    /// Names of the fields that changed, such as `albumArtist`, or
+    /// `thumbnail` when the artwork did. Absent from the initial snapshot
+    /// and when media shows up.
+    pub changed_fields: Option<Vec<String>>,
+    /// Whether the track of a `TrackEnded` event, given in `mediaInfo`,
+    /// played to completion or was skipped
+    pub end_reason: Option<TrackEndReason>,
+    /// The session of a `SessionAdded`, `SessionRemoved` or
+    /// `CurrentSessionChanged` event, absent when no session is current
+    /// anymore
+    pub session_id: Option<String>,
+    /// Id of the application owning the session, which is the application
+    /// name for audio sessions
+    pub source_app_id: Option<String>,
+    /// Whether the session is a media session or an audio session
+    pub session_kind: Option<SessionKind>,
+    /// The default device whose level a `VolumeChange` or `MuteChange`
+    /// event reports. `changedFields` holds `deviceId` when another device
+    /// became the default.
+    pub device_id: Option<String>,
+    /// Whether the device of a `VolumeChange` or `MuteChange` event is the
+    /// default output or the default input
+    pub device_direction: Option<DeviceDirection>,
 }
 
-/// The data for a subscription
+/// Options narrowing what a subscription reports
 #[napi(object)]
+#[derive(Default)]
+pub struct SubscriptionOptions {
+    /// Event types to receive, all of them when not given
+    pub event_types: Option<Vec<EventType>>,
+    /// How often the state is read when the platform has to be polled, and
+    /// the least time between two reads when it pushes changes, so bursts
+    /// of changes come as one event. Defaults to 500 ms when polling and to
+    /// no debouncing otherwise. Intervals below 100 ms are raised to 100 ms.
+    pub interval_ms: Option<u32>,
+    /// Session whose media events are reported, instead of the current one.
+    /// Session events are reported for every session regardless.
+    pub session_id: Option<String>,
+    /// App whose media events are reported, instead of the current session.
+    /// Ignored when `sessionId` is given.
+    pub source_app_id: Option<String>,
+    /// Whether to report the current media, volume and mute state right
+    /// after subscribing. Defaults to true.
+    pub initial_snapshot: Option<bool>,
+    /// How often to report the position while media is playing. Position
+    /// events are only sent when this is given, or when `eventTypes` asks
+    /// for `PositionChange`, in which case it defaults to 1000 ms. Intervals
+    /// below 50 ms are raised to 50 ms.
+    pub position_interval_ms: Option<u32>,
+    /// How long before the end of a track `TrackNearEnd` is sent, once per
+    /// track while it plays. Defaults to 10000 ms.
+    pub near_end_threshold_ms: Option<u32>,
+}
+
+/// Handle to a running event subscription
+///
+/// Events keep coming until `stop` is called or the environment that
+/// created the subscription is torn down, such as when a worker thread
+/// exits or an Electron window reloads.
+#[napi(custom_finalize)]
 pub struct Subscription {
-    #[napi(ts_type = "() => void")]
-    pub stop: JsFunction,
+    /// Id in the event hub, taken once stopped
+    id: Option<u64>,
+    cleanup_hook: Option<CleanupEnvHook<u64>>,
+    stop_callback: Option<Ref<()>>,
 }
 
-/// Subscribes to events
 #[napi]
-pub fn subscribe_to_events(
-    callback: ThreadsafeFunction<EventData, ErrorStrategy::Fatal>,
-    #[napi(ts_arg_type = "() => void")] stop_callback: JsFunction,
-) -> Subscription {
-    thread::spawn(move || {
-        let mut last_media_info: Option<MediaInfo> = None;
-        let mut last_volume: Option<f64> = None;
-        let mut last_mute: Option<bool> = None;
-        
-        let rt = Runtime::new().expect("Failed to create Tokio runtime");
-
-        loop {
-            // Check for media changes
-            if let Some(current_info) = crate::get_media_info() {
-                match &last_media_info {
-                    Some(last_info) => {
-                        if last_info.title != current_info.title 
-                            || last_info.artist != current_info.artist 
-                            || last_info.album != current_info.album {
-                            let _ = callback.call(
-                                EventData {
-                                    event_type: EventType::MediaChange,
-                                    media_info: Some(current_info.clone()),
-                                    volume: None,
-                                    muted: None,
-                                },
-                                ThreadsafeFunctionCallMode::NonBlocking
-                            );
-                        }
-                        if last_info.playback_status != current_info.playback_status {
-                            let _ = callback.call(
-                                EventData {
-                                    event_type: EventType::PlaybackChange,
-                                    media_info: Some(current_info.clone()),
-                                    volume: None,
-                                    muted: None,
-                                },
-                                ThreadsafeFunctionCallMode::NonBlocking
-                            );
-                        }
-                    }
-                    None => {
-                        let _ = callback.call(
-                            EventData {
-                                event_type: EventType::MediaChange,
-                                media_info: Some(current_info.clone()),
-                                volume: None,
-                                muted: None,
-                            },
-                            ThreadsafeFunctionCallMode::NonBlocking
-                        );
-                    }
-                }
-                last_media_info = Some(current_info);
-            }
-
-            // Check for volume changes
-            if let Some(current_volume) = rt.block_on(crate::get_system_volume()) {
-                if last_volume != Some(current_volume) {
-                    let _ = callback.call(
-                        EventData {
-                            event_type: EventType::VolumeChange,
-                            media_info: None,
-                            volume: Some(current_volume),
-                            muted: None,
-                        },
-                        ThreadsafeFunctionCallMode::NonBlocking
-                    );
-                    last_volume = Some(current_volume);
-                }
-            }
+impl Subscription {
+    /// Stops the subscription, then calls the stop callback given to
+    /// `subscribeToEvents`. Stopping the last subscription also waits for
+    /// the native event thread to exit, for at most a second while it is
+    /// stuck on an unresponsive backend. Does nothing when already stopped.
+    #[napi]
+    pub fn stop(&mut self, mut env: Env) -> Result<()> {
+        if let Some(id) = self.id.take() {
+            event_hub::detach(id);
+        }
+        if let Some(hook) = self.cleanup_hook.take() {
+            env.remove_env_cleanup_hook(hook)?;
+        }
+        if let Some(mut stop_callback) = self.stop_callback.take() {
+            let function: JsFunction = env.get_reference_value(&stop_callback)?;
+            stop_callback.unref(env)?;
+            function.call_without_args(None)?;
+        }
+        Ok(())
+    }
 
-            if let Some(current_mute) = rt.block_on(crate::get_system_mute()) {
-                if last_mute != Some(current_mute) {
-                    let _ = callback.call(
-                        EventData {
-                            event_type: EventType::MuteChange,
-                            media_info: None,
-                            volume: None,
-                            muted: Some(current_mute),
-                        },
-                        ThreadsafeFunctionCallMode::NonBlocking
-                    );
-                    last_mute = Some(current_mute);
-                }
-            }
+    /// Whether the subscription still delivers events
+    #[napi(getter)]
+    pub fn is_active(&self) -> bool {
+        self.id.is_some()
+    }
+}
 
-            thread::sleep(Duration::from_millis(500));
+impl ObjectFinalize for Subscription {
+    /// Dropping the handle does not stop the subscription, the env cleanup
+    /// hook still does once the environment goes away
+    fn finalize(mut self, env: Env) -> Result<()> {
+        if let Some(mut stop_callback) = self.stop_callback.take() {
+            stop_callback.unref(env)?;
         }
-    });
-
-    Subscription {
-        stop: stop_callback
+        Ok(())
     }
 }
-  
 
-/// Custom error codes that can be thrown
-#[napi(string_enum)]
-pub enum ErrorStatus {
-    #[napi(value = "ERR_STATUS_CANTDSMTGH")]
-    CantDoSomething,
-    #[napi(value = "ERR_STATUS_INVALID")]
-    InvalidCondition,
-}
\ No newline at end of file
+/// Subscribes to events
+///
+/// Changes are picked up from platform notifications where the backend
+/// supports them, and by polling otherwise. Every subscription shares the
+/// same native event thread, so adding subscribers costs no extra system
+/// queries.
+/// @param callback - Called with every event
+/// @param stopCallbackOrOptions - Called once the subscription is stopped, or the options when no stop callback is needed
+/// @param options - Options of the subscription, when a stop callback is given
+#[napi]
+pub fn subscribe_to_events(
+    mut env: Env,
+    callback: ThreadsafeFunction<EventData, ErrorStrategy::Fatal>,
+    #[napi(ts_arg_type = "(() => void) | SubscriptionOptions")] stop_callback_or_options: Option<Either<JsFunction, SubscriptionOptions>>,
+    options: Option<SubscriptionOptions>,
+) -> Result<Subscription> {
+    let (stop_callback, options) = match stop_callback_or_options {
+        Some(Either::A(stop_callback)) => (Some(stop_callback), options.unwrap_or_default()),
+        Some(Either::B(options)) => (None, options),
+        None => (None, options.unwrap_or_default()),
+    };
+    let id = event_hub::attach(callback, options);
+
+    let cleanup_hook = env.add_env_cleanup_hook(id, event_hub::detach)?;
+    let stop_callback = match stop_callback {
+        Some(stop_callback) => Some(env.create_reference(stop_callback)?),
+        None => None,
+    };
+
+    Ok(Subscription {
+        id: Some(id),
+        cleanup_hook: Some(cleanup_hook),
+        stop_callback,
+    })
+}
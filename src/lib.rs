@@ -1,36 +1,33 @@
 #![deny(clippy::all)]
 
+#[cfg(windows)]
 mod audio_controller;
 mod session;
+mod backend;
+mod channels;
+mod curve;
+mod error;
+mod event_hub;
 mod events;
 
+#[cfg(windows)]
 pub use audio_controller::{AudioController, CoinitMode};
-pub use session::{Session, ApplicationSession, EndPointSession};
+pub use session::{Session, AudioSessionKind, AudioSessionState};
+#[cfg(windows)]
+pub use session::{ApplicationSession, EndPointSession};
+pub use backend::{AudioBackend, MediaBackend, FakeAudioDevice, FakeAudioSession, FakeFailure, FakeScenario, FakeSession, FakeStep};
+pub use curve::{get_volume_curve, set_volume_curve, VolumeCurve};
+pub use error::{set_error_mode, ErrorMode, ErrorStatus};
 pub use events::{
     subscribe_to_events, 
     EventType, 
-    EventData,
-    ErrorStatus
+    EventData
 };
 
 use napi_derive::napi;
-use windows::{
-    Media::Control::{
-        GlobalSystemMediaTransportControlsSession, GlobalSystemMediaTransportControlsSessionManager,
-        GlobalSystemMediaTransportControlsSessionPlaybackStatus,
-    },
-    Storage::Streams::{Buffer, IRandomAccessStreamReference, InputStreamOptions, IBuffer},
-    core::{Interface, GUID},
-    Win32::{
-        Media::Audio::{
-            eRender, eConsole, IMMDeviceEnumerator, MMDeviceEnumerator,
-            Endpoints::IAudioEndpointVolume,
-        },
-        System::Com::{
-            CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED,
-        }
-    },
-};
+use backend::{DeviceRef, TransportCommand};
+use curve::Target;
+use error::{Error, ErrorStatus as Status, Outcome, Result};
 
 /// Information about the currently playing media
 #[napi(object)]
@@ -48,270 +45,840 @@ pub struct MediaInfo {
     pub playback_status: String,
     /// Whether the media has an associated thumbnail image
     pub has_thumbnail: bool,
+    /// The subtitle of the media
+    pub subtitle: Option<String>,
+    /// The position of the track on its album
+    pub track_number: Option<u32>,
+    /// The number of tracks on the album
+    pub track_count: Option<u32>,
+    /// The genres of the media
+    pub genres: Option<Vec<String>>,
+    /// The kind of media being played (music, video, image or unknown)
+    pub playback_type: Option<String>,
+    /// The length of the media in milliseconds
+    pub duration_ms: Option<f64>,
+    /// The playback position in milliseconds, as of `lastUpdated`
+    pub position_ms: Option<f64>,
+    /// When the position was last updated, in milliseconds since the Unix epoch
+    pub last_updated: Option<f64>,
+    /// Id of the application playing the media
+    pub source_app_id: Option<String>,
+    /// Whether tracks are played in random order
+    pub shuffle: Option<bool>,
+    /// Whether the track or the whole list is repeated
+    pub repeat_mode: Option<RepeatMode>,
+    /// The playback speed, 1.0 being normal speed
+    pub playback_rate: Option<f64>,
+    /// The controls the player currently accepts
+    pub capabilities: Option<MediaCapabilities>,
+    /// Id the player gives the track, which tells apart tracks with the
+    /// same metadata. Only MPRIS players publish one.
+    pub track_id: Option<String>,
+}
+
+/// The controls a media session currently accepts
+#[napi(object)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaCapabilities {
+    /// Whether `play` is accepted
+    pub play: bool,
+    /// Whether `pause` is accepted
+    pub pause: bool,
+    /// Whether `stop` is accepted
+    pub stop: bool,
+    /// Whether `next` is accepted
+    pub next: bool,
+    /// Whether `previous` is accepted
+    pub previous: bool,
+    /// Whether `seek` and `setPosition` are accepted
+    pub seek: bool,
+    /// Whether `setShuffle` is accepted
+    pub shuffle: bool,
+    /// Whether `setRepeatMode` is accepted
+    pub repeat: bool,
+    /// Whether `setPlaybackRate` is accepted
+    pub rate: bool,
+    /// Whether the player exposes its own volume
+    pub volume: bool,
 }
 
-fn request_session_manager() -> Option<GlobalSystemMediaTransportControlsSessionManager> {
-    let async_op = GlobalSystemMediaTransportControlsSessionManager::RequestAsync().ok()?;
-    async_op.get().ok()
+/// What a player repeats once the current track ends
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum RepeatMode {
+    /// Playback stops at the end of the list
+    #[napi(value = "none")]
+    None,
+    /// The current track is repeated
+    #[napi(value = "track")]
+    Track,
+    /// The whole list is repeated
+    #[napi(value = "list")]
+    List,
 }
 
-/// Gets information about the currently playing media
+/// Outcome of a seek or set-position request
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum SeekResult {
+    /// The player moved to the new position
+    #[napi(value = "ok")]
+    Ok,
+    /// The player does not support seeking in the current media
+    #[napi(value = "unsupported")]
+    Unsupported,
+    /// There is no such session, or no session at all
+    #[napi(value = "no-session")]
+    NoSession,
+    /// The player supports seeking but rejected the request
+    #[napi(value = "failed")]
+    Failed,
+}
+
+/// A media session, such as a player or a browser tab playing media
+#[napi(object)]
+#[derive(Clone)]
+pub struct MediaSession {
+    /// Id the session is addressed by, stable for as long as the session exists
+    pub id: String,
+    /// Id of the application owning the session
+    pub source_app_id: String,
+    /// Whether this is the session the system considers current
+    pub is_current: bool,
+}
+
+/// An application playing audio, with its own volume and mute state
+#[napi(object)]
+#[derive(Clone)]
+pub struct AudioSession {
+    /// Id the session is addressed by
+    pub id: String,
+    /// The name of the application
+    pub name: String,
+    /// The process id of the application, if known
+    pub pid: Option<u32>,
+}
+
+/// Whether a device plays or records audio
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeviceDirection {
+    /// Speakers, headphones and other outputs
+    #[napi(value = "output")]
+    Output,
+    /// Microphones and other inputs
+    #[napi(value = "input")]
+    Input,
+}
+
+/// Whether a device can be used
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeviceState {
+    /// The device is there and enabled
+    #[napi(value = "active")]
+    Active,
+    /// The device was turned off in the sound settings
+    #[napi(value = "disabled")]
+    Disabled,
+    /// Nothing is plugged into the jack of the device
+    #[napi(value = "unplugged")]
+    Unplugged,
+}
+
+/// An output or input audio device
+#[napi(object)]
+#[derive(Clone)]
+pub struct AudioDevice {
+    /// Id the device is addressed by, which stays the same across restarts
+    pub id: String,
+    /// The name to show for the device
+    pub name: String,
+    /// What kind of device it is, such as speakers or headphones
+    pub description: Option<String>,
+    /// Whether the device plays or records audio
+    pub direction: DeviceDirection,
+    /// Whether the device can be used
+    pub state: DeviceState,
+    /// Whether the system plays or records through the device by default
+    pub is_default: bool,
+    /// Whether the system uses the device for calls by default
+    pub is_default_communication: bool,
+    /// The number of channels of the device, if known
+    pub channels: Option<u32>,
+    /// The sample rate of the device in Hz, if known
+    pub sample_rate: Option<u32>,
+}
+
+/// Where the speaker a channel plays through stands
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChannelPosition {
+    /// The only channel of a mono stream
+    #[napi(value = "mono")]
+    Mono,
+    #[napi(value = "front-left")]
+    FrontLeft,
+    #[napi(value = "front-right")]
+    FrontRight,
+    #[napi(value = "front-center")]
+    FrontCenter,
+    /// The subwoofer
+    #[napi(value = "lfe")]
+    Lfe,
+    #[napi(value = "rear-left")]
+    RearLeft,
+    #[napi(value = "rear-right")]
+    RearRight,
+    #[napi(value = "rear-center")]
+    RearCenter,
+    #[napi(value = "front-left-of-center")]
+    FrontLeftOfCenter,
+    #[napi(value = "front-right-of-center")]
+    FrontRightOfCenter,
+    #[napi(value = "side-left")]
+    SideLeft,
+    #[napi(value = "side-right")]
+    SideRight,
+    /// Height and auxiliary channels
+    #[napi(value = "other")]
+    Other,
+}
+
+/// The volume of one channel of a device or an application
+#[napi(object)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChannelVolume {
+    /// Where the channel plays
+    pub position: ChannelPosition,
+    /// Volume level of the channel between 0.0 and 1.0
+    pub volume: f64,
+}
+
+/// The volumes in dB a device or an application can be set to
+#[napi(object)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VolumeRange {
+    /// The quietest volume in dB. Volumes applied in software go below it
+    /// down to silence.
+    pub min_db: f64,
+    /// The loudest volume in dB
+    pub max_db: f64,
+    /// The smallest change in dB the device makes, if it tells
+    pub increment_db: Option<f64>,
+    /// The number of volume steps of the device, for devices that cannot be
+    /// set to any volume
+    pub steps: Option<u32>,
+}
+
+/// Which way `volumeStep` moves the volume
+#[napi(string_enum)]
+#[derive(Debug, PartialEq, Eq)]
+pub enum StepDirection {
+    #[napi(value = "up")]
+    Up,
+    #[napi(value = "down")]
+    Down,
+}
+
+/// Which devices `listAudioDevices` returns
+#[napi(object)]
+#[derive(Default)]
+pub struct AudioDeviceFilter {
+    /// Only list devices of this direction, both when not given
+    pub direction: Option<DeviceDirection>,
+}
+
+/// Switches the backend every function talks to
+/// @param name - `system` for the platform backend, `fake` for an in-memory backend
+/// @param scenario - Initial state and timeline of the fake backend
 #[napi]
-pub fn get_media_info() -> Option<MediaInfo> {
-    let manager = request_session_manager()?;
-    let session = manager.GetCurrentSession().ok()?;
-    
-    let async_props = session.TryGetMediaPropertiesAsync().ok()?;
-    let media_props = async_props.get().ok()?;
-
-    let playback_info = session.GetPlaybackInfo().ok()?;
-    let status = playback_info.PlaybackStatus().ok()?;
-    let status_str = match status {
-        GlobalSystemMediaTransportControlsSessionPlaybackStatus::Closed => "closed",
-        GlobalSystemMediaTransportControlsSessionPlaybackStatus::Opened => "opened",
-        GlobalSystemMediaTransportControlsSessionPlaybackStatus::Changing => "changing",
-        GlobalSystemMediaTransportControlsSessionPlaybackStatus::Stopped => "stopped",
-        GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing => "playing",
-        GlobalSystemMediaTransportControlsSessionPlaybackStatus::Paused => "paused",
-        _ => "unknown",
-    };
-
-    let thumbnail: Option<IRandomAccessStreamReference> = media_props.Thumbnail().ok();
-
-    Some(MediaInfo {
-        title: media_props.Title().ok().map(|s| s.to_string()),
-        artist: media_props.Artist().ok().map(|s| s.to_string()),
-        album: media_props.AlbumTitle().ok().map(|s| s.to_string()),
-        album_artist: media_props.AlbumArtist().ok().map(|s| s.to_string()),
-        playback_status: status_str.to_string(),
-        has_thumbnail: thumbnail.is_some(),
-    })
+pub fn use_backend(
+    #[napi(ts_arg_type = "'system' | 'fake'")] name: String,
+    scenario: Option<FakeScenario>,
+) -> napi::Result<()> {
+    match name.as_str() {
+        "system" => backend::use_backend(None),
+        "fake" => backend::use_backend(Some(scenario.unwrap_or_default())),
+        _ => {
+            return Err(napi::Error::new(
+                napi::Status::InvalidArg,
+                format!("Unknown backend: {name}"),
+            ))
+        }
+    }
+    Ok(())
 }
 
-/// Gets the thumbnail image for the currently playing media as a byte array
+/// Moves the clock of the fake backend forward, applying any scenario steps that become due
+/// @param ms - Milliseconds to advance by
 #[napi]
-pub fn get_thumbnail() -> Option<Vec<u8>> {
-    let manager = request_session_manager()?;
-    let session = manager.GetCurrentSession().ok()?;
-    
-    let async_props = session.TryGetMediaPropertiesAsync().ok()?;
-    let media_props = async_props.get().ok()?;
-
-    let thumbnail = media_props.Thumbnail().ok()?;
-    let async_stream = thumbnail.OpenReadAsync().ok()?;
-    let stream = async_stream.get().ok()?;
-    
-    let size = stream.Size().ok()? as u32;
-    let buffer = Buffer::Create(size).ok()?;
-    
-    let input_stream = stream.GetInputStreamAt(0).ok()?;
-    let async_read = input_stream.ReadAsync(
-        &buffer,
-        size,
-        InputStreamOptions::default(),
-    ).ok()?;
-    
-    let _bytes_read = async_read.get().ok()?;
-    
-    // Get bytes from buffer
-    let ibuffer: IBuffer = buffer.cast().ok()?;
-    let len = ibuffer.Length().ok()? as usize;
-    let mut vec = vec![0u8; len];
-    
-    // Create a new DataReader to read from the buffer
-    let reader = windows::Storage::Streams::DataReader::FromBuffer(&ibuffer).ok()?;
-    reader.ReadBytes(&mut vec).ok()?;
-    
-    Some(vec)
+pub fn advance_fake_clock(ms: f64) -> napi::Result<()> {
+    match backend::fake() {
+        Some(fake) => fake
+            .advance(ms)
+            .map_err(|err| napi::Error::new(napi::Status::InvalidArg, err.to_string())),
+        None => Err(napi::Error::new(
+            napi::Status::GenericFailure,
+            "The fake backend is not in use".to_string(),
+        )),
+    }
+}
+
+/// Lists the media sessions of the backend in use
+pub(crate) fn media_sessions() -> Result<Vec<MediaSession>> {
+    let media = backend::media();
+    let current = media.current_session()?;
+
+    Ok(media
+        .list_sessions()?
+        .into_iter()
+        .map(|id| MediaSession {
+            source_app_id: media.source_app_id(&id).unwrap_or_else(|| id.clone()),
+            is_current: current.as_ref() == Some(&id),
+            id,
+        })
+        .collect())
+}
+
+/// Lists every media session, not only the current one
+/// @returns The media sessions, whose ids the other media functions take
+#[napi(ts_return_type = "Array<MediaSession>")]
+pub fn list_media_sessions() -> Outcome<Vec<MediaSession>> {
+    media_sessions().into()
+}
+
+/// The requested session, or the current one if there is any
+fn requested_session(media: &dyn MediaBackend, session_id: Option<String>) -> Result<Option<String>> {
+    match session_id {
+        Some(session_id) => Ok(Some(session_id)),
+        None => media.current_session(),
+    }
+}
+
+/// Picks the requested session, falling back to the current one
+fn resolve_session(media: &dyn MediaBackend, session_id: Option<String>) -> Result<String> {
+    requested_session(media, session_id)?
+        .ok_or_else(|| Error::new(Status::NoSession, "There is no current media session"))
+}
+
+/// Reads the requested session, or the current one if there is any
+fn media_info(session_id: Option<String>) -> Result<Option<MediaInfo>> {
+    let media = backend::media();
+    match requested_session(media.as_ref(), session_id)? {
+        Some(session_id) => media.media_info(&session_id).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Gets information about the currently playing media
+/// @param sessionId - Session to read instead of the current one
+/// @returns The media information, or null if there is no current session
+#[napi(ts_return_type = "MediaInfo | null")]
+pub fn get_media_info(session_id: Option<String>) -> Outcome<Option<MediaInfo>> {
+    media_info(session_id).into()
+}
+
+/// Gets the thumbnail image for the currently playing media as a byte array
+/// @param sessionId - Session to read instead of the current one
+/// @returns The image, or null if there is no current session or no artwork
+#[napi(ts_return_type = "Array<number> | null")]
+pub fn get_thumbnail(session_id: Option<String>) -> Outcome<Option<Vec<u8>>> {
+    let media = backend::media();
+    requested_session(media.as_ref(), session_id)
+        .and_then(|session_id| match session_id {
+            Some(session_id) => media.thumbnail(&session_id),
+            None => Ok(None),
+        })
+        .into()
+}
+
+/// Sends a transport command to the given session, or to the current one
+fn send_command(command: TransportCommand, session_id: Option<String>) -> Outcome<bool> {
+    let media = backend::media();
+    resolve_session(media.as_ref(), session_id)
+        .and_then(|session_id| media.send_command(&session_id, command))
+        .map(|()| true)
+        .into()
 }
 
 /// Attempts to play the current media
+/// @param sessionId - Session to control instead of the current one
 /// @returns Whether the play command was successful
-#[napi]
-pub async fn play() -> bool {
-    if let Some(session) = get_current_session() {
-        if let Ok(async_op) = session.TryPlayAsync() {
-            async_op.get().is_ok()
-        } else {
-            false
-        }
-    } else {
-        false
-    }
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn play(session_id: Option<String>) -> Outcome<bool> {
+    send_command(TransportCommand::Play, session_id)
 }
 
 /// Attempts to pause the current media
+/// @param sessionId - Session to control instead of the current one
 /// @returns Whether the pause command was successful
-#[napi]
-pub async fn pause() -> bool {
-    if let Some(session) = get_current_session() {
-        if let Ok(async_op) = session.TryPauseAsync() {
-            async_op.get().is_ok()
-        } else {
-            false
-        }
-    } else {
-        false
-    }
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn pause(session_id: Option<String>) -> Outcome<bool> {
+    send_command(TransportCommand::Pause, session_id)
 }
 
 /// Attempts to skip to the next track
+/// @param sessionId - Session to control instead of the current one
 /// @returns Whether the next command was successful
-#[napi]
-pub async fn next() -> bool {
-    if let Some(session) = get_current_session() {
-        if let Ok(async_op) = session.TrySkipNextAsync() {
-            async_op.get().is_ok()
-        } else {
-            false
-        }
-    } else {
-        false
-    }
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn next(session_id: Option<String>) -> Outcome<bool> {
+    send_command(TransportCommand::Next, session_id)
 }
 
 /// Attempts to go back to the previous track
+/// @param sessionId - Session to control instead of the current one
 /// @returns Whether the previous command was successful
-#[napi]
-pub async fn previous() -> bool {
-    if let Some(session) = get_current_session() {
-        if let Ok(async_op) = session.TrySkipPreviousAsync() {
-            async_op.get().is_ok()
-        } else {
-            false
-        }
-    } else {
-        false
-    }
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn previous(session_id: Option<String>) -> Outcome<bool> {
+    send_command(TransportCommand::Previous, session_id)
 }
 
 /// Attempts to stop playback of the current media
+/// @param sessionId - Session to control instead of the current one
 /// @returns Whether the stop command was successful
-#[napi]
-pub async fn stop() -> bool {
-    if let Some(session) = get_current_session() {
-        if let Ok(async_op) = session.TryStopAsync() {
-            async_op.get().is_ok()
-        } else {
-            false
-        }
-    } else {
-        false
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn stop(session_id: Option<String>) -> Outcome<bool> {
+    send_command(TransportCommand::Stop, session_id)
+}
+
+/// Checks that a time in milliseconds is a finite number
+fn finite_ms(ms: f64, what: &str) -> Result<f64> {
+    match ms.is_finite() {
+        true => Ok(ms),
+        false => Err(Error::invalid_argument(format!("{what} must be a finite number of milliseconds, got {ms}"))),
     }
 }
 
-fn get_current_session() -> Option<GlobalSystemMediaTransportControlsSession> {
-    let manager = request_session_manager()?;
-    manager.GetCurrentSession().ok()
+/// Moves the playback position of the current media forwards or backwards
+/// @param offsetMs - Milliseconds to move by, negative to go back
+/// @param sessionId - Session to control instead of the current one
+/// @returns Whether the seek was applied, or why it was not
+#[napi(ts_return_type = "Promise<SeekResult>")]
+pub async fn seek(offset_ms: f64, session_id: Option<String>) -> Outcome<SeekResult> {
+    let media = backend::media();
+    finite_ms(offset_ms, "Seek offset")
+        .and_then(|offset_ms| Ok((offset_ms, resolve_session(media.as_ref(), session_id)?)))
+        .and_then(|(offset_ms, session_id)| media.seek(&session_id, offset_ms))
+        .map(|()| SeekResult::Ok)
+        .into()
 }
 
-/// Sets the system volume level
-/// @param level - Volume level between 0.0 and 1.0
-/// @returns Whether setting the volume was successful
-#[napi]
-pub async fn set_system_volume(level: f64) -> bool {
-    // Validate input range
-    if level < 0.0 || level > 1.0 {
-        return false;
+/// Moves the playback position of the current media to an absolute position
+/// @param positionMs - Position from the start of the media in milliseconds
+/// @param sessionId - Session to control instead of the current one
+/// @returns Whether the position was changed, or why it was not
+#[napi(ts_return_type = "Promise<SeekResult>")]
+pub async fn set_position(position_ms: f64, session_id: Option<String>) -> Outcome<SeekResult> {
+    let media = backend::media();
+    finite_ms(position_ms, "Position")
+        .and_then(|position_ms| Ok((position_ms, resolve_session(media.as_ref(), session_id)?)))
+        .and_then(|(position_ms, session_id)| media.set_position(&session_id, position_ms.max(0.0)))
+        .map(|()| SeekResult::Ok)
+        .into()
+}
+
+/// Gets the controls the current media session accepts
+/// @param sessionId - Session to read instead of the current one
+/// @returns The enabled controls, or null if there is no current session
+#[napi(ts_return_type = "Promise<MediaCapabilities | null>")]
+pub async fn get_capabilities(session_id: Option<String>) -> Outcome<Option<MediaCapabilities>> {
+    media_info(session_id).map(|info| info.and_then(|info| info.capabilities)).into()
+}
+
+/// Gets whether the current media is shuffled
+/// @param sessionId - Session to read instead of the current one
+/// @returns Whether shuffle is on, or null if the player does not report it
+#[napi(ts_return_type = "Promise<boolean | null>")]
+pub async fn get_shuffle(session_id: Option<String>) -> Outcome<Option<bool>> {
+    media_info(session_id).map(|info| info.and_then(|info| info.shuffle)).into()
+}
+
+/// Turns shuffle on or off
+/// @param shuffle - Whether tracks should play in random order
+/// @param sessionId - Session to control instead of the current one
+/// @returns Whether the player applied the change
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_shuffle(shuffle: bool, session_id: Option<String>) -> Outcome<bool> {
+    let media = backend::media();
+    resolve_session(media.as_ref(), session_id)
+        .and_then(|session_id| media.set_shuffle(&session_id, shuffle))
+        .map(|()| true)
+        .into()
+}
+
+/// Gets what the player repeats once the current track ends
+/// @param sessionId - Session to read instead of the current one
+/// @returns The repeat mode, or null if the player does not report it
+#[napi(ts_return_type = "Promise<RepeatMode | null>")]
+pub async fn get_repeat_mode(session_id: Option<String>) -> Outcome<Option<RepeatMode>> {
+    media_info(session_id).map(|info| info.and_then(|info| info.repeat_mode)).into()
+}
+
+/// Sets what the player repeats once the current track ends
+/// @param mode - `none`, `track` or `list`
+/// @param sessionId - Session to control instead of the current one
+/// @returns Whether the player applied the change
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_repeat_mode(mode: RepeatMode, session_id: Option<String>) -> Outcome<bool> {
+    let media = backend::media();
+    resolve_session(media.as_ref(), session_id)
+        .and_then(|session_id| media.set_repeat_mode(&session_id, mode))
+        .map(|()| true)
+        .into()
+}
+
+/// Gets the playback speed of the current media
+/// @param sessionId - Session to read instead of the current one
+/// @returns The playback rate, 1.0 being normal speed, or null if the player does not report it
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn get_playback_rate(session_id: Option<String>) -> Outcome<Option<f64>> {
+    media_info(session_id).map(|info| info.and_then(|info| info.playback_rate)).into()
+}
+
+/// Sets the playback speed of the current media
+/// @param rate - Playback rate, 1.0 being normal speed
+/// @param sessionId - Session to control instead of the current one
+/// @returns Whether the player applied the change
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_playback_rate(rate: f64, session_id: Option<String>) -> Outcome<bool> {
+    if !rate.is_finite() || rate <= 0.0 {
+        return Outcome(Err(Error::invalid_argument(format!("Playback rate must be above 0, got {rate}"))));
     }
 
-    unsafe {
-        // Skip CoInitializeEx if already initialized
-        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
-
-        let device_enumerator: IMMDeviceEnumerator = match CoCreateInstance(
-            &MMDeviceEnumerator,
-            None,
-            CLSCTX_ALL
-        ) {
-            Ok(enumerator) => enumerator,
-            Err(_) => return false,
-        };
-
-        let device = match device_enumerator.GetDefaultAudioEndpoint(eRender, eConsole) {
-            Ok(device) => device,
-            Err(_) => return false,
-        };
-
-        let volume: IAudioEndpointVolume = match device.Activate(CLSCTX_ALL, None) {
-            Ok(volume) => volume,
-            Err(_) => return false,
-        };
-
-        volume.SetMasterVolumeLevelScalar(level as f32, &GUID::zeroed()).is_ok()
+    let media = backend::media();
+    resolve_session(media.as_ref(), session_id)
+        .and_then(|session_id| media.set_playback_rate(&session_id, rate))
+        .map(|()| true)
+        .into()
+}
+
+/// Checks that a volume level lies between 0.0 and 1.0
+fn volume_level(level: f64) -> Result<f64> {
+    match (0.0..=1.0).contains(&level) {
+        true => Ok(level),
+        false => Err(Error::invalid_argument(format!("Volume level must be between 0.0 and 1.0, got {level}"))),
     }
 }
 
+/// Checks that every level of a channel lies between 0.0 and 1.0
+fn volume_levels(levels: Vec<f64>) -> Result<Vec<f64>> {
+    levels.into_iter().map(volume_level).collect()
+}
+
+/// The device with the given id, or the default output device
+fn output(device_id: &Option<String>) -> DeviceRef<'_> {
+    DeviceRef::new(device_id.as_deref(), DeviceDirection::Output)
+}
+
+/// The device with the given id, or the default input device
+fn input(device_id: &Option<String>) -> DeviceRef<'_> {
+    DeviceRef::new(device_id.as_deref(), DeviceDirection::Input)
+}
+
+/// Sets the system volume level
+/// @param level - Volume level between 0.0 and 1.0
+/// @param deviceId - Device to change instead of the default output device
+/// @returns Whether setting the volume was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_system_volume(level: f64, device_id: Option<String>) -> Outcome<bool> {
+    volume_level(level)
+        .and_then(|level| Target::Device(output(&device_id)).set_level(backend::audio().as_ref(), level))
+        .map(|()| true)
+        .into()
+}
+
 /// Gets the current system volume level
-/// @returns Volume level between 0.0 and 1.0, or null if unable to get volume
-#[napi]
-pub async fn get_system_volume() -> Option<f64> {
-    unsafe {
-        // Skip CoInitializeEx if already initialized
-        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
-
-        let device_enumerator: IMMDeviceEnumerator = CoCreateInstance(
-            &MMDeviceEnumerator,
-            None,
-            CLSCTX_ALL
-        ).ok()?;
-
-        let device = device_enumerator.GetDefaultAudioEndpoint(eRender, eConsole).ok()?;
-        let volume: IAudioEndpointVolume = device.Activate(CLSCTX_ALL, None).ok()?;
-        
-        volume.GetMasterVolumeLevelScalar().ok().map(|v| v as f64)
-    }
+/// @param deviceId - Device to read instead of the default output device
+/// @returns Volume level between 0.0 and 1.0, or null in the compatibility mode if unable to get volume
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn get_system_volume(device_id: Option<String>) -> Outcome<Option<f64>> {
+    Target::Device(output(&device_id)).level(backend::audio().as_ref()).map(Some).into()
 }
 
 /// Sets the system mute state
 /// @param mute - Whether to mute (true) or unmute (false) the system audio
+/// @param deviceId - Device to change instead of the default output device
 /// @returns Whether setting the mute state was successful
-#[napi]
-pub async fn set_system_mute(mute: bool) -> bool {
-    unsafe {
-        // Skip CoInitializeEx if already initialized
-        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
-
-        let device_enumerator: IMMDeviceEnumerator = match CoCreateInstance(
-            &MMDeviceEnumerator,
-            None,
-            CLSCTX_ALL
-        ) {
-            Ok(enumerator) => enumerator,
-            Err(_) => return false,
-        };
-
-        let device = match device_enumerator.GetDefaultAudioEndpoint(eRender, eConsole) {
-            Ok(device) => device,
-            Err(_) => return false,
-        };
-
-        let volume: IAudioEndpointVolume = match device.Activate(CLSCTX_ALL, None) {
-            Ok(volume) => volume,
-            Err(_) => return false,
-        };
-
-        volume.SetMute(mute, &GUID::zeroed()).is_ok()
-    }
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_system_mute(mute: bool, device_id: Option<String>) -> Outcome<bool> {
+    backend::audio().set_muted(output(&device_id), mute).map(|()| true).into()
 }
 
 /// Gets the current system mute state
-/// @returns Whether the system is muted (true) or not (false), or null if unable to get state
-#[napi]
-pub async fn get_system_mute() -> Option<bool> {
-    unsafe {
-        // Skip CoInitializeEx if already initialized
-        let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
-
-        let device_enumerator: IMMDeviceEnumerator = CoCreateInstance(
-            &MMDeviceEnumerator,
-            None,
-            CLSCTX_ALL
-        ).ok()?;
-
-        let device = device_enumerator.GetDefaultAudioEndpoint(eRender, eConsole).ok()?;
-        let volume: IAudioEndpointVolume = device.Activate(CLSCTX_ALL, None).ok()?;
-        
-        volume.GetMute().ok().map(|m| m.as_bool())
-    }
-}
\ No newline at end of file
+/// @param deviceId - Device to read instead of the default output device
+/// @returns Whether the system is muted (true) or not (false), or null in the compatibility mode if unable to get state
+#[napi(ts_return_type = "Promise<boolean | null>")]
+pub async fn get_system_mute(device_id: Option<String>) -> Outcome<Option<bool>> {
+    backend::audio().muted(output(&device_id)).map(Some).into()
+}
+
+/// Sets the volume level of the microphone
+/// @param level - Volume level between 0.0 and 1.0
+/// @param deviceId - Device to change instead of the default input device
+/// @returns Whether setting the volume was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_input_volume(level: f64, device_id: Option<String>) -> Outcome<bool> {
+    volume_level(level)
+        .and_then(|level| Target::Device(input(&device_id)).set_level(backend::audio().as_ref(), level))
+        .map(|()| true)
+        .into()
+}
+
+/// Gets the volume level of the microphone
+/// @param deviceId - Device to read instead of the default input device
+/// @returns Volume level between 0.0 and 1.0, or null in the compatibility mode if unable to get volume
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn get_input_volume(device_id: Option<String>) -> Outcome<Option<f64>> {
+    Target::Device(input(&device_id)).level(backend::audio().as_ref()).map(Some).into()
+}
+
+/// Mutes or unmutes the microphone
+/// @param mute - Whether to mute (true) or unmute (false) the microphone
+/// @param deviceId - Device to change instead of the default input device
+/// @returns Whether setting the mute state was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_input_mute(mute: bool, device_id: Option<String>) -> Outcome<bool> {
+    backend::audio().set_muted(input(&device_id), mute).map(|()| true).into()
+}
+
+/// Gets whether the microphone is muted
+/// @param deviceId - Device to read instead of the default input device
+/// @returns Whether the microphone is muted, or null in the compatibility mode if unable to get state
+#[napi(ts_return_type = "Promise<boolean | null>")]
+pub async fn get_input_mute(device_id: Option<String>) -> Outcome<Option<bool>> {
+    backend::audio().muted(input(&device_id)).map(Some).into()
+}
+
+/// Gets the volume of each channel of a device
+/// @param deviceId - Device to read instead of the default output device
+/// @returns The channels in the order the device mixes them, with where each one plays
+#[napi(ts_return_type = "Promise<Array<ChannelVolume>>")]
+pub async fn get_channel_volumes(device_id: Option<String>) -> Outcome<Vec<ChannelVolume>> {
+    backend::audio().channel_volumes(output(&device_id)).into()
+}
+
+/// Sets the volume of each channel of a device
+/// @param levels - Volume level between 0.0 and 1.0 for each channel, in the order `getChannelVolumes` gives them
+/// @param deviceId - Device to change instead of the default output device
+/// @returns Whether setting the volumes was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_channel_volumes(levels: Vec<f64>, device_id: Option<String>) -> Outcome<bool> {
+    volume_levels(levels)
+        .and_then(|levels| backend::audio().set_channel_volumes(output(&device_id), &levels))
+        .map(|()| true)
+        .into()
+}
+
+/// Gets the balance between the left and right channels of a device
+/// @param deviceId - Device to read instead of the default output device
+/// @returns Balance between -1.0 (left only) and 1.0 (right only), 0.0 for devices without left and right channels
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn get_balance(device_id: Option<String>) -> Outcome<Option<f64>> {
+    backend::audio()
+        .channel_volumes(output(&device_id))
+        .map(|channels| Some(channels::balance(&channels)))
+        .into()
+}
+
+/// Moves the balance between the left and right channels of a device. The louder side keeps its volume, so the
+/// overall loudness stays the same.
+/// @param balance - Balance between -1.0 (left only) and 1.0 (right only)
+/// @param deviceId - Device to change instead of the default output device
+/// @returns Whether setting the balance was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_balance(balance: f64, device_id: Option<String>) -> Outcome<bool> {
+    let audio = backend::audio();
+    let device = output(&device_id);
+    audio
+        .channel_volumes(device)
+        .and_then(|channels| channels::balanced(&channels, balance))
+        .and_then(|levels| audio.set_channel_volumes(device, &levels))
+        .map(|()| true)
+        .into()
+}
+
+/// Gets the volumes in dB a device can be set to
+/// @param deviceId - Device to read instead of the default output device
+/// @returns The range of the device, or null in the compatibility mode if unable to get it
+#[napi(ts_return_type = "Promise<VolumeRange | null>")]
+pub async fn get_volume_range(device_id: Option<String>) -> Outcome<Option<VolumeRange>> {
+    Target::Device(output(&device_id)).range(backend::audio().as_ref()).map(Some).into()
+}
+
+/// Gets the volume of a device in dB
+/// @param deviceId - Device to read instead of the default output device
+/// @returns The volume in dB, `-Infinity` when silent, or null in the compatibility mode if unable to get volume
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn get_volume_db(device_id: Option<String>) -> Outcome<Option<f64>> {
+    Target::Device(output(&device_id)).db(backend::audio().as_ref()).map(Some).into()
+}
+
+/// Sets the volume of a device in dB
+/// @param db - Volume in dB up to the `maxDb` of `getVolumeRange`. Volumes below `minDb` give the quietest volume
+/// the device has.
+/// @param deviceId - Device to change instead of the default output device
+/// @returns Whether setting the volume was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_volume_db(db: f64, device_id: Option<String>) -> Outcome<bool> {
+    Target::Device(output(&device_id))
+        .set_db(backend::audio().as_ref(), db)
+        .map(|()| true)
+        .into()
+}
+
+/// Turns the volume of a device up or down, like the volume keys do. On devices with a fixed number of volume steps
+/// the volume lands on one of them.
+/// @param direction - `up` or `down`
+/// @param step - How far to move the volume level, defaults to one step of the device or else 0.05
+/// @param deviceId - Device to change instead of the default output device
+/// @returns The new volume level between 0.0 and 1.0, or null in the compatibility mode if unable to change it
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn volume_step(direction: StepDirection, step: Option<f64>, device_id: Option<String>) -> Outcome<Option<f64>> {
+    Target::Device(output(&device_id))
+        .step(backend::audio().as_ref(), direction, step)
+        .map(Some)
+        .into()
+}
+
+/// Lists the output and input audio devices
+/// @param filter - Limits the list to outputs or inputs
+/// @returns The devices, whose ids the volume and mute functions take
+#[napi(ts_return_type = "Promise<Array<AudioDevice>>")]
+pub async fn list_audio_devices(filter: Option<AudioDeviceFilter>) -> Outcome<Vec<AudioDevice>> {
+    let direction = filter.unwrap_or_default().direction;
+    backend::audio().list_devices(direction).into()
+}
+
+/// Makes a device the default output or input, depending on its direction
+/// @param id - Id of the audio device
+/// @returns Whether the default device was changed
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_default_device(id: String) -> Outcome<bool> {
+    backend::audio().set_default_device(&id).map(|()| true).into()
+}
+
+/// Lists the applications currently playing audio
+/// @returns The audio sessions, whose ids the other session functions take
+#[napi(ts_return_type = "Promise<Array<AudioSession>>")]
+pub async fn list_audio_sessions() -> Outcome<Vec<AudioSession>> {
+    backend::audio().list_sessions().into()
+}
+
+/// Sets the volume level of an application
+/// @param id - Id of the audio session
+/// @param level - Volume level between 0.0 and 1.0
+/// @returns Whether setting the volume was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_session_volume(id: String, level: f64) -> Outcome<bool> {
+    volume_level(level)
+        .and_then(|level| Target::Session(&id).set_level(backend::audio().as_ref(), level))
+        .map(|()| true)
+        .into()
+}
+
+/// Gets the volume level of an application
+/// @param id - Id of the audio session
+/// @returns Volume level between 0.0 and 1.0, or null in the compatibility mode if the session was not found
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn get_session_volume(id: String) -> Outcome<Option<f64>> {
+    Target::Session(&id).level(backend::audio().as_ref()).map(Some).into()
+}
+
+/// Sets the mute state of an application
+/// @param id - Id of the audio session
+/// @param mute - Whether to mute (true) or unmute (false) the application
+/// @returns Whether setting the mute state was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_session_mute(id: String, mute: bool) -> Outcome<bool> {
+    backend::audio().set_session_muted(&id, mute).map(|()| true).into()
+}
+
+/// Gets the mute state of an application
+/// @param id - Id of the audio session
+/// @returns Whether the application is muted, or null in the compatibility mode if the session was not found
+#[napi(ts_return_type = "Promise<boolean | null>")]
+pub async fn get_session_mute(id: String) -> Outcome<Option<bool>> {
+    backend::audio().session_muted(&id).map(Some).into()
+}
+
+/// Gets the volume of each channel of an application
+/// @param id - Id of the audio session
+/// @returns The channels of the application, with where each one plays
+#[napi(ts_return_type = "Promise<Array<ChannelVolume>>")]
+pub async fn get_session_channel_volumes(id: String) -> Outcome<Vec<ChannelVolume>> {
+    backend::audio().session_channel_volumes(&id).into()
+}
+
+/// Sets the volume of each channel of an application
+/// @param id - Id of the audio session
+/// @param levels - Volume level between 0.0 and 1.0 for each channel, in the order `getSessionChannelVolumes` gives them
+/// @returns Whether setting the volumes was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_session_channel_volumes(id: String, levels: Vec<f64>) -> Outcome<bool> {
+    volume_levels(levels)
+        .and_then(|levels| backend::audio().set_session_channel_volumes(&id, &levels))
+        .map(|()| true)
+        .into()
+}
+
+/// Gets the balance between the left and right channels of an application
+/// @param id - Id of the audio session
+/// @returns Balance between -1.0 (left only) and 1.0 (right only), or null in the compatibility mode if the session was not found
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn get_session_balance(id: String) -> Outcome<Option<f64>> {
+    backend::audio()
+        .session_channel_volumes(&id)
+        .map(|channels| Some(channels::balance(&channels)))
+        .into()
+}
+
+/// Moves the balance between the left and right channels of an application, keeping its loudness
+/// @param id - Id of the audio session
+/// @param balance - Balance between -1.0 (left only) and 1.0 (right only)
+/// @returns Whether setting the balance was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_session_balance(id: String, balance: f64) -> Outcome<bool> {
+    let audio = backend::audio();
+    audio
+        .session_channel_volumes(&id)
+        .and_then(|channels| channels::balanced(&channels, balance))
+        .and_then(|levels| audio.set_session_channel_volumes(&id, &levels))
+        .map(|()| true)
+        .into()
+}
+
+/// Gets the volumes in dB an application can be set to
+/// @param id - Id of the audio session
+/// @returns The range of the application, or null in the compatibility mode if the session was not found
+#[napi(ts_return_type = "Promise<VolumeRange | null>")]
+pub async fn get_session_volume_range(id: String) -> Outcome<Option<VolumeRange>> {
+    Target::Session(&id).range(backend::audio().as_ref()).map(Some).into()
+}
+
+/// Gets the volume of an application in dB
+/// @param id - Id of the audio session
+/// @returns The volume in dB, `-Infinity` when silent, or null in the compatibility mode if the session was not found
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn get_session_volume_db(id: String) -> Outcome<Option<f64>> {
+    Target::Session(&id).db(backend::audio().as_ref()).map(Some).into()
+}
+
+/// Sets the volume of an application in dB
+/// @param id - Id of the audio session
+/// @param db - Volume in dB up to the `maxDb` of `getSessionVolumeRange`, going down to silence
+/// @returns Whether setting the volume was successful
+#[napi(ts_return_type = "Promise<boolean>")]
+pub async fn set_session_volume_db(id: String, db: f64) -> Outcome<bool> {
+    Target::Session(&id).set_db(backend::audio().as_ref(), db).map(|()| true).into()
+}
+
+/// Turns the volume of an application up or down
+/// @param id - Id of the audio session
+/// @param direction - `up` or `down`
+/// @param step - How far to move the volume level, defaults to 0.05
+/// @returns The new volume level between 0.0 and 1.0, or null in the compatibility mode if unable to change it
+#[napi(ts_return_type = "Promise<number | null>")]
+pub async fn session_volume_step(id: String, direction: StepDirection, step: Option<f64>) -> Outcome<Option<f64>> {
+    Target::Session(&id).step(backend::audio().as_ref(), direction, step).map(Some).into()
+}
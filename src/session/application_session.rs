@@ -1,73 +1,154 @@
 use windows::{
-    Win32::Foundation::BOOL,
-    Win32::Media::Audio::{ISimpleAudioVolume, Endpoints::IAudioEndpointVolume},
-    core::GUID,
+    Win32::Media::Audio::{
+        AudioSessionStateActive, AudioSessionStateExpired, IAudioSessionControl2, IChannelAudioVolume,
+        ISimpleAudioVolume,
+    },
+    core::{Interface, GUID},
 };
-use std::process::exit;
-use super::Session;
+use crate::{
+    channels::check_levels,
+    error::{Error, Result},
+    ChannelPosition, ChannelVolume, VolumeCurve, VolumeRange,
+};
+use super::{take_string, AudioSessionKind, AudioSessionState, Session};
+
+/// The volume of a session is the amplitude its signal is scaled by
+const SCALE: VolumeCurve = VolumeCurve::Linear;
 
 pub struct ApplicationSession {
+    control: IAudioSessionControl2,
     simple_audio_volume: ISimpleAudioVolume,
+    channel_audio_volume: IChannelAudioVolume,
+    id: String,
+    pid: u32,
     name: String,
     guid: GUID,
 }
 
+// SAFETY: Core Audio objects are free-threaded, so their interfaces can be
+// called from any thread
+unsafe impl Send for ApplicationSession {}
+unsafe impl Sync for ApplicationSession {}
+
 impl ApplicationSession {
-    pub fn new(simple_audio_volume: ISimpleAudioVolume, name: String) -> Self {
-        let guid = GUID::new().unwrap_or_else(|err| {
-            eprintln!("ERROR: Couldn't generate GUID {err}");
-            exit(1);
-        });
-        
-        Self {
+    /// Wraps an audio session, named by its display name or else by
+    /// `fallback_name`
+    pub fn new(control: IAudioSessionControl2, fallback_name: String) -> Result<Self> {
+        let guid = GUID::new().map_err(|err| Error::from(err).context("Couldn't generate GUID"))?;
+
+        let (simple_audio_volume, channel_audio_volume, id, pid, display_name) = unsafe {
+            (
+                control.cast::<ISimpleAudioVolume>()?,
+                control.cast::<IChannelAudioVolume>()?,
+                take_string(control.GetSessionInstanceIdentifier()?),
+                control.GetProcessId()?,
+                take_string(control.GetDisplayName()?),
+            )
+        };
+        // Apps that set no display name leave it empty, and some point to a
+        // resource string such as "@%SystemRoot%\System32\AudioSrv.Dll,-202"
+        let name = match display_name.is_empty() || display_name.starts_with('@') {
+            true => fallback_name,
+            false => display_name,
+        };
+
+        Ok(Self {
+            control,
             simple_audio_volume,
+            channel_audio_volume,
+            id,
+            pid,
             name,
             guid,
-        }
+        })
     }
 }
 
 impl Session for ApplicationSession {
-    unsafe fn get_audio_endpoint_volume(&self) -> Option<IAudioEndpointVolume> {
-        None
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn pid(&self) -> Option<u32> {
+        Some(self.pid)
+    }
+
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> AudioSessionKind {
+        AudioSessionKind::Application
+    }
+
+    fn state(&self) -> Result<AudioSessionState> {
+        let state = unsafe { self.control.GetState()? };
+        Ok(if state == AudioSessionStateActive {
+            AudioSessionState::Active
+        } else if state == AudioSessionStateExpired {
+            AudioSessionState::Expired
+        } else {
+            AudioSessionState::Inactive
+        })
     }
 
-    unsafe fn get_name(&self) -> String {
-        self.name.clone()
+    fn volume(&self) -> Result<f64> {
+        Ok(unsafe { self.simple_audio_volume.GetMasterVolume()? } as f64)
     }
 
-    unsafe fn get_volume(&self) -> f32 {
-        self.simple_audio_volume
-            .GetMasterVolume()
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldn't get volume {err}");
-                0.0
-            })
+    fn set_volume(&self, level: f64) -> Result<()> {
+        unsafe { self.simple_audio_volume.SetMasterVolume(level as f32, &self.guid) }.map_err(Error::from)
     }
 
-    unsafe fn set_volume(&self, vol: f32) {
-        self.simple_audio_volume
-            .SetMasterVolume(vol, &self.guid)
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldn't set volume: {err}");
-            });
+    fn muted(&self) -> Result<bool> {
+        Ok(unsafe { self.simple_audio_volume.GetMute()? }.as_bool())
     }
 
-    unsafe fn get_mute(&self) -> bool {
-        self.simple_audio_volume
-            .GetMute()
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldn't get mute {err}");
-                BOOL(0)
-            })
-            .as_bool()
+    fn set_muted(&self, muted: bool) -> Result<()> {
+        unsafe { self.simple_audio_volume.SetMute(muted, &self.guid) }.map_err(Error::from)
     }
 
-    unsafe fn set_mute(&self, mute: bool) {
-        self.simple_audio_volume
-            .SetMute(mute, &self.guid)
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldn't set mute: {err}");
-            });
+    /// The channel volumes of a session scale its master volume, so each
+    /// channel plays at the product of both. The channels follow the default
+    /// layout for their count, as the session does not tell its format.
+    fn channel_volumes(&self) -> Result<Vec<ChannelVolume>> {
+        let master = unsafe { self.simple_audio_volume.GetMasterVolume()? } as f64;
+        let mut channels = vec![0.0; unsafe { self.channel_audio_volume.GetChannelCount()? } as usize];
+        unsafe { self.channel_audio_volume.GetAllVolumes(&mut channels)? };
+
+        Ok(ChannelPosition::layout(channels.len())
+            .into_iter()
+            .zip(channels)
+            .map(|(position, channel)| ChannelVolume { position, volume: master * channel as f64 })
+            .collect())
     }
-} 
\ No newline at end of file
+
+    /// Puts the master volume at the loudest channel and the channels
+    /// relative to it
+    fn set_channel_volumes(&self, levels: &[f64]) -> Result<()> {
+        check_levels(unsafe { self.channel_audio_volume.GetChannelCount()? } as usize, levels)?;
+        let master = levels.iter().copied().fold(0.0, f64::max);
+        let channels: Vec<f32> = levels
+            .iter()
+            .map(|level| if master == 0.0 { 1.0 } else { (level / master) as f32 })
+            .collect();
+
+        unsafe {
+            self.simple_audio_volume.SetMasterVolume(master as f32, &self.guid)?;
+            self.channel_audio_volume.SetAllVolumes(&channels, &self.guid)?;
+        }
+        Ok(())
+    }
+
+    fn volume_range(&self) -> Result<VolumeRange> {
+        Ok(VolumeRange::software(None))
+    }
+
+    fn volume_db(&self) -> Result<f64> {
+        Ok(SCALE.db(self.volume()?, &VolumeRange::software(None)))
+    }
+
+    fn set_volume_db(&self, db: f64) -> Result<()> {
+        self.set_volume(SCALE.level(db, &VolumeRange::software(None)))
+    }
+}
@@ -1,73 +1,202 @@
 use windows::{
-    Win32::Foundation::BOOL,
-    Win32::Media::Audio::Endpoints::IAudioEndpointVolume,
+    Win32::{
+        Media::Audio::{
+            Endpoints::IAudioEndpointVolume, IAudioClient, IMMDevice, DEVICE_STATE_ACTIVE, DEVICE_STATE_NOTPRESENT,
+            WAVEFORMATEX, WAVEFORMATEXTENSIBLE,
+        },
+        System::Com::{CoTaskMemFree, CLSCTX_ALL},
+    },
     core::GUID,
 };
-use std::process::exit;
-use super::Session;
+use crate::{
+    channels::check_levels,
+    error::{Error, Result},
+    ChannelPosition, ChannelVolume, VolumeRange,
+};
+use super::{take_string, AudioSessionKind, AudioSessionState, Session};
+
+/// Format tag of a mix format that carries a channel mask
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Speaker positions in the order of the bits of a channel mask. The
+/// height speakers after them are reported as other positions.
+const SPEAKERS: [ChannelPosition; 11] = [
+    ChannelPosition::FrontLeft,
+    ChannelPosition::FrontRight,
+    ChannelPosition::FrontCenter,
+    ChannelPosition::Lfe,
+    ChannelPosition::RearLeft,
+    ChannelPosition::RearRight,
+    ChannelPosition::FrontLeftOfCenter,
+    ChannelPosition::FrontRightOfCenter,
+    ChannelPosition::RearCenter,
+    ChannelPosition::SideLeft,
+    ChannelPosition::SideRight,
+];
+
+/// Reads the channel mask of the format the audio engine mixes an endpoint
+/// at, which only active endpoints have
+fn channel_mask(device: &IMMDevice) -> Option<u32> {
+    unsafe {
+        let client: IAudioClient = device.Activate(CLSCTX_ALL, None).ok()?;
+        let format = client.GetMixFormat().ok().filter(|format| !format.is_null())?;
+        let header: WAVEFORMATEX = format.read_unaligned();
+        let mask = (header.wFormatTag == WAVE_FORMAT_EXTENSIBLE)
+            .then(|| (format as *const WAVEFORMATEXTENSIBLE).read_unaligned().dwChannelMask);
+        CoTaskMemFree(Some(format as *const _));
+        mask
+    }
+}
+
+/// Positions of the `count` channels of an endpoint, from its channel mask
+/// when it matches the channel count
+fn channel_positions(device: &IMMDevice, count: usize) -> Vec<ChannelPosition> {
+    let positions: Option<Vec<ChannelPosition>> = channel_mask(device).map(|mask| {
+        (0..u32::BITS)
+            .filter(|bit| mask & (1 << bit) != 0)
+            .map(|bit| SPEAKERS.get(bit as usize).copied().unwrap_or(ChannelPosition::Other))
+            .collect()
+    });
+    positions
+        .filter(|positions| positions.len() == count)
+        .unwrap_or_else(|| ChannelPosition::layout(count))
+}
+
+/// Reads the level of each channel of an endpoint
+pub(crate) fn endpoint_channel_volumes(device: &IMMDevice, volume: &IAudioEndpointVolume) -> Result<Vec<ChannelVolume>> {
+    let count = unsafe { volume.GetChannelCount()? };
+    channel_positions(device, count as usize)
+        .into_iter()
+        .zip(0..count)
+        .map(|(position, index)| {
+            let level = unsafe { volume.GetChannelVolumeLevelScalar(index)? };
+            Ok(ChannelVolume { position, volume: level as f64 })
+        })
+        .collect()
+}
+
+/// Sets the level of each channel of an endpoint
+pub(crate) fn set_endpoint_channel_volumes(volume: &IAudioEndpointVolume, levels: &[f64], context: &GUID) -> Result<()> {
+    check_levels(unsafe { volume.GetChannelCount()? } as usize, levels)?;
+    for (index, level) in levels.iter().enumerate() {
+        unsafe { volume.SetChannelVolumeLevelScalar(index as u32, *level as f32, context)? };
+    }
+    Ok(())
+}
+
+/// Reads the dB range and the number of volume steps of an endpoint
+pub(crate) fn endpoint_volume_range(volume: &IAudioEndpointVolume) -> Result<VolumeRange> {
+    let (mut min_db, mut max_db, mut increment_db) = (0.0, 0.0, 0.0);
+    let (mut step, mut steps) = (0, 0);
+    unsafe {
+        volume.GetVolumeRange(&mut min_db, &mut max_db, &mut increment_db)?;
+        volume.GetVolumeStepInfo(&mut step, &mut steps)?;
+    }
+    Ok(VolumeRange {
+        min_db: min_db as f64,
+        max_db: max_db as f64,
+        increment_db: Some(increment_db as f64),
+        steps: Some(steps),
+    })
+}
+
+/// Sets the volume of an endpoint in dB, kept within its range as the
+/// endpoint refuses anything outside it
+pub(crate) fn set_endpoint_volume_db(volume: &IAudioEndpointVolume, db: f64, context: &GUID) -> Result<()> {
+    let range = endpoint_volume_range(volume)?;
+    let db = db.clamp(range.min_db, range.max_db);
+    unsafe { volume.SetMasterVolumeLevel(db as f32, context) }.map_err(Error::from)
+}
 
 pub struct EndPointSession {
     simple_audio_volume: IAudioEndpointVolume,
+    device: IMMDevice,
+    id: String,
     name: String,
     guid: GUID
 }
 
+// SAFETY: Core Audio objects are free-threaded, so their interfaces can be
+// called from any thread
+unsafe impl Send for EndPointSession {}
+unsafe impl Sync for EndPointSession {}
+
 impl EndPointSession {
-    pub fn new(simple_audio_volume: IAudioEndpointVolume, name: String) -> Self {
-        let guid = GUID::new().unwrap_or_else(|err| {
-            eprintln!("ERROR: Couldn't generate GUID {err}");
-            exit(1);
-        });
+    /// Wraps the volume control of `device`, whose endpoint id becomes the id
+    /// of the session
+    pub fn new(device: &IMMDevice, simple_audio_volume: IAudioEndpointVolume, name: String) -> Result<Self> {
+        let guid = GUID::new().map_err(|err| Error::from(err).context("Couldn't generate GUID"))?;
+        let id = unsafe { take_string(device.GetId()?) };
 
-        Self {
+        Ok(Self {
             simple_audio_volume,
+            device: device.clone(),
+            id,
             name,
             guid
-        }
+        })
     }
 }
 
 impl Session for EndPointSession {
-    unsafe fn get_audio_endpoint_volume(&self) -> Option<IAudioEndpointVolume> {
-        Some(self.simple_audio_volume.clone())
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn pid(&self) -> Option<u32> {
+        None
+    }
+
+    fn display_name(&self) -> &str {
+        &self.name
+    }
+
+    fn kind(&self) -> AudioSessionKind {
+        AudioSessionKind::Endpoint
+    }
+
+    fn state(&self) -> Result<AudioSessionState> {
+        Ok(match unsafe { self.device.GetState()? } {
+            DEVICE_STATE_ACTIVE => AudioSessionState::Active,
+            DEVICE_STATE_NOTPRESENT => AudioSessionState::Expired,
+            // Disabled or unplugged
+            _ => AudioSessionState::Inactive,
+        })
     }
 
-    unsafe fn get_name(&self) -> String {
-        self.name.clone()
+    fn volume(&self) -> Result<f64> {
+        Ok(unsafe { self.simple_audio_volume.GetMasterVolumeLevelScalar()? } as f64)
     }
 
-    unsafe fn get_volume(&self) -> f32 {
-        self.simple_audio_volume
-            .GetMasterVolumeLevelScalar()
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldn't get volume {err}");
-                0.0
-            })
+    fn set_volume(&self, level: f64) -> Result<()> {
+        unsafe { self.simple_audio_volume.SetMasterVolumeLevelScalar(level as f32, &self.guid) }.map_err(Error::from)
     }
 
-    unsafe fn set_volume(&self, vol: f32) {
-        self.simple_audio_volume
-            .SetMasterVolumeLevelScalar(vol, &self.guid)
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldn't set volume: {err}");
-            });
+    fn muted(&self) -> Result<bool> {
+        Ok(unsafe { self.simple_audio_volume.GetMute()? }.as_bool())
     }
 
-    unsafe fn get_mute(&self) -> bool {
-        self.simple_audio_volume
-            .GetMute()
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldn't get mute {err}");
-                BOOL(0)
-            })
-            .as_bool()
+    fn set_muted(&self, muted: bool) -> Result<()> {
+        unsafe { self.simple_audio_volume.SetMute(muted, &self.guid) }.map_err(Error::from)
     }
 
-    unsafe fn set_mute(&self, mute: bool) {
-        self.simple_audio_volume
-            .SetMute(mute, &self.guid)
-            .unwrap_or_else(|err| {
-                eprintln!("ERROR: Couldn't set mute: {err}");
-            });
+    fn channel_volumes(&self) -> Result<Vec<ChannelVolume>> {
+        endpoint_channel_volumes(&self.device, &self.simple_audio_volume)
     }
-} 
\ No newline at end of file
+
+    fn set_channel_volumes(&self, levels: &[f64]) -> Result<()> {
+        set_endpoint_channel_volumes(&self.simple_audio_volume, levels, &self.guid)
+    }
+
+    fn volume_range(&self) -> Result<VolumeRange> {
+        endpoint_volume_range(&self.simple_audio_volume)
+    }
+
+    fn volume_db(&self) -> Result<f64> {
+        Ok(unsafe { self.simple_audio_volume.GetMasterVolumeLevel()? } as f64)
+    }
+
+    fn set_volume_db(&self, db: f64) -> Result<()> {
+        set_endpoint_volume_db(&self.simple_audio_volume, db, &self.guid)
+    }
+}
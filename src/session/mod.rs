@@ -1,16 +1,81 @@
+#[cfg(windows)]
 mod endpoint_session;
+#[cfg(windows)]
 mod application_session;
 
+#[cfg(windows)]
 pub use endpoint_session::EndPointSession;
+#[cfg(windows)]
 pub use application_session::ApplicationSession;
+#[cfg(windows)]
+pub(crate) use endpoint_session::{
+    endpoint_channel_volumes, endpoint_volume_range, set_endpoint_channel_volumes, set_endpoint_volume_db,
+};
 
-use windows::Win32::Media::Audio::Endpoints::IAudioEndpointVolume;
+use crate::{error::Result, AudioSession, ChannelVolume, VolumeRange};
 
-pub trait Session {
-    unsafe fn get_audio_endpoint_volume(&self) -> Option<IAudioEndpointVolume>;
-    unsafe fn get_name(&self) -> String;
-    unsafe fn get_volume(&self) -> f32;
-    unsafe fn set_volume(&self, vol: f32);
-    unsafe fn get_mute(&self) -> bool;
-    unsafe fn set_mute(&self, mute: bool);
-} 
\ No newline at end of file
+/// Whether a session controls a whole device or a single application
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioSessionKind {
+    Endpoint,
+    Application,
+}
+
+/// Whether a session is playing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioSessionState {
+    /// Audio is playing
+    Active,
+    /// The session is there but plays nothing
+    Inactive,
+    /// The session went away, so changing it no longer has an effect
+    Expired,
+}
+
+/// Volume and mute of a device or an application, whichever platform it
+/// comes from
+pub trait Session: Send + Sync {
+    /// Id that stays the same for the life of the session, and tells apart
+    /// sessions of the same application
+    fn id(&self) -> &str;
+    /// Process playing the audio, if the session belongs to a single one
+    fn pid(&self) -> Option<u32>;
+    /// Name to show for the session
+    fn display_name(&self) -> &str;
+    fn kind(&self) -> AudioSessionKind;
+    fn state(&self) -> Result<AudioSessionState>;
+    /// Volume level between 0.0 and 1.0
+    fn volume(&self) -> Result<f64>;
+    fn set_volume(&self, level: f64) -> Result<()>;
+    fn muted(&self) -> Result<bool>;
+    fn set_muted(&self, muted: bool) -> Result<()>;
+    /// Volume of each channel, the loudest of which plays at [`Session::volume`]
+    fn channel_volumes(&self) -> Result<Vec<ChannelVolume>>;
+    /// Sets the volume of each channel, given in the order of
+    /// [`Session::channel_volumes`]
+    fn set_channel_volumes(&self, levels: &[f64]) -> Result<()>;
+    /// Volumes in dB the session can be set to
+    fn volume_range(&self) -> Result<VolumeRange>;
+    /// Volume in dB, negative infinity when silent
+    fn volume_db(&self) -> Result<f64>;
+    /// Sets the volume in dB, going no lower than the session can
+    fn set_volume_db(&self, db: f64) -> Result<()>;
+}
+
+impl<S: Session + ?Sized> From<&S> for AudioSession {
+    fn from(session: &S) -> Self {
+        AudioSession {
+            id: session.id().to_string(),
+            name: session.display_name().to_string(),
+            pid: session.pid(),
+        }
+    }
+}
+
+/// Reads a string Core Audio allocated for the caller, and frees it
+#[cfg(windows)]
+pub(crate) unsafe fn take_string(text: windows::core::PWSTR) -> String {
+    let string = unsafe { text.to_string() }.unwrap_or_default();
+    unsafe { windows::Win32::System::Com::CoTaskMemFree(Some(text.0 as *const _)) };
+    string
+}